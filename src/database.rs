@@ -1,2 +1,13 @@
 pub mod container;
 pub mod user;
+pub mod graphql;
+pub mod websocket;
+pub mod example;
+pub mod script;
+pub mod history;
+pub mod environment;
+pub mod path;
+pub mod trash;
+pub mod undo;
+pub mod selection;
+pub mod storage;
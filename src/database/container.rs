@@ -1,31 +1,54 @@
+use std::collections::HashMap;
 use std::str::FromStr;
 use crate::util::dbhandler::*;
 
 use std::fmt;
 
 use sqlite::*;
+use crate::error::{Error, Result};
 use curl::easy::Easy;
 use super::user::*;
+use super::script::{copy_scripts, Owner};
 
+/// HTTP method of a Request. Methods outside the standard ones, such as the
+/// WebDAV `PROPFIND` or `MKCOL`, are kept as `Custom`. Variants are named as
+/// sent.
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, PartialEq, Clone)]
 pub enum Methods {
     GET,
+    HEAD,
     POST,
-    DELETE,
-    MODIFY,
     PUT,
+    PATCH,
+    DELETE,
+    OPTIONS,
+    TRACE,
+    CONNECT,
+    Custom(String),
 }
 
+/// The standard methods, in the order they are offered.
+pub const METHODS : &[&str] = &["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS", "TRACE", "CONNECT"];
+
 impl FromStr for Methods {
     type Err = ();
     fn from_str(input : &str) -> std::result::Result<Methods, Self::Err> {
         match input {
             "GET" => Ok(Methods::GET),
+            "HEAD" => Ok(Methods::HEAD),
             "POST" => Ok(Methods::POST),
-            "DELETE" => Ok(Methods::DELETE),
-            "MODIFY" => Ok(Methods::MODIFY),
             "PUT" => Ok(Methods::PUT),
-            _      => Err(()),
+            "PATCH" => Ok(Methods::PATCH),
+            "DELETE" => Ok(Methods::DELETE),
+            "OPTIONS" => Ok(Methods::OPTIONS),
+            "TRACE" => Ok(Methods::TRACE),
+            "CONNECT" => Ok(Methods::CONNECT),
+            // Any other token of RFC 7230 is a method.
+            _ if !input.is_empty() && input.chars().all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)) => {
+                Ok(Methods::Custom(input.to_owned()))
+            }
+            _ => Err(()),
         }
     }
 }
@@ -34,7 +57,89 @@ impl FromStr for Methods {
 impl fmt::Display for Methods {
 
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            Methods::Custom(method) => f.write_str(method),
+            method => write!(f, "{:?}", method),
+        }
+    }
+}
+
+/// How the body of a Request is interpreted when it is sent.
+///
+/// For `Form` and `Multipart` the body holds one `key=value` pair per line,
+/// multipart values starting with `@` are file paths. For `Binary` the body is
+/// the path of the file to stream.
+#[derive(Debug, PartialEq, Clone)]
+pub enum BodyMode {
+    None,
+    Raw,
+    Json,
+    Form,
+    Multipart,
+    Binary,
+}
+
+impl BodyMode {
+
+    /// The mode after this one, used to cycle through modes in the UI.
+    pub fn next(&self) -> BodyMode {
+        match self {
+            BodyMode::None => BodyMode::Raw,
+            BodyMode::Raw => BodyMode::Json,
+            BodyMode::Json => BodyMode::Form,
+            BodyMode::Form => BodyMode::Multipart,
+            BodyMode::Multipart => BodyMode::Binary,
+            BodyMode::Binary => BodyMode::None,
+        }
+    }
+}
+
+impl FromStr for BodyMode {
+    type Err = ();
+    fn from_str(input : &str) -> std::result::Result<BodyMode, Self::Err> {
+        match input {
+            "NONE" => Ok(BodyMode::None),
+            "RAW" => Ok(BodyMode::Raw),
+            "JSON" => Ok(BodyMode::Json),
+            "FORM" => Ok(BodyMode::Form),
+            "MULTIPART" => Ok(BodyMode::Multipart),
+            "BINARY" => Ok(BodyMode::Binary),
+            _      => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for BodyMode {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&format!("{:?}", self).to_uppercase())
+    }
+}
+
+/// The protocol a Request is sent with.
+#[derive(Debug, PartialEq, Clone)]
+pub enum RequestKind {
+    Http,
+    Graphql,
+    Websocket,
+}
+
+impl FromStr for RequestKind {
+    type Err = ();
+    fn from_str(input : &str) -> std::result::Result<RequestKind, Self::Err> {
+        match input {
+            "HTTP" => Ok(RequestKind::Http),
+            "GRAPHQL" => Ok(RequestKind::Graphql),
+            "WEBSOCKET" => Ok(RequestKind::Websocket),
+            _      => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for RequestKind {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&format!("{:?}", self).to_uppercase())
     }
 }
 
@@ -53,6 +158,15 @@ pub struct Collection {
     pub queries : Vec<Request>,
 }
 
+/// A folder of a Collection, holding requests and other folders.
+#[derive(Clone)]
+pub struct Folder {
+    pub id : i64,
+    pub name : String,
+    /// The folder it is in, at the root of the collection otherwise.
+    pub id_parent : Option<i64>,
+}
+
 pub trait Container {
 
     fn new(id :i64, name : String) -> Self;
@@ -91,14 +205,24 @@ pub trait Protocol {
 
 }
 
+/// A saved request. For `RequestKind::Graphql` the body is the query and
+/// `variables` holds its JSON variables, for `RequestKind::Websocket` the body
+/// is the next message to send.
 #[derive(Clone)]
 pub struct Request {
     pub id : i64,
     pub name : String,
+    pub kind : RequestKind,
     pub method : Methods,
     pub url : String,
     pub params : String,
+    pub body_mode : BodyMode,
     pub body : String,
+    pub headers : String,
+    pub variables : String,
+    pub id_collection : i64,
+    /// The folder of the collection it is in, at its root otherwise.
+    pub id_folder : Option<i64>,
 }
 
 impl Protocol for Request {
@@ -111,10 +235,16 @@ impl Protocol for Request {
                body : String) -> Request {
         Request { id : id,
         name : name ,
+        kind : RequestKind::Http,
         method : method,
         url : url,
         params : params,
-        body : body}
+        body_mode : BodyMode::None,
+        body : body,
+        headers : String::new(),
+        variables : String::new(),
+        id_collection : 0,
+        id_folder : None}
     }
 
     fn name(&self) -> String {
@@ -128,42 +258,31 @@ impl Protocol for Request {
 /// params : 
 /// name - the name of the workspace that will be created in the database.
 /// db - a database object.
+///
+/// Returns the id of the workspace.
 pub fn create_workspace(
     user : &User,
     name : &str,
-    db : &Database) -> Result<()> {
+    db : &Database) -> Result<i64> {
 
     //This methods works and is not vulnerable to SQL injections because of the vec! I think.
     //Also doesn't work without a cursor and I don't get why.
-    let mut statement = db.connection.prepare("INSERT INTO Workspace(name) VALUES (:name);").unwrap();
+    let mut statement = db.connection.prepare("INSERT INTO Workspace(name) VALUES (:name);")?;
     let mut cursor = statement.into_cursor();
     cursor.bind_by_name(vec![(":name", Value::String(name.to_owned()))])?;
     cursor.next()?;
 
-    // Need to get the id of the workspace that's just been created.
-    // 
-    statement = db.connection.prepare("SELECT rowid FROM Workspace WHERE name = :name;").unwrap();
-    cursor = statement.into_cursor();
-    cursor.bind_by_name(vec![(":name", Value::String(name.to_owned()))])?;
-    match cursor.next().unwrap() {
-        Some(row) => {
-            //get the id of the last created Workspace.
-            let workspace_id = row[0].as_integer().unwrap();
-
-            // Join table insert statement
-            statement = db.connection.prepare("INSERT INTO User_Workspace(id_user, id_workspace) VALUES (:id_user, :id_workspace);")?;
-            let mut cursor = statement.into_cursor();
-            cursor.bind_by_name(vec![(":id_user" ,Value::Integer(user.id.into())),
-            (":id_workspace", Value::Integer(workspace_id.into()))])?;
-            cursor.next()?;
-            Ok(())
-        }
-        None => Err(Error { 
-            code : Some(1),
-            message : Some(String::from("Something went wrong, User_Workspace entry not created.")),
-        })
+    // Need to get the id of the workspace that's just been created, its name
+    // may be taken by other workspaces.
+    let workspace_id = last_insert_id(db)?;
 
-    }
+    // Join table insert statement
+    statement = db.connection.prepare("INSERT INTO User_Workspace(id_user, id_workspace) VALUES (:id_user, :id_workspace);")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id_user" ,Value::Integer(user.id.into())),
+    (":id_workspace", Value::Integer(workspace_id.into()))])?;
+    cursor.next()?;
+    Ok(workspace_id)
 
 
 
@@ -179,10 +298,12 @@ pub fn create_workspace(
 /// name - the name of the collection that will be created in the database.
 /// workspace_id : the workspace id this collection will be attached to.
 /// db - a database object.
+///
+/// Returns the id of the collection.
 pub fn create_collection(
     name : &str,
     workspace_id : i64,
-    db : &Database) -> Result<()> { 
+    db : &Database) -> Result<i64> { 
 
     let mut statement = db.connection.prepare("INSERT INTO Collection(name, id_workspace) VALUES (:name, :id_workspace);")?;
     let mut cursor = statement.into_cursor();
@@ -190,7 +311,7 @@ pub fn create_collection(
     (":id_workspace", Value::Integer(workspace_id.into()))
     ])?;
     cursor.next()?;
-    Ok(())
+    last_insert_id(db)
 
 }
 
@@ -200,94 +321,504 @@ pub fn create_collection(
 ///
 /// name - the name of the request. 
 /// id_collection - the collection the request will be attached to. 
+/// id_folder - the folder of the collection to put it in, if any.
 /// method - the HTTP method (GET, POST ...).
 /// url - the url of the request. 
 /// db - a database object.
+///
+/// Returns the id of the request.
 pub fn create_request(
     name : &str,
     id_collection : i64,
+    id_folder : Option<i64>,
     method : &str,
     url : &str,
-    db : &Database) -> Result<()> {
+    db : &Database) -> Result<i64> {
 
-    let mut statement = db.connection.prepare("INSERT INTO Request(name, id_collection, method, url) VALUES (:name, :id_collection, :method, :url);")?;
+    let mut statement = db.connection.prepare("INSERT INTO Request(name, id_collection, id_folder, method, url) VALUES (:name, :id_collection, :id_folder, :method, :url);")?;
     let mut cursor = statement.into_cursor();
     cursor.bind_by_name(vec![(":name", Value::String(name.to_owned())),
     (":id_collection", Value::Integer(id_collection.into())),
+    (":id_folder", optional_id(id_folder)),
     (":method", Value::String(method.into())),
     (":url", Value::String(url.into()))
     ])?;
     cursor.next()?;
-    Ok(())
+    last_insert_id(db)
 }
 
-/// Delete a workspace from it's name.
+/// Creates a new GraphQL Request for a Collection. It is sent as a POST with a
+/// JSON envelope holding the query and its variables.
 ///
-/// * `name` - &str of the workspace to delete.
+/// * `name` - the name of the request.
+/// * `id_collection` - the collection the request will be attached to.
+/// * `id_folder` - the folder of the collection to put it in, if any.
+/// * `url` - the url of the GraphQL endpoint.
 /// * `db` - Database to work on.
-pub fn delete_workspace(
+///
+/// Returns the id of the request.
+pub fn create_graphql_request(
     name : &str,
-    db : &Database,) -> Result<()> {
+    id_collection : i64,
+    id_folder : Option<i64>,
+    url : &str,
+    db : &Database) -> Result<i64> {
 
-    let mut statement = db.connection.prepare("DELETE FROM User_Workspace 
-                                              WHERE id_workspace IN (
-                                                  SELECT id_workspace FROM User_Workspace uw
-                                                  INNER JOIN Workspace w ON w.id = uw.id_workspace
-                                                  WHERE w.name = :name)")?;
+    let statement = db.connection.prepare("INSERT INTO Request(name, id_collection, id_folder, method, url, body_mode, kind)
+                                           VALUES (:name, :id_collection, :id_folder, 'POST', :url, 'JSON', 'GRAPHQL');")?;
     let mut cursor = statement.into_cursor();
     cursor.bind_by_name(vec![(":name", Value::String(name.to_owned())),
-    (":name", Value::String(name.into()))
+    (":id_collection", Value::Integer(id_collection)),
+    (":id_folder", optional_id(id_folder)),
+    (":url", Value::String(url.into()))
     ])?;
     cursor.next()?;
+    last_insert_id(db)
+}
+
+/// Creates a new WebSocket Request for a Collection.
+///
+/// * `name` - the name of the request.
+/// * `id_collection` - the collection the request will be attached to.
+/// * `id_folder` - the folder of the collection to put it in, if any.
+/// * `url` - the `ws://` or `wss://` url to connect to.
+/// * `db` - Database to work on.
+///
+/// Returns the id of the request.
+pub fn create_websocket_request(
+    name : &str,
+    id_collection : i64,
+    id_folder : Option<i64>,
+    url : &str,
+    db : &Database) -> Result<i64> {
 
-    statement = db.connection.prepare("DELETE FROM Workspace WHERE name = :name;")?;
-    cursor = statement.into_cursor();
+    let statement = db.connection.prepare("INSERT INTO Request(name, id_collection, id_folder, method, url, body_mode, kind)
+                                           VALUES (:name, :id_collection, :id_folder, 'GET', :url, 'RAW', 'WEBSOCKET');")?;
+    let mut cursor = statement.into_cursor();
     cursor.bind_by_name(vec![(":name", Value::String(name.to_owned())),
-    (":name", Value::String(name.into()))
+    (":id_collection", Value::Integer(id_collection)),
+    (":id_folder", optional_id(id_folder)),
+    (":url", Value::String(url.into()))
+    ])?;
+    cursor.next()?;
+    last_insert_id(db)
+}
+
+/// Sets the JSON variables sent along with a GraphQL Request's query.
+///
+/// * `id` - the i64 id of the request.
+/// * `variables` - the variables as a JSON object.
+/// * `db` - Database to work on.
+pub fn update_request_variables(
+    id : i64,
+    variables : &str,
+    db : &Database) -> Result<()> {
+
+    let statement = db.connection.prepare("UPDATE Request SET variables = :variables WHERE id = :id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":variables", Value::String(variables.to_owned())),
+    (":id", Value::Integer(id)),
     ])?;
     cursor.next()?;
     Ok(())
+}
 
+/// Sets the body of a Request along with the way it is sent.
+///
+/// * `id` - the i64 id of the request.
+/// * `body_mode` - how the body is interpreted.
+/// * `body` - raw body, key/value lines or file path depending on the mode.
+/// * `db` - Database to work on.
+pub fn update_request_body(
+    id : i64,
+    body_mode : &BodyMode,
+    body : &str,
+    db : &Database) -> Result<()> {
+
+    let statement = db.connection.prepare("UPDATE Request SET body_mode = :body_mode, body = :body WHERE id = :id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":body_mode", Value::String(body_mode.to_string())),
+    (":body", Value::String(body.to_owned())),
+    (":id", Value::Integer(id)),
+    ])?;
+    cursor.next()?;
+    Ok(())
 }
 
-/// Delete a collection from it's name.
+/// Sets the headers of a Request, one `Name: value` per line.
 ///
-/// * `name` - &str of the collection to delete.
+/// * `id` - the i64 id of the request.
+/// * `headers` - the header lines.
 /// * `db` - Database to work on.
-pub fn delete_collection(
+pub fn update_request_headers(
+    id : i64,
+    headers : &str,
+    db : &Database) -> Result<()> {
+
+    let statement = db.connection.prepare("UPDATE Request SET headers = :headers WHERE id = :id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":headers", Value::String(headers.to_owned())),
+    (":id", Value::Integer(id)),
+    ])?;
+    cursor.next()?;
+    Ok(())
+}
+
+/// Renames a Workspace.
+///
+/// * `id` - the i64 id of the workspace.
+/// * `name` - its new name.
+/// * `db` - Database to work on.
+pub fn rename_workspace(
+    id : i64,
     name : &str,
-    db : &Database,) -> Result<()> {
+    db : &Database) -> Result<()> {
 
-    let mut statement = db.connection.prepare("DELETE FROM Collection WHERE name = :name;")?;
+    let statement = db.connection.prepare("UPDATE Workspace SET name = :name WHERE id = :id;")?;
     let mut cursor = statement.into_cursor();
     cursor.bind_by_name(vec![(":name", Value::String(name.to_owned())),
-    (":name", Value::String(name.into()))
+    (":id", Value::Integer(id)),
     ])?;
     cursor.next()?;
     Ok(())
+}
+
+/// Renames a Collection.
+///
+/// * `id` - the i64 id of the collection.
+/// * `name` - its new name.
+/// * `db` - Database to work on.
+pub fn rename_collection(
+    id : i64,
+    name : &str,
+    db : &Database) -> Result<()> {
 
+    let statement = db.connection.prepare("UPDATE Collection SET name = :name WHERE id = :id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":name", Value::String(name.to_owned())),
+    (":id", Value::Integer(id)),
+    ])?;
+    cursor.next()?;
+    Ok(())
 }
 
-/// Delete a collection from it's name.
+/// Renames a Request.
 ///
-/// * `name` - &str of the collection to delete.
+/// * `id` - the i64 id of the request.
+/// * `name` - its new name.
 /// * `db` - Database to work on.
-pub fn delete_request(
+pub fn rename_request(
+    id : i64,
     name : &str,
-    db : &Database,) -> Result<()> {
+    db : &Database) -> Result<()> {
 
-    let mut statement = db.connection.prepare("DELETE FROM Request WHERE name = :name;")?;
+    let statement = db.connection.prepare("UPDATE Request SET name = :name WHERE id = :id;")?;
     let mut cursor = statement.into_cursor();
     cursor.bind_by_name(vec![(":name", Value::String(name.to_owned())),
-    (":name", Value::String(name.into()))
+    (":id", Value::Integer(id)),
     ])?;
     cursor.next()?;
     Ok(())
+}
 
+/// Moves a Request to another Collection or folder.
+///
+/// * `id` - the i64 id of the request.
+/// * `id_collection` - the collection it goes to.
+/// * `id_folder` - the folder of that collection, its root if None.
+/// * `db` - Database to work on.
+pub fn move_request(
+    id : i64,
+    id_collection : i64,
+    id_folder : Option<i64>,
+    db : &Database) -> Result<()> {
+
+    let statement = db.connection.prepare("UPDATE Request SET id_collection = :id_collection, id_folder = :id_folder WHERE id = :id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id_collection", Value::Integer(id_collection)),
+    (":id_folder", optional_id(id_folder)),
+    (":id", Value::Integer(id)),
+    ])?;
+    cursor.next()?;
+    Ok(())
 }
 
+/// Moves a Collection, with its folders and requests, to another Workspace.
+///
+/// * `id` - the i64 id of the collection.
+/// * `id_workspace` - the workspace it goes to.
+/// * `db` - Database to work on.
+pub fn move_collection(
+    id : i64,
+    id_workspace : i64,
+    db : &Database) -> Result<()> {
 
-/// Fetches all workspaces from a user's id. 
+    let statement = db.connection.prepare("UPDATE Collection SET id_workspace = :id_workspace WHERE id = :id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id_workspace", Value::Integer(id_workspace)),
+    (":id", Value::Integer(id)),
+    ])?;
+    cursor.next()?;
+    Ok(())
+}
+
+/// An optional id as a nullable column value.
+fn optional_id(id : Option<i64>) -> Value {
+    id.map(Value::Integer).unwrap_or(Value::Null)
+}
+
+/// Copies a Request, with its scripts and examples, into a Collection.
+///
+/// * `id` - the i64 id of the request to copy.
+/// * `name` - the name of the copy.
+/// * `id_collection` - the collection the copy goes to.
+/// * `id_folder` - the folder of that collection, its root if None.
+/// * `db` - Database to work on.
+///
+/// Returns the id of the copy.
+pub fn duplicate_request(
+    id : i64,
+    name : &str,
+    id_collection : i64,
+    id_folder : Option<i64>,
+    db : &Database) -> Result<i64> {
+
+    let statement = db.connection.prepare("INSERT INTO Request(name, method, url, params, body, id_collection, body_mode, headers, kind, variables, id_folder)
+                                           SELECT :name, method, url, params, body, :id_collection, body_mode, headers, kind, variables, :id_folder
+                                           FROM Request WHERE id = :id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":name", Value::String(name.to_owned())),
+    (":id_collection", Value::Integer(id_collection)),
+    (":id_folder", optional_id(id_folder)),
+    (":id", Value::Integer(id)),
+    ])?;
+    cursor.next()?;
+    let id_copy = last_insert_id(db)?;
+
+    copy_scripts(Owner::Request, id, id_copy, db)?;
+    let statement = db.connection.prepare("INSERT INTO Example(id_request, name, status, headers, body, latency)
+                                           SELECT :id_copy, name, status, headers, body, latency FROM Example
+                                           WHERE id_request = :id ORDER BY id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id_copy", Value::Integer(id_copy)),
+    (":id", Value::Integer(id)),
+    ])?;
+    cursor.next()?;
+    Ok(id_copy)
+}
+
+/// Copies a Collection with its folders, requests and scripts into a
+/// Workspace.
+///
+/// * `id` - the i64 id of the collection to copy.
+/// * `name` - the name of the copy.
+/// * `id_workspace` - the workspace the copy goes to.
+/// * `db` - Database to work on.
+///
+/// Returns the id of the copy.
+pub fn duplicate_collection(
+    id : i64,
+    name : &str,
+    id_workspace : i64,
+    db : &Database) -> Result<i64> {
+
+    transaction(db, || {
+        let id_copy = create_collection(name, id_workspace, db)?;
+        copy_scripts(Owner::Collection, id, id_copy, db)?;
+
+        // Parents are listed before their folders, so their copy already exists.
+        let mut folders : HashMap<i64, i64> = HashMap::new();
+        for folder in get_all_folders(id, db)? {
+            let id_parent = folder.id_parent.and_then(|id_parent| folders.get(&id_parent).copied());
+            folders.insert(folder.id, create_folder(&folder.name, id_copy, id_parent, db)?);
+        }
+        for request in get_all_requests(id, db)? {
+            let id_folder = request.id_folder.and_then(|id_folder| folders.get(&id_folder).copied());
+            duplicate_request(request.id, &request.name, id_copy, id_folder, db)?;
+        }
+        Ok(id_copy)
+    })
+}
+
+/// Creates a Folder in a Collection.
+///
+/// * `name` - the name of the folder.
+/// * `id_collection` - the collection it belongs to.
+/// * `id_parent` - the folder it is in, the root of the collection if None.
+/// * `db` - Database to work on.
+///
+/// Returns the id of the folder.
+pub fn create_folder(
+    name : &str,
+    id_collection : i64,
+    id_parent : Option<i64>,
+    db : &Database) -> Result<i64> {
+
+    let statement = db.connection.prepare("INSERT INTO Folder(name, id_collection, id_parent)
+                                           VALUES (:name, :id_collection, :id_parent);")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":name", Value::String(name.to_owned())),
+    (":id_collection", Value::Integer(id_collection)),
+    (":id_parent", optional_id(id_parent)),
+    ])?;
+    cursor.next()?;
+    last_insert_id(db)
+}
+
+/// Fetches the folders of a Collection, each one after its parent. Folders in
+/// the trash are left out with what they hold.
+///
+/// * `id_collection` - the collection to look in.
+/// * `db` - Database to work on.
+pub fn get_all_folders(
+    id_collection : i64,
+    db : &Database) -> Result<Vec<Folder>> {
+
+    let mut folders = vec![];
+    let statement = db.connection.prepare("WITH RECURSIVE tree(id, depth) AS (
+                                               SELECT id, 0 FROM Folder WHERE id_collection = :id_collection AND id_parent IS NULL AND deleted IS NULL
+                                               UNION ALL
+                                               SELECT f.id, tree.depth + 1 FROM Folder f INNER JOIN tree ON f.id_parent = tree.id
+                                               WHERE f.deleted IS NULL)
+                                           SELECT f.id, f.name, f.id_parent FROM Folder f
+                                           INNER JOIN tree ON tree.id = f.id ORDER BY tree.depth, f.id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id_collection", Value::Integer(id_collection))])?;
+    while let Some(row) = cursor.next()? {
+        folders.push(Folder {
+            id : integer(row, 0, "Folder")?,
+            name : text(row, 1, "Folder")?,
+            id_parent : row[2].as_integer(),
+        });
+    }
+    Ok(folders)
+}
+
+/// Renames a Folder.
+///
+/// * `id` - the i64 id of the folder.
+/// * `name` - its new name.
+/// * `db` - Database to work on.
+pub fn rename_folder(
+    id : i64,
+    name : &str,
+    db : &Database) -> Result<()> {
+
+    let statement = db.connection.prepare("UPDATE Folder SET name = :name WHERE id = :id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":name", Value::String(name.to_owned())),
+    (":id", Value::Integer(id)),
+    ])?;
+    cursor.next()?;
+    Ok(())
+}
+
+/// Deletes a Folder with the folders and requests it holds.
+///
+/// * `id` - the i64 id of the folder.
+/// * `db` - Database to work on.
+pub fn delete_folder(
+    id : i64,
+    db : &Database) -> Result<()> {
+
+    let subtree = "WITH RECURSIVE tree(id) AS (
+                       SELECT :id
+                       UNION ALL
+                       SELECT f.id FROM Folder f INNER JOIN tree ON f.id_parent = tree.id)";
+    transaction(db, || {
+        delete_requests_where(subtree, "id_folder IN (SELECT id FROM tree)", id, db)?;
+        execute_with_id(&format!("{} DELETE FROM Folder WHERE id IN (SELECT id FROM tree);", subtree), id, db)
+    })
+}
+
+/// Runs a statement taking an `:id` parameter.
+fn execute_with_id(
+    statement : &str,
+    id : i64,
+    db : &Database) -> Result<()> {
+
+    let mut cursor = db.connection.prepare(statement)?.into_cursor();
+    cursor.bind_by_name(vec![(":id", Value::Integer(id))])?;
+    cursor.next()?;
+    Ok(())
+}
+
+/// Deletes the requests matching a condition along with their examples,
+/// scripts and WebSocket messages.
+///
+/// * `prefix` - a `WITH` clause used by the condition, or nothing.
+/// * `condition` - an SQL condition on Request rows using an `:id` parameter.
+/// * `id` - the value of `:id`.
+/// * `db` - Database to work on.
+fn delete_requests_where(
+    prefix : &str,
+    condition : &str,
+    id : i64,
+    db : &Database) -> Result<()> {
+
+    let requests = format!("SELECT id FROM Request WHERE {}", condition);
+    let statements = [
+        format!("DELETE FROM Example WHERE id_request IN ({});", requests),
+        format!("DELETE FROM Websocket_Message WHERE id_request IN ({});", requests),
+        format!("DELETE FROM Request_History WHERE id_request IN ({});", requests),
+        format!("DELETE FROM Script WHERE owner = '{}' AND id_owner IN ({});", Owner::Request, requests),
+        format!("DELETE FROM Request WHERE {};", condition),
+    ];
+    for statement in statements.iter() {
+        execute_with_id(&format!("{} {}", prefix, statement), id, db)?;
+    }
+    Ok(())
+}
+
+/// Deletes a Workspace with its collections, folders and requests.
+///
+/// * `id` - the i64 id of the workspace.
+/// * `db` - Database to work on.
+pub fn delete_workspace(
+    id : i64,
+    db : &Database) -> Result<()> {
+
+    let collections = "SELECT id FROM Collection WHERE id_workspace = :id";
+    transaction(db, || {
+        delete_requests_where("", &format!("id_collection IN ({})", collections), id, db)?;
+        execute_with_id(&format!("DELETE FROM Folder WHERE id_collection IN ({});", collections), id, db)?;
+        execute_with_id(&format!("DELETE FROM Script WHERE owner = '{}' AND id_owner IN ({});", Owner::Collection, collections), id, db)?;
+        execute_with_id("DELETE FROM Collection WHERE id_workspace = :id;", id, db)?;
+        execute_with_id("DELETE FROM Environment WHERE id_workspace = :id;", id, db)?;
+        execute_with_id("DELETE FROM User_Workspace WHERE id_workspace = :id;", id, db)?;
+        execute_with_id("DELETE FROM Workspace WHERE id = :id;", id, db)
+    })
+}
+
+/// Deletes a Collection with its folders, requests and scripts.
+///
+/// * `id` - the i64 id of the collection.
+/// * `db` - Database to work on.
+pub fn delete_collection(
+    id : i64,
+    db : &Database) -> Result<()> {
+
+    transaction(db, || {
+        delete_requests_where("", "id_collection = :id", id, db)?;
+        execute_with_id("DELETE FROM Folder WHERE id_collection = :id;", id, db)?;
+        execute_with_id(&format!("DELETE FROM Script WHERE owner = '{}' AND id_owner = :id;", Owner::Collection), id, db)?;
+        execute_with_id("DELETE FROM Collection WHERE id = :id;", id, db)
+    })
+}
+
+/// Deletes a Request with its examples, scripts and saved WebSocket
+/// messages.
+///
+/// * `id` - the i64 id of the request.
+/// * `db` - Database to work on.
+pub fn delete_request(
+    id : i64,
+    db : &Database) -> Result<()> {
+
+    transaction(db, || delete_requests_where("", "id = :id", id, db))
+}
+
+/// Fetches all workspaces from a user's id, but those in the trash.
 ///
 /// Returns a Vec of Workspaces Struct wrapped in sqlite's Result.
 pub fn get_all_workspaces(
@@ -297,15 +828,15 @@ pub fn get_all_workspaces(
     let mut workspaces : Vec<Workspace> = vec![]; 
     let mut cursor = db.connection.prepare("SELECT * FROM Workspace w
                                            INNER JOIN User_Workspace uw ON uw.id_workspace = w.id
-                                           AND uw.id_user = :id_user")
-        .unwrap()
+                                           AND uw.id_user = :id_user
+                                           WHERE w.deleted IS NULL")?
         .into_cursor();
 
     cursor.bind_by_name(vec![(":id_user", Value::Integer(id_user.into()))])?;
-    while let Some(row) = cursor.next().unwrap() {
+    while let Some(row) = cursor.next()? {
         let workspace = Workspace {
-            id : row[0].as_integer().unwrap().to_owned(),
-            name : row[1].as_string().unwrap().to_owned(),
+            id : integer(row, 0, "Workspace")?,
+            name : text(row, 1, "Workspace")?,
             collections : vec![],
         };
         workspaces.push(workspace);
@@ -314,7 +845,7 @@ pub fn get_all_workspaces(
     Ok(workspaces)
 }
 
-/// Fetches all Collections from a Workspace id
+/// Fetches all Collections from a Workspace id, but those in the trash.
 ///
 /// * `id_workspace` - a i64 id to the corresponding workspace
 /// * `db` - Database to work on.
@@ -326,15 +857,14 @@ pub fn get_all_collections(
     let mut collections : Vec<Collection> = vec![];
 
     let mut cursor = db.connection.prepare("SELECT * FROM Collection 
-                                           WHERE id_workspace = :id_workspace")
-        .unwrap()
+                                           WHERE id_workspace = :id_workspace AND deleted IS NULL")?
         .into_cursor();
     cursor.bind_by_name(vec![(":id_workspace", Value::Integer(id_workspace.into()))])?;
 
-    while let Some(row) = cursor.next().unwrap() {
+    while let Some(row) = cursor.next()? {
         let collection = Collection {
-            id : row[0].as_integer().unwrap().to_owned(),
-            name : row[1].as_string().unwrap().to_owned(),
+            id : integer(row, 0, "Collection")?,
+            name : text(row, 1, "Collection")?,
             queries : vec![],
         };
         collections.push(collection);
@@ -342,6 +872,60 @@ pub fn get_all_collections(
     Ok(collections)
 }
 
+/// Fetches a Collection from its id.
+///
+/// * `id` - the i64 id of the collection.
+/// * `db` - Database to work on.
+pub fn get_collection(
+    id : i64,
+    db : &Database) -> Result<Option<Collection>> {
+
+    let mut cursor = db.connection.prepare("SELECT * FROM Collection WHERE id = :id")?
+        .into_cursor();
+    cursor.bind_by_name(vec![(":id", Value::Integer(id))])?;
+
+    match cursor.next()? {
+        Some(row) => Ok(Some(Collection::new(integer(row, 0, "Collection")?, text(row, 1, "Collection")?))),
+        None => Ok(None),
+    }
+}
+
+/// Fetches the id of the Workspace a Collection is in.
+///
+/// * `id` - the i64 id of the collection.
+/// * `db` - Database to work on.
+pub fn get_collection_workspace(
+    id : i64,
+    db : &Database) -> Result<Option<i64>> {
+
+    let mut cursor = db.connection.prepare("SELECT id_workspace FROM Collection WHERE id = :id")?
+        .into_cursor();
+    cursor.bind_by_name(vec![(":id", Value::Integer(id))])?;
+
+    Ok(cursor.next()?.and_then(|row| row[0].as_integer()))
+}
+
+/// Builds a Request from a `SELECT *` row of the Request table.
+fn request_from_row(row : &[Value]) -> Result<Request> {
+    Ok(Request {
+        id : integer(row, 0, "Request")?,
+        name : text(row, 1, "Request")?,
+        // Rows written by hand may hold anything, it is sent as is.
+        method : row[2].as_string()
+            .map(|method| Methods::from_str(method).unwrap_or_else(|_| Methods::Custom(method.to_owned())))
+            .unwrap_or(Methods::GET),
+        url : text(row, 3, "Request")?,
+        params : row[4].as_string().unwrap_or_default().to_owned(),
+        body : row[5].as_string().unwrap_or_default().to_owned(),
+        body_mode : BodyMode::from_str(row[7].as_string().unwrap_or("NONE")).unwrap_or(BodyMode::None),
+        headers : row[8].as_string().unwrap_or_default().to_owned(),
+        kind : RequestKind::from_str(row[9].as_string().unwrap_or("HTTP")).unwrap_or(RequestKind::Http),
+        variables : row[10].as_string().unwrap_or_default().to_owned(),
+        id_collection : integer(row, 6, "Request")?,
+        id_folder : row[11].as_integer(),
+    })
+}
+
 /// Fetches a request from it's id 
 ///
 /// * `id` - the i64 id of the request
@@ -350,37 +934,22 @@ pub fn get_request(
     id : i64,
     db : &Database) -> Result<Request> {
     let mut cursor = db.connection.prepare("SELECT * FROM Request 
-                                           WHERE id = :id")
-        .unwrap()
+                                           WHERE id = :id")?
         .into_cursor();
     cursor.bind_by_name(vec![(":id", Value::Integer(id.into()))])?;
 
 
-    match cursor.next().unwrap() {
-        Some(row) => {
-
-            let request = Request {
-
-                id : row[0].as_integer().unwrap().to_owned(),
-                name : row[1].as_string().unwrap().to_owned(),
-                method : Methods::from_str(row[2].as_string().unwrap()).unwrap(),
-                url : row[3].as_string().unwrap().to_owned(),
-                params : row[4].as_string().unwrap_or_default().to_owned(),
-                body : row[5].as_string().unwrap_or_default().to_owned(),
-            };
-            Ok(request)
-        }
-        None => Err(Error { 
-            code : Some(1),
-            message : Some(String::from("Something went wrong")),
-        })
+    match cursor.next()? {
+        Some(row) => request_from_row(row),
+        None => Err(Error::NotFound(format!("request {}", id))),
     }
 
 
 }
 
 
-/// Fetches all Requests from a Workspace id
+/// Fetches all Requests from a Workspace id, but those in the trash or in a
+/// folder in the trash.
 ///
 /// * `id_collection` - a i64 id to the corresponding workspace
 /// * `db` - Database to work on.
@@ -391,22 +960,19 @@ pub fn get_all_requests(
 
     let mut requests : Vec<Request> = vec![];
 
-    let mut cursor = db.connection.prepare("SELECT * FROM Request 
-                                           WHERE id_collection = :id_collection")
-        .unwrap()
+    let mut cursor = db.connection.prepare("WITH RECURSIVE tree(id) AS (
+                                               SELECT id FROM Folder WHERE id_collection = :id_collection AND id_parent IS NULL AND deleted IS NULL
+                                               UNION ALL
+                                               SELECT f.id FROM Folder f INNER JOIN tree ON f.id_parent = tree.id
+                                               WHERE f.deleted IS NULL)
+                                           SELECT * FROM Request
+                                           WHERE id_collection = :id_collection AND deleted IS NULL
+                                           AND (id_folder IS NULL OR id_folder IN tree)")?
         .into_cursor();
     cursor.bind_by_name(vec![(":id_collection", Value::Integer(id_collection.into()))])?;
 
-    while let Some(row) = cursor.next().unwrap() {
-        let request = Request {
-            id : row[0].as_integer().unwrap().to_owned(),
-            name : row[1].as_string().unwrap().to_owned(),
-            method : Methods::from_str(row[2].as_string().unwrap()).unwrap(),
-            url : row[3].as_string().unwrap().to_owned(),
-            params : row[4].as_string().unwrap_or_default().to_owned(),
-            body : row[5].as_string().unwrap_or_default().to_owned(),
-        };
-        requests.push(request);
+    while let Some(row) = cursor.next()? {
+        requests.push(request_from_row(row)?);
     }
     Ok(requests)
 }
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use crate::util::dbhandler::*;
+
+use sqlite::*;
+use crate::error::Result;
+
+/// A named set of `{{name}}` variables of a workspace, such as the urls and
+/// tokens of a staging or production server.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Environment {
+    pub id : i64,
+    pub id_workspace : i64,
+    pub name : String,
+    pub variables : HashMap<String, String>,
+}
+
+/// Writes variables as `name=value` lines, sorted by name.
+fn variables_to_text(variables : &HashMap<String, String>) -> String {
+    let mut lines : Vec<String> = variables.iter().map(|(name, value)| format!("{}={}\n", name, value)).collect();
+    lines.sort();
+    lines.concat()
+}
+
+/// Reads `name=value` lines, skipping those without `=`.
+fn variables_from_text(text : &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| line.find('=').map(|i| (line[..i].to_owned(), line[i + 1..].to_owned())))
+        .collect()
+}
+
+/// Creates an Environment in a Workspace.
+///
+/// * `id_workspace` - the workspace holding it.
+/// * `name` - the name of the environment.
+/// * `variables` - its variables.
+/// * `db` - Database to work on.
+///
+/// Returns the id of the environment.
+pub fn create_environment(
+    id_workspace : i64,
+    name : &str,
+    variables : &HashMap<String, String>,
+    db : &Database) -> Result<i64> {
+
+    let statement = db.connection.prepare("INSERT INTO Environment(id_workspace, name, variables)
+                                           VALUES (:id_workspace, :name, :variables);")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id_workspace", Value::Integer(id_workspace)),
+    (":name", Value::String(name.to_owned())),
+    (":variables", Value::String(variables_to_text(variables))),
+    ])?;
+    cursor.next()?;
+    last_insert_id(db)
+}
+
+/// Fetches the Environments of a Workspace, by name.
+///
+/// * `id_workspace` - the workspace holding them.
+/// * `db` - Database to work on.
+pub fn get_environments(
+    id_workspace : i64,
+    db : &Database) -> Result<Vec<Environment>> {
+
+    let statement = db.connection.prepare("SELECT id, id_workspace, name, variables FROM Environment
+                                           WHERE id_workspace = :id_workspace ORDER BY name, id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id_workspace", Value::Integer(id_workspace))])?;
+
+    let mut environments = vec![];
+    while let Some(row) = cursor.next()? {
+        environments.push(Environment {
+            id : integer(row, 0, "Environment")?,
+            id_workspace : integer(row, 1, "Environment")?,
+            name : text(row, 2, "Environment")?,
+            variables : variables_from_text(&text(row, 3, "Environment")?),
+        });
+    }
+    Ok(environments)
+}
+
+/// Replaces the variables of an Environment.
+///
+/// * `id` - the i64 id of the environment.
+/// * `variables` - its new variables.
+/// * `db` - Database to work on.
+pub fn update_environment(
+    id : i64,
+    variables : &HashMap<String, String>,
+    db : &Database) -> Result<()> {
+
+    let statement = db.connection.prepare("UPDATE Environment SET variables = :variables WHERE id = :id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":variables", Value::String(variables_to_text(variables))),
+    (":id", Value::Integer(id)),
+    ])?;
+    cursor.next()?;
+    Ok(())
+}
+
+/// Deletes an Environment from its id.
+///
+/// * `id` - the i64 id of the environment.
+/// * `db` - Database to work on.
+pub fn delete_environment(
+    id : i64,
+    db : &Database) -> Result<()> {
+
+    let statement = db.connection.prepare("DELETE FROM Environment WHERE id = :id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id", Value::Integer(id))])?;
+    cursor.next()?;
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use crate::util::dbhandler::*;
+
+use sqlite::*;
+use crate::error::Result;
+
+/// A saved response of a Request.
+#[derive(Clone)]
+pub struct Example {
+    pub id : i64,
+    pub id_request : i64,
+    pub name : String,
+    pub status : i64,
+    /// One `Name: value` per line.
+    pub headers : String,
+    pub body : String,
+    /// Milliseconds the mock server waits before answering with it.
+    pub latency : i64,
+}
+
+fn example_from_row(row : &[Value]) -> Result<Example> {
+    Ok(Example {
+        id : integer(row, 0, "Example")?,
+        id_request : integer(row, 1, "Example")?,
+        name : text(row, 2, "Example")?,
+        status : integer(row, 3, "Example")?,
+        headers : optional_text(row, 4, "Example")?,
+        body : optional_text(row, 5, "Example")?,
+        latency : integer(row, 6, "Example")?,
+    })
+}
+
+/// Creates an Example for a Request.
+///
+/// * `example` - the example to save, its id is ignored.
+/// * `db` - Database to work on.
+pub fn create_example(
+    example : &Example,
+    db : &Database) -> Result<()> {
+
+    let statement = db.connection.prepare("INSERT INTO Example(id_request, name, status, headers, body, latency)
+                                           VALUES (:id_request, :name, :status, :headers, :body, :latency);")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id_request", Value::Integer(example.id_request)),
+    (":name", Value::String(example.name.clone())),
+    (":status", Value::Integer(example.status)),
+    (":headers", Value::String(example.headers.clone())),
+    (":body", Value::String(example.body.clone())),
+    (":latency", Value::Integer(example.latency)),
+    ])?;
+    cursor.next()?;
+    Ok(())
+}
+
+/// Saves the edited headers and body of an Example.
+///
+/// * `example` - the example to update, found by its id.
+/// * `db` - Database to work on.
+pub fn update_example(
+    example : &Example,
+    db : &Database) -> Result<()> {
+
+    let statement = db.connection.prepare("UPDATE Example SET name = :name, status = :status, headers = :headers,
+                                           body = :body, latency = :latency WHERE id = :id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":name", Value::String(example.name.clone())),
+    (":status", Value::Integer(example.status)),
+    (":headers", Value::String(example.headers.clone())),
+    (":body", Value::String(example.body.clone())),
+    (":latency", Value::Integer(example.latency)),
+    (":id", Value::Integer(example.id)),
+    ])?;
+    cursor.next()?;
+    Ok(())
+}
+
+/// Deletes an Example from its id.
+///
+/// * `id` - the i64 id of the example.
+/// * `db` - Database to work on.
+pub fn delete_example(
+    id : i64,
+    db : &Database) -> Result<()> {
+
+    let statement = db.connection.prepare("DELETE FROM Example WHERE id = :id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id", Value::Integer(id))])?;
+    cursor.next()?;
+    Ok(())
+}
+
+/// Fetches the Examples of a Request, oldest first.
+///
+/// * `id_request` - the i64 id of the request.
+/// * `db` - Database to work on.
+pub fn get_examples(
+    id_request : i64,
+    db : &Database) -> Result<Vec<Example>> {
+
+    let mut examples = vec![];
+    let statement = db.connection.prepare("SELECT * FROM Example WHERE id_request = :id_request ORDER BY id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id_request", Value::Integer(id_request))])?;
+
+    while let Some(row) = cursor.next()? {
+        examples.push(example_from_row(row)?);
+    }
+    Ok(examples)
+}
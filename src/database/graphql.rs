@@ -0,0 +1,45 @@
+use crate::util::dbhandler::*;
+
+use sqlite::*;
+use crate::error::Result;
+
+/// Fetches the cached introspection result of a GraphQL endpoint.
+///
+/// * `url` - the url of the endpoint.
+/// * `db` - Database to work on.
+///
+/// Returns the `__schema` JSON, or None if it was never fetched.
+pub fn get_schema(
+    url : &str,
+    db : &Database) -> Result<Option<String>> {
+
+    let statement = db.connection.prepare("SELECT schema FROM Graphql_Schema WHERE url = :url;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":url", Value::String(url.to_owned()))])?;
+
+    match cursor.next()? {
+        Some(row) => Ok(row[0].as_string().map(|schema| schema.to_owned())),
+        None => Ok(None),
+    }
+}
+
+/// Caches the introspection result of a GraphQL endpoint, replacing any
+/// previous one.
+///
+/// * `url` - the url of the endpoint.
+/// * `schema` - the `__schema` JSON.
+/// * `db` - Database to work on.
+pub fn save_schema(
+    url : &str,
+    schema : &str,
+    db : &Database) -> Result<()> {
+
+    let statement = db.connection.prepare("INSERT INTO Graphql_Schema(url, schema) VALUES (:url, :schema)
+                                           ON CONFLICT(url) DO UPDATE SET schema = excluded.schema;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":url", Value::String(url.to_owned())),
+    (":schema", Value::String(schema.to_owned())),
+    ])?;
+    cursor.next()?;
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use crate::util::dbhandler::*;
+use crate::util::time::now_millis;
+
+use sqlite::*;
+use crate::error::Result;
+
+/// Number of commands kept, older ones are dropped as new ones come in.
+const HISTORY_SIZE : i64 = 500;
+
+/// Adds a command to the history, unless it repeats the last one.
+///
+/// * `command` - the command line as typed.
+/// * `db` - Database to work on.
+pub fn add_command(
+    command : &str,
+    db : &Database) -> Result<()> {
+
+    if get_commands(db)?.last().map(|last| last.as_str()) == Some(command) {
+        return Ok(());
+    }
+    let statement = db.connection.prepare("INSERT INTO Command_History(command, time) VALUES (:command, :time);")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":command", Value::String(command.to_owned())),
+    (":time", Value::Integer(now_millis())),
+    ])?;
+    cursor.next()?;
+
+    let statement = db.connection.prepare("DELETE FROM Command_History WHERE id NOT IN
+                                           (SELECT id FROM Command_History ORDER BY id DESC LIMIT :size);")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":size", Value::Integer(HISTORY_SIZE))])?;
+    cursor.next()?;
+    Ok(())
+}
+
+/// Gets the command history, oldest first.
+///
+/// * `db` - Database to work on.
+pub fn get_commands(db : &Database) -> Result<Vec<String>> {
+    let mut commands = vec![];
+    let statement = db.connection.prepare("SELECT command FROM Command_History ORDER BY id;")?;
+    let mut cursor = statement.into_cursor();
+    while let Some(row) = cursor.next()? {
+        commands.push(row[0].as_string().unwrap_or_default().to_owned());
+    }
+    Ok(commands)
+}
+
+/// Number of sends kept per request.
+const SENT_SIZE : i64 = 100;
+
+/// A send of a Request, as it went out and how it came back.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Sent {
+    pub id_request : i64,
+    /// When it was sent, in milliseconds.
+    pub time : i64,
+    pub method : String,
+    /// The url with its variables substituted.
+    pub url : String,
+    pub status : i64,
+    /// Milliseconds until the response was complete.
+    pub latency : i64,
+}
+
+/// Records a send of a Request, dropping the oldest ones past `SENT_SIZE`.
+///
+/// * `sent` - what was sent.
+/// * `db` - Database to work on.
+pub fn add_sent(
+    sent : &Sent,
+    db : &Database) -> Result<()> {
+
+    let statement = db.connection.prepare("INSERT INTO Request_History(id_request, time, method, url, status, latency)
+                                           VALUES (:id_request, :time, :method, :url, :status, :latency);")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id_request", Value::Integer(sent.id_request)),
+    (":time", Value::Integer(sent.time)),
+    (":method", Value::String(sent.method.clone())),
+    (":url", Value::String(sent.url.clone())),
+    (":status", Value::Integer(sent.status)),
+    (":latency", Value::Integer(sent.latency)),
+    ])?;
+    cursor.next()?;
+
+    let statement = db.connection.prepare("DELETE FROM Request_History WHERE id_request = :id_request AND id NOT IN
+                                           (SELECT id FROM Request_History WHERE id_request = :id_request ORDER BY id DESC LIMIT :size);")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id_request", Value::Integer(sent.id_request)),
+    (":size", Value::Integer(SENT_SIZE)),
+    ])?;
+    cursor.next()?;
+    Ok(())
+}
+
+/// Gets the sends of a Request, last one first.
+///
+/// * `id_request` - the i64 id of the request.
+/// * `db` - Database to work on.
+pub fn get_sent(
+    id_request : i64,
+    db : &Database) -> Result<Vec<Sent>> {
+
+    let statement = db.connection.prepare("SELECT id_request, time, method, url, status, latency FROM Request_History
+                                           WHERE id_request = :id_request ORDER BY id DESC;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id_request", Value::Integer(id_request))])?;
+
+    let mut sent = vec![];
+    while let Some(row) = cursor.next()? {
+        sent.push(Sent {
+            id_request : integer(row, 0, "Request_History")?,
+            time : integer(row, 1, "Request_History")?,
+            method : text(row, 2, "Request_History")?,
+            url : text(row, 3, "Request_History")?,
+            status : integer(row, 4, "Request_History")?,
+            latency : integer(row, 5, "Request_History")?,
+        });
+    }
+    Ok(sent)
+}
@@ -0,0 +1,277 @@
+use crate::database::container::*;
+use crate::database::storage::Storage;
+use crate::database::trash::Kind;
+
+use crate::error::Result;
+
+/// Where names without a `/` are looked up, and what `.` stands for.
+#[derive(Clone, Copy, Default)]
+pub struct Scope {
+    pub id_user : i64,
+    /// The selected workspace, its collections are found by name.
+    pub id_workspace : i64,
+    /// The selected collection, its folders and requests are found by name.
+    pub id_collection : Option<i64>,
+    pub id_folder : Option<i64>,
+    pub id_request : Option<i64>,
+}
+
+/// Names of a path, `workspace/collection/folder/.../request`.
+fn segments(path : &str) -> Vec<&str> {
+    path.split('/').filter(|segment| !segment.is_empty()).collect()
+}
+
+/// Workspaces of the user found by a path : `.` for the selected one, or its
+/// name.
+///
+/// * `scope` - the user and the selection.
+/// * `path` - the path to resolve.
+/// * `db` - Database to work on.
+pub fn find_workspaces(
+    scope : &Scope,
+    path : &str,
+    db : &dyn Storage) -> Result<Vec<Workspace>> {
+
+    let workspaces = db.get_all_workspaces(scope.id_user)?.into_iter();
+    Ok(match segments(path).as_slice() {
+        ["."] => workspaces.filter(|workspace| workspace.id == scope.id_workspace).collect(),
+        [name] => workspaces.filter(|workspace| workspace.name == *name).collect(),
+        _ => vec![],
+    })
+}
+
+/// Collections found by a path : `.` for the selected one, a name in the
+/// selected workspace, or `workspace/collection`.
+///
+/// * `scope` - the user and the selection.
+/// * `path` - the path to resolve.
+/// * `db` - Database to work on.
+pub fn find_collections(
+    scope : &Scope,
+    path : &str,
+    db : &dyn Storage) -> Result<Vec<Collection>> {
+
+    match segments(path).as_slice() {
+        ["."] => Ok(match scope.id_collection {
+            Some(id) => db.get_collection(id)?.into_iter().collect(),
+            None => vec![],
+        }),
+        [name] => Ok(db.get_all_collections(scope.id_workspace)?.into_iter().filter(|collection| collection.name == *name).collect()),
+        [workspace, name] => collections_in(scope, workspace, name, db),
+        _ => Ok(vec![]),
+    }
+}
+
+fn collections_in(
+    scope : &Scope,
+    workspace : &str,
+    name : &str,
+    db : &dyn Storage) -> Result<Vec<Collection>> {
+
+    let mut collections = vec![];
+    for workspace in find_workspaces(scope, workspace, db)? {
+        collections.extend(db.get_all_collections(workspace.id)?.into_iter().filter(|collection| collection.name == name));
+    }
+    Ok(collections)
+}
+
+/// Folders of a collection at the end of a chain of folder names, the root of
+/// the collection being None.
+fn folders_at(
+    id_collection : i64,
+    names : &[&str],
+    db : &dyn Storage) -> Result<Vec<Option<Folder>>> {
+
+    let folders = db.get_all_folders(id_collection)?;
+    let mut found : Vec<Option<Folder>> = vec![None];
+    for name in names.iter() {
+        found = folders.iter()
+            .filter(|folder| folder.name == *name && found.iter().any(|parent| parent.as_ref().map(|parent| parent.id) == folder.id_parent))
+            .cloned()
+            .map(Some)
+            .collect();
+    }
+    Ok(found)
+}
+
+/// Folders found by a path : `.` for the selected one, a name in the selected
+/// collection, or `workspace/collection/folder/.../folder`.
+///
+/// * `scope` - the user and the selection.
+/// * `path` - the path to resolve.
+/// * `db` - Database to work on.
+pub fn find_folders(
+    scope : &Scope,
+    path : &str,
+    db : &dyn Storage) -> Result<Vec<Folder>> {
+
+    let segments = segments(path);
+    match segments.as_slice() {
+        ["."] => Ok(match (scope.id_collection, scope.id_folder) {
+            (Some(id_collection), Some(id)) => db.get_all_folders(id_collection)?.into_iter().filter(|folder| folder.id == id).collect(),
+            _ => vec![],
+        }),
+        [name] => Ok(match scope.id_collection {
+            Some(id_collection) => db.get_all_folders(id_collection)?.into_iter().filter(|folder| folder.name == *name).collect(),
+            None => vec![],
+        }),
+        [workspace, collection, folders @ ..] if !folders.is_empty() => {
+            let mut found = vec![];
+            for collection in collections_in(scope, workspace, collection, db)? {
+                found.extend(folders_at(collection.id, folders, db)?.into_iter().flatten());
+            }
+            Ok(found)
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+/// Requests found by a path : `.` for the selected one, a name anywhere in the
+/// selected collection, or `workspace/collection/folder/.../request`.
+///
+/// * `scope` - the user and the selection.
+/// * `path` - the path to resolve.
+/// * `db` - Database to work on.
+pub fn find_requests(
+    scope : &Scope,
+    path : &str,
+    db : &dyn Storage) -> Result<Vec<Request>> {
+
+    let segments = segments(path);
+    match segments.as_slice() {
+        ["."] => Ok(match scope.id_request {
+            Some(id) => db.get_request(id).into_iter().collect(),
+            None => vec![],
+        }),
+        [name] => Ok(match scope.id_collection {
+            Some(id_collection) => db.get_all_requests(id_collection)?.into_iter().filter(|request| request.name == *name).collect(),
+            None => vec![],
+        }),
+        [workspace, collection, folders @ .., name] => {
+            let mut found = vec![];
+            for collection in collections_in(scope, workspace, collection, db)? {
+                let parents : Vec<Option<i64>> = folders_at(collection.id, folders, db)?
+                    .into_iter()
+                    .map(|folder| folder.map(|folder| folder.id))
+                    .collect();
+                found.extend(db.get_all_requests(collection.id)?
+                             .into_iter()
+                             .filter(|request| request.name == *name && parents.contains(&request.id_folder)));
+            }
+            Ok(found)
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+/// Paths completing a partial one, for a path naming an item of a kind. The
+/// names of the selection come first, then `workspace/` and what is in the
+/// containers along the path, those that can be walked into ending with `/`.
+///
+/// * `scope` - the user and the selection.
+/// * `kind` - what the completed path names.
+/// * `partial` - the path typed so far.
+/// * `db` - Database to work on.
+pub fn complete_paths(
+    scope : &Scope,
+    kind : Kind,
+    partial : &str,
+    db : &dyn Storage) -> Result<Vec<String>> {
+
+    let (parent, dir) = match partial.rfind('/') {
+        Some(i) => (&partial[..i], &partial[..=i]),
+        None => ("", ""),
+    };
+    let mut paths : Vec<String> = vec![];
+    let mut push = |name : &str, slash : bool| paths.push(format!("{}{}{}", dir, name, if slash { "/" } else { "" }));
+
+    match (kind, segments(parent).as_slice()) {
+        (Kind::Workspace, []) => {
+            for workspace in db.get_all_workspaces(scope.id_user)? {
+                push(&workspace.name, false);
+            }
+        }
+        (_, []) => {
+            match (kind, scope.id_collection) {
+                (Kind::Collection, _) => for collection in db.get_all_collections(scope.id_workspace)? {
+                    push(&collection.name, false);
+                },
+                (Kind::Folder, Some(id_collection)) => for folder in db.get_all_folders(id_collection)? {
+                    push(&folder.name, false);
+                },
+                (Kind::Request, Some(id_collection)) => for request in db.get_all_requests(id_collection)? {
+                    push(&request.name, false);
+                },
+                _ => {}
+            }
+            for workspace in db.get_all_workspaces(scope.id_user)? {
+                push(&workspace.name, true);
+            }
+        }
+        (Kind::Collection, [workspace]) | (Kind::Folder, [workspace]) | (Kind::Request, [workspace]) => {
+            for workspace in find_workspaces(scope, workspace, db)? {
+                for collection in db.get_all_collections(workspace.id)? {
+                    push(&collection.name, kind != Kind::Collection);
+                }
+            }
+        }
+        (Kind::Folder, [workspace, collection, folders @ ..]) | (Kind::Request, [workspace, collection, folders @ ..]) => {
+            for collection in collections_in(scope, workspace, collection, db)? {
+                let all_folders = db.get_all_folders(collection.id)?;
+                for parent in folders_at(collection.id, folders, db)? {
+                    let id_parent = parent.map(|folder| folder.id);
+                    for folder in all_folders.iter().filter(|folder| folder.id_parent == id_parent) {
+                        let nested = all_folders.iter().any(|child| child.id_parent == Some(folder.id));
+                        if kind == Kind::Folder {
+                            push(&folder.name, false);
+                        }
+                        if kind == Kind::Request || nested {
+                            push(&folder.name, true);
+                        }
+                    }
+                    if kind == Kind::Request {
+                        for request in db.get_all_requests(collection.id)?.iter().filter(|request| request.id_folder == id_parent) {
+                            push(&request.name, false);
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::dbhandler;
+
+    #[test]
+    fn completes_path_segments() {
+        let db = dbhandler::in_memory().unwrap();
+        let user = db.create_user(1, "tester").unwrap();
+        let ws = db.create_workspace(&user, "ws").unwrap();
+        db.create_workspace(&user, "other").unwrap();
+        let api = db.create_collection("api", ws).unwrap();
+        let users = db.create_folder("users", api, None).unwrap();
+        let admins = db.create_folder("admins", api, Some(users)).unwrap();
+        db.create_request("list", api, Some(users), "GET", "http://localhost").unwrap();
+        db.create_request("root", api, None, "GET", "http://localhost").unwrap();
+        db.create_request("ban", api, Some(admins), "POST", "http://localhost").unwrap();
+        let scope = Scope { id_user : 1, id_workspace : ws, id_collection : Some(api), ..Scope::default() };
+        let complete = |kind, partial| complete_paths(&scope, kind, partial, &db).unwrap();
+
+        assert_eq!(complete(Kind::Workspace, ""), vec!["other", "ws"]);
+        assert_eq!(complete(Kind::Collection, ""), vec!["api", "other/", "ws/"]);
+        assert_eq!(complete(Kind::Collection, "ws/"), vec!["ws/api"]);
+        assert_eq!(complete(Kind::Request, "ws/"), vec!["ws/api/"]);
+        assert_eq!(complete(Kind::Request, "ws/api/"), vec!["ws/api/root", "ws/api/users/"]);
+        assert_eq!(complete(Kind::Request, "ws/api/users/"), vec!["ws/api/users/admins/", "ws/api/users/list"]);
+        assert_eq!(complete(Kind::Folder, "ws/api/"), vec!["ws/api/users", "ws/api/users/"]);
+        assert_eq!(complete(Kind::Folder, "ws/api/users/"), vec!["ws/api/users/admins"]);
+        assert!(complete(Kind::Request, "nowhere/api/").is_empty());
+    }
+}
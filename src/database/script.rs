@@ -0,0 +1,132 @@
+use crate::util::dbhandler::*;
+
+use std::fmt;
+
+use sqlite::*;
+use crate::error::Result;
+
+/// What a script is attached to. Collection scripts run around every request
+/// of the collection, before the request's own.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Owner {
+    Request,
+    Collection,
+}
+
+impl fmt::Display for Owner {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&format!("{:?}", self).to_uppercase())
+    }
+}
+
+/// When a script runs.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Stage {
+    /// Before sending, it can change the request and set variables.
+    PreRequest,
+    /// Once the response is received, it inspects it and records tests.
+    PostResponse,
+}
+
+impl fmt::Display for Stage {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stage::PreRequest => f.write_str("PRE_REQUEST"),
+            Stage::PostResponse => f.write_str("POST_RESPONSE"),
+        }
+    }
+}
+
+/// The scripts to run around a Request, collection scripts first.
+#[derive(Clone, Default)]
+pub struct Scripts {
+    pub pre_request : Vec<String>,
+    pub post_response : Vec<String>,
+}
+
+/// Fetches a script, empty if none was written.
+///
+/// * `owner` - whether `id_owner` is a request or a collection.
+/// * `id_owner` - the i64 id of the request or collection.
+/// * `stage` - when the script runs.
+/// * `db` - Database to work on.
+pub fn get_script(
+    owner : Owner,
+    id_owner : i64,
+    stage : Stage,
+    db : &Database) -> Result<String> {
+
+    let statement = db.connection.prepare("SELECT source FROM Script
+                                           WHERE owner = :owner AND id_owner = :id_owner AND stage = :stage;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":owner", Value::String(owner.to_string())),
+    (":id_owner", Value::Integer(id_owner)),
+    (":stage", Value::String(stage.to_string())),
+    ])?;
+
+    match cursor.next()? {
+        Some(row) => text(row, 0, "Script"),
+        None => Ok(String::new()),
+    }
+}
+
+/// Saves a script, replacing the previous one. A blank source removes it.
+///
+/// * `owner` - whether `id_owner` is a request or a collection.
+/// * `id_owner` - the i64 id of the request or collection.
+/// * `stage` - when the script runs.
+/// * `source` - the Rhai source of the script.
+/// * `db` - Database to work on.
+pub fn save_script(
+    owner : Owner,
+    id_owner : i64,
+    stage : Stage,
+    source : &str,
+    db : &Database) -> Result<()> {
+
+    let query = if source.trim().is_empty() {
+        "DELETE FROM Script WHERE owner = :owner AND id_owner = :id_owner AND stage = :stage;"
+    }
+    else {
+        "INSERT INTO Script(owner, id_owner, stage, source) VALUES (:owner, :id_owner, :stage, :source)
+         ON CONFLICT(owner, id_owner, stage) DO UPDATE SET source = excluded.source;"
+    };
+    let statement = db.connection.prepare(query)?;
+    let mut cursor = statement.into_cursor();
+    let mut values = vec![(":owner", Value::String(owner.to_string())),
+    (":id_owner", Value::Integer(id_owner)),
+    (":stage", Value::String(stage.to_string())),
+    ];
+    if !source.trim().is_empty() {
+        values.push((":source", Value::String(source.to_owned())));
+    }
+    cursor.bind_by_name(values)?;
+    cursor.next()?;
+    Ok(())
+}
+
+/// Copies the scripts of a request or collection to another one.
+///
+/// * `owner` - what the scripts are attached to.
+/// * `id_from` - the id of the request or collection to copy from.
+/// * `id_to` - the id of the one to copy to.
+/// * `db` - Database to work on.
+pub fn copy_scripts(
+    owner : Owner,
+    id_from : i64,
+    id_to : i64,
+    db : &Database) -> Result<()> {
+
+    let statement = db.connection.prepare("INSERT INTO Script(owner, id_owner, stage, source)
+                                           SELECT owner, :id_to, stage, source FROM Script
+                                           WHERE owner = :owner AND id_owner = :id_from;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":owner", Value::String(owner.to_string())),
+    (":id_from", Value::Integer(id_from)),
+    (":id_to", Value::Integer(id_to)),
+    ])?;
+    cursor.next()?;
+    Ok(())
+}
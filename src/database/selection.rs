@@ -0,0 +1,88 @@
+use crate::util::dbhandler::*;
+use crate::database::trash::Kind;
+
+use sqlite::*;
+use crate::error::Result;
+
+/// Where a user left the UI, to open it there again.
+#[derive(Clone, Default, PartialEq)]
+pub struct Selection {
+    /// The workspace of the selected tab.
+    pub id_workspace : i64,
+    /// The selected collection, folder or request of the tree.
+    pub item : Option<(Kind, i64)>,
+    /// The collections and folders open in the tree.
+    pub expanded : Vec<(Kind, i64)>,
+    /// First row of the tree shown.
+    pub tree_offset : usize,
+    /// Lines of the response scrolled past.
+    pub response_scroll : u16,
+}
+
+/// Writes items as `kind id` lines.
+fn items_to_text(items : &[(Kind, i64)]) -> String {
+    items.iter().map(|(kind, id)| format!("{} {}\n", kind, id)).collect()
+}
+
+/// Reads `kind id` lines, skipping those that do not parse.
+fn items_from_text(text : &str) -> Vec<(Kind, i64)> {
+    text.lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            Some((words.next()?.parse().ok()?, words.next()?.parse().ok()?))
+        })
+        .collect()
+}
+
+/// Fetches where a user left the UI.
+///
+/// * `id_user` - the i64 id of the user.
+/// * `db` - Database to work on.
+///
+/// Returns None if it was never saved.
+pub fn get_selection(
+    id_user : i64,
+    db : &Database) -> Result<Option<Selection>> {
+
+    let statement = db.connection.prepare("SELECT id_workspace, item, expanded, tree_offset, response_scroll FROM Selection WHERE id_user = :id_user;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id_user", Value::Integer(id_user))])?;
+
+    match cursor.next()? {
+        Some(row) => Ok(Some(Selection {
+            id_workspace : integer(row, 0, "Selection")?,
+            item : items_from_text(&optional_text(row, 1, "Selection")?).pop(),
+            expanded : items_from_text(&optional_text(row, 2, "Selection")?),
+            tree_offset : integer(row, 3, "Selection")? as usize,
+            response_scroll : integer(row, 4, "Selection")? as u16,
+        })),
+        None => Ok(None),
+    }
+}
+
+/// Saves where a user left the UI, replacing what was saved before.
+///
+/// * `id_user` - the i64 id of the user.
+/// * `selection` - the selection to save.
+/// * `db` - Database to work on.
+pub fn save_selection(
+    id_user : i64,
+    selection : &Selection,
+    db : &Database) -> Result<()> {
+
+    let statement = db.connection.prepare("INSERT INTO Selection(id_user, id_workspace, item, expanded, tree_offset, response_scroll)
+                                           VALUES (:id_user, :id_workspace, :item, :expanded, :tree_offset, :response_scroll)
+                                           ON CONFLICT(id_user) DO UPDATE SET id_workspace = excluded.id_workspace,
+                                           item = excluded.item, expanded = excluded.expanded,
+                                           tree_offset = excluded.tree_offset, response_scroll = excluded.response_scroll;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id_user", Value::Integer(id_user)),
+    (":id_workspace", Value::Integer(selection.id_workspace)),
+    (":item", Value::String(items_to_text(&selection.item.into_iter().collect::<Vec<_>>()))),
+    (":expanded", Value::String(items_to_text(&selection.expanded))),
+    (":tree_offset", Value::Integer(selection.tree_offset as i64)),
+    (":response_scroll", Value::Integer(selection.response_scroll as i64)),
+    ])?;
+    cursor.next()?;
+    Ok(())
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::database::container::*;
+use crate::database::environment::Environment;
+use crate::database::example::Example;
+use crate::database::history::Sent;
+use crate::database::script::{Owner, Scripts, Stage};
+use crate::database::selection::Selection;
+use crate::database::trash::{Kind, Trashed};
+use crate::database::user::User;
+use crate::database::websocket::Message;
+
+use crate::error::Result;
+
+pub mod sqlite;
+pub mod files;
+pub mod cache;
+
+/// Where the data is kept, picked with the `storage` option.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Backend {
+    /// A sqlite database file.
+    Sqlite,
+    /// A directory with a JSON file per row, see `files::Files`.
+    Files,
+    /// A sqlite database in memory, gone on exit.
+    Memory,
+}
+
+impl FromStr for Backend {
+    type Err = ();
+    fn from_str(input : &str) -> std::result::Result<Backend, Self::Err> {
+        match input {
+            "sqlite" => Ok(Backend::Sqlite),
+            "files" => Ok(Backend::Files),
+            "memory" => Ok(Backend::Memory),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&format!("{:?}", self).to_lowercase())
+    }
+}
+
+/// Workspaces, collections, folders and requests with everything attached to
+/// them : examples, scripts, GraphQL schemas, WebSocket sessions, the trash,
+/// environments and the history of commands and sends. The UI works on this
+/// only, the free functions of the `database` modules being its sqlite
+/// implementation.
+///
+/// Items in the trash are left out of every listing, see `trash`.
+pub trait Storage {
+
+    /// The user of an id, None if there is no such user.
+    fn get_user(&self, id : i64) -> Result<Option<User>>;
+    /// Creates a user with a given id, used to set up a new storage.
+    fn create_user(&self, id : i64, name : &str) -> Result<User>;
+
+    /// Creates a workspace for a user, returning its id.
+    fn create_workspace(&self, user : &User, name : &str) -> Result<i64>;
+    fn get_all_workspaces(&self, id_user : i64) -> Result<Vec<Workspace>>;
+    fn rename_workspace(&self, id : i64, name : &str) -> Result<()>;
+    /// Deletes a workspace with everything it holds.
+    fn delete_workspace(&self, id : i64) -> Result<()>;
+
+    /// Creates a collection in a workspace, returning its id.
+    fn create_collection(&self, name : &str, id_workspace : i64) -> Result<i64>;
+    fn get_all_collections(&self, id_workspace : i64) -> Result<Vec<Collection>>;
+    /// The collection of an id, in the trash or not.
+    fn get_collection(&self, id : i64) -> Result<Option<Collection>>;
+    fn get_collection_workspace(&self, id : i64) -> Result<Option<i64>>;
+    fn rename_collection(&self, id : i64, name : &str) -> Result<()>;
+    fn move_collection(&self, id : i64, id_workspace : i64) -> Result<()>;
+    /// Copies a collection with its folders, requests and scripts, returning
+    /// the id of the copy.
+    fn duplicate_collection(&self, id : i64, name : &str, id_workspace : i64) -> Result<i64>;
+    /// Deletes a collection with everything it holds.
+    fn delete_collection(&self, id : i64) -> Result<()>;
+
+    /// Creates a folder in a collection, or in another folder, returning its
+    /// id.
+    fn create_folder(&self, name : &str, id_collection : i64, id_parent : Option<i64>) -> Result<i64>;
+    /// The folders of a collection, each one after its parent.
+    fn get_all_folders(&self, id_collection : i64) -> Result<Vec<Folder>>;
+    fn rename_folder(&self, id : i64, name : &str) -> Result<()>;
+    /// Deletes a folder with the folders and requests it holds.
+    fn delete_folder(&self, id : i64) -> Result<()>;
+
+    /// Creates an HTTP request, returning its id.
+    fn create_request(&self, name : &str, id_collection : i64, id_folder : Option<i64>, method : &str, url : &str) -> Result<i64>;
+    /// Creates a GraphQL request, returning its id.
+    fn create_graphql_request(&self, name : &str, id_collection : i64, id_folder : Option<i64>, url : &str) -> Result<i64>;
+    /// Creates a WebSocket request, returning its id.
+    fn create_websocket_request(&self, name : &str, id_collection : i64, id_folder : Option<i64>, url : &str) -> Result<i64>;
+    /// The request of an id, in the trash or not.
+    fn get_request(&self, id : i64) -> Result<Request>;
+    fn get_all_requests(&self, id_collection : i64) -> Result<Vec<Request>>;
+    fn update_request_body(&self, id : i64, body_mode : &BodyMode, body : &str) -> Result<()>;
+    fn update_request_headers(&self, id : i64, headers : &str) -> Result<()>;
+    fn update_request_variables(&self, id : i64, variables : &str) -> Result<()>;
+    fn rename_request(&self, id : i64, name : &str) -> Result<()>;
+    /// Moves a request to a collection, at its root if `id_folder` is None.
+    fn move_request(&self, id : i64, id_collection : i64, id_folder : Option<i64>) -> Result<()>;
+    /// Copies a request with its scripts and examples, returning the id of the
+    /// copy.
+    fn duplicate_request(&self, id : i64, name : &str, id_collection : i64, id_folder : Option<i64>) -> Result<i64>;
+    /// Deletes a request with its examples, scripts and WebSocket messages.
+    fn delete_request(&self, id : i64) -> Result<()>;
+
+    /// Puts an item in the trash, hiding it and what it holds.
+    fn trash(&self, kind : Kind, id : i64) -> Result<()>;
+    fn restore(&self, kind : Kind, id : i64) -> Result<()>;
+    /// The items a user put in the trash, last deleted first.
+    fn get_trash(&self, id_user : i64) -> Result<Vec<Trashed>>;
+
+    /// Deletes an item for good, with everything it holds.
+    fn purge(&self, kind : Kind, id : i64) -> Result<()> {
+        match kind {
+            Kind::Workspace => self.delete_workspace(id),
+            Kind::Collection => self.delete_collection(id),
+            Kind::Folder => self.delete_folder(id),
+            Kind::Request => self.delete_request(id),
+        }
+    }
+
+    /// Deletes everything a user put in the trash for good, returning the
+    /// number of items deleted.
+    fn empty_trash(&self, id_user : i64) -> Result<usize> {
+        let trashed = self.get_trash(id_user)?;
+        for item in trashed.iter() {
+            self.purge(item.kind, item.id)?;
+        }
+        Ok(trashed.len())
+    }
+
+    /// Adds a command to the history, unless it repeats the last one.
+    fn add_command(&self, command : &str) -> Result<()>;
+    /// The command history, oldest first.
+    fn get_commands(&self) -> Result<Vec<String>>;
+
+    /// Records a send of a request.
+    fn add_sent(&self, sent : &Sent) -> Result<()>;
+    /// The sends of a request, last one first.
+    fn get_sent(&self, id_request : i64) -> Result<Vec<Sent>>;
+
+    /// Creates an environment in a workspace, returning its id.
+    fn create_environment(&self, id_workspace : i64, name : &str, variables : &HashMap<String, String>) -> Result<i64>;
+    /// The environments of a workspace, by name.
+    fn get_environments(&self, id_workspace : i64) -> Result<Vec<Environment>>;
+    /// Replaces the variables of an environment.
+    fn update_environment(&self, id : i64, variables : &HashMap<String, String>) -> Result<()>;
+    fn delete_environment(&self, id : i64) -> Result<()>;
+
+    /// Creates an example, its id is ignored.
+    fn create_example(&self, example : &Example) -> Result<()>;
+    /// Saves an example, found by its id.
+    fn update_example(&self, example : &Example) -> Result<()>;
+    fn delete_example(&self, id : i64) -> Result<()>;
+    /// The examples of a request, oldest first.
+    fn get_examples(&self, id_request : i64) -> Result<Vec<Example>>;
+
+    /// A script, empty if none was written.
+    fn get_script(&self, owner : Owner, id_owner : i64, stage : Stage) -> Result<String>;
+    /// Saves a script, a blank source removing it.
+    fn save_script(&self, owner : Owner, id_owner : i64, stage : Stage, source : &str) -> Result<()>;
+
+    /// The scripts to run around a request, those of its collection first.
+    fn get_scripts(&self, id_collection : i64, id_request : i64) -> Result<Scripts> {
+        let mut scripts = Scripts::default();
+        for (owner, id_owner) in [(Owner::Collection, id_collection), (Owner::Request, id_request)].iter() {
+            for stage in [Stage::PreRequest, Stage::PostResponse].iter() {
+                let source = self.get_script(*owner, *id_owner, *stage)?;
+                if source.trim().is_empty() {
+                    continue;
+                }
+                match stage {
+                    Stage::PreRequest => scripts.pre_request.push(source),
+                    Stage::PostResponse => scripts.post_response.push(source),
+                }
+            }
+        }
+        Ok(scripts)
+    }
+
+    /// The requests of a collection with the scripts to run around each one.
+    fn get_requests_with_scripts(&self, id_collection : i64) -> Result<Vec<(Request, Scripts)>> {
+        let mut requests = vec![];
+        for request in self.get_all_requests(id_collection)? {
+            let scripts = self.get_scripts(id_collection, request.id)?;
+            requests.push((request, scripts));
+        }
+        Ok(requests)
+    }
+
+    /// The cached `__schema` JSON of a GraphQL endpoint.
+    fn get_schema(&self, url : &str) -> Result<Option<String>>;
+    fn save_schema(&self, url : &str, schema : &str) -> Result<()>;
+
+    /// Where a user left the UI, None if it was never saved.
+    fn get_selection(&self, id_user : i64) -> Result<Option<Selection>>;
+    fn save_selection(&self, id_user : i64, selection : &Selection) -> Result<()>;
+
+    /// Replaces the saved session of a WebSocket request.
+    fn save_messages(&self, id_request : i64, messages : &[Message]) -> Result<()>;
+    /// The saved session of a WebSocket request, oldest message first.
+    fn get_messages(&self, id_request : i64) -> Result<Vec<Message>>;
+}
@@ -0,0 +1,371 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::database::container::*;
+use crate::database::environment::Environment;
+use crate::database::example::Example;
+use crate::database::history::Sent;
+use crate::database::script::{Owner, Stage};
+use crate::database::selection::Selection;
+use crate::database::trash::{Kind, Trashed};
+use crate::database::user::User;
+use crate::database::websocket::Message;
+
+use crate::error::Result;
+use super::Storage;
+
+/// Keeps what the UI reads on every frame, the workspaces, collections,
+/// folders, requests, examples and WebSocket sessions, in memory in front of
+/// another storage. Changes go through to it and only forget what they touch,
+/// the rest is read once.
+///
+/// Changes made by other programs to the storage underneath are not seen.
+pub struct Cache {
+    storage : Box<dyn Storage>,
+    /// Bumped on every change, so that views built out of the cache know when
+    /// to be built again.
+    generation : Cell<u64>,
+    workspaces : RefCell<HashMap<i64, Vec<Workspace>>>,
+    collections : RefCell<HashMap<i64, Vec<Collection>>>,
+    folders : RefCell<HashMap<i64, Vec<Folder>>>,
+    requests : RefCell<HashMap<i64, Vec<Request>>>,
+    examples : RefCell<HashMap<i64, Vec<Example>>>,
+    messages : RefCell<HashMap<i64, Vec<Message>>>,
+}
+
+/// The cached value of a key, read from the storage underneath when missing.
+fn cached<K, V, F>(
+    map : &RefCell<HashMap<K, V>>,
+    key : K,
+    read : F) -> Result<V>
+where
+K : Eq + Hash,
+V : Clone,
+F : FnOnce() -> Result<V>,
+{
+    if let Some(value) = map.borrow().get(&key) {
+        return Ok(value.clone());
+    }
+    let value = read()?;
+    map.borrow_mut().insert(key, value.clone());
+    Ok(value)
+}
+
+impl Cache {
+
+    pub fn new(storage : Box<dyn Storage>) -> Cache {
+        Cache {
+            storage,
+            generation : Cell::new(0),
+            workspaces : RefCell::new(HashMap::new()),
+            collections : RefCell::new(HashMap::new()),
+            folders : RefCell::new(HashMap::new()),
+            requests : RefCell::new(HashMap::new()),
+            examples : RefCell::new(HashMap::new()),
+            messages : RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Changes every time something is written.
+    pub fn generation(&self) -> u64 {
+        self.generation.get()
+    }
+
+    /// Passes the result of a change along, noting that there was one if it
+    /// went through.
+    fn changed<T>(&self, result : Result<T>) -> Result<T> {
+        if result.is_ok() {
+            self.generation.set(self.generation.get() + 1);
+        }
+        result
+    }
+
+    /// Forgets the requests of the collection holding a request.
+    fn forget_request(&self, id : i64) {
+        self.requests.borrow_mut().retain(|_, requests| requests.iter().all(|request| request.id != id));
+    }
+
+    /// Forgets everything held by collections, when what changed is not known
+    /// precisely.
+    fn forget_contents(&self) {
+        self.collections.borrow_mut().clear();
+        self.folders.borrow_mut().clear();
+        self.requests.borrow_mut().clear();
+    }
+}
+
+impl Storage for Cache {
+
+    fn get_user(&self, id : i64) -> Result<Option<User>> {
+        self.storage.get_user(id)
+    }
+
+    fn create_user(&self, id : i64, name : &str) -> Result<User> {
+        self.changed(self.storage.create_user(id, name))
+    }
+
+    fn create_workspace(&self, user : &User, name : &str) -> Result<i64> {
+        self.workspaces.borrow_mut().remove(&user.id);
+        self.changed(self.storage.create_workspace(user, name))
+    }
+
+    fn get_all_workspaces(&self, id_user : i64) -> Result<Vec<Workspace>> {
+        cached(&self.workspaces, id_user, || self.storage.get_all_workspaces(id_user))
+    }
+
+    fn rename_workspace(&self, id : i64, name : &str) -> Result<()> {
+        self.workspaces.borrow_mut().clear();
+        self.changed(self.storage.rename_workspace(id, name))
+    }
+
+    fn delete_workspace(&self, id : i64) -> Result<()> {
+        self.workspaces.borrow_mut().clear();
+        self.forget_contents();
+        self.changed(self.storage.delete_workspace(id))
+    }
+
+    fn create_collection(&self, name : &str, id_workspace : i64) -> Result<i64> {
+        self.collections.borrow_mut().remove(&id_workspace);
+        self.changed(self.storage.create_collection(name, id_workspace))
+    }
+
+    fn get_all_collections(&self, id_workspace : i64) -> Result<Vec<Collection>> {
+        cached(&self.collections, id_workspace, || self.storage.get_all_collections(id_workspace))
+    }
+
+    fn get_collection(&self, id : i64) -> Result<Option<Collection>> {
+        self.storage.get_collection(id)
+    }
+
+    fn get_collection_workspace(&self, id : i64) -> Result<Option<i64>> {
+        self.storage.get_collection_workspace(id)
+    }
+
+    fn rename_collection(&self, id : i64, name : &str) -> Result<()> {
+        self.collections.borrow_mut().clear();
+        self.changed(self.storage.rename_collection(id, name))
+    }
+
+    fn move_collection(&self, id : i64, id_workspace : i64) -> Result<()> {
+        self.collections.borrow_mut().clear();
+        self.changed(self.storage.move_collection(id, id_workspace))
+    }
+
+    fn duplicate_collection(&self, id : i64, name : &str, id_workspace : i64) -> Result<i64> {
+        self.collections.borrow_mut().remove(&id_workspace);
+        self.changed(self.storage.duplicate_collection(id, name, id_workspace))
+    }
+
+    fn delete_collection(&self, id : i64) -> Result<()> {
+        self.collections.borrow_mut().clear();
+        self.folders.borrow_mut().remove(&id);
+        self.requests.borrow_mut().remove(&id);
+        self.changed(self.storage.delete_collection(id))
+    }
+
+    fn create_folder(&self, name : &str, id_collection : i64, id_parent : Option<i64>) -> Result<i64> {
+        self.folders.borrow_mut().remove(&id_collection);
+        self.changed(self.storage.create_folder(name, id_collection, id_parent))
+    }
+
+    fn get_all_folders(&self, id_collection : i64) -> Result<Vec<Folder>> {
+        cached(&self.folders, id_collection, || self.storage.get_all_folders(id_collection))
+    }
+
+    fn rename_folder(&self, id : i64, name : &str) -> Result<()> {
+        self.folders.borrow_mut().retain(|_, folders| folders.iter().all(|folder| folder.id != id));
+        self.changed(self.storage.rename_folder(id, name))
+    }
+
+    fn delete_folder(&self, id : i64) -> Result<()> {
+        self.forget_contents();
+        self.changed(self.storage.delete_folder(id))
+    }
+
+    fn create_request(&self, name : &str, id_collection : i64, id_folder : Option<i64>, method : &str, url : &str) -> Result<i64> {
+        self.requests.borrow_mut().remove(&id_collection);
+        self.changed(self.storage.create_request(name, id_collection, id_folder, method, url))
+    }
+
+    fn create_graphql_request(&self, name : &str, id_collection : i64, id_folder : Option<i64>, url : &str) -> Result<i64> {
+        self.requests.borrow_mut().remove(&id_collection);
+        self.changed(self.storage.create_graphql_request(name, id_collection, id_folder, url))
+    }
+
+    fn create_websocket_request(&self, name : &str, id_collection : i64, id_folder : Option<i64>, url : &str) -> Result<i64> {
+        self.requests.borrow_mut().remove(&id_collection);
+        self.changed(self.storage.create_websocket_request(name, id_collection, id_folder, url))
+    }
+
+    fn get_request(&self, id : i64) -> Result<Request> {
+        self.storage.get_request(id)
+    }
+
+    fn get_all_requests(&self, id_collection : i64) -> Result<Vec<Request>> {
+        cached(&self.requests, id_collection, || self.storage.get_all_requests(id_collection))
+    }
+
+    fn update_request_body(&self, id : i64, body_mode : &BodyMode, body : &str) -> Result<()> {
+        self.forget_request(id);
+        self.changed(self.storage.update_request_body(id, body_mode, body))
+    }
+
+    fn update_request_headers(&self, id : i64, headers : &str) -> Result<()> {
+        self.forget_request(id);
+        self.changed(self.storage.update_request_headers(id, headers))
+    }
+
+    fn update_request_variables(&self, id : i64, variables : &str) -> Result<()> {
+        self.forget_request(id);
+        self.changed(self.storage.update_request_variables(id, variables))
+    }
+
+    fn rename_request(&self, id : i64, name : &str) -> Result<()> {
+        self.forget_request(id);
+        self.changed(self.storage.rename_request(id, name))
+    }
+
+    fn move_request(&self, id : i64, id_collection : i64, id_folder : Option<i64>) -> Result<()> {
+        self.forget_request(id);
+        self.requests.borrow_mut().remove(&id_collection);
+        self.changed(self.storage.move_request(id, id_collection, id_folder))
+    }
+
+    fn duplicate_request(&self, id : i64, name : &str, id_collection : i64, id_folder : Option<i64>) -> Result<i64> {
+        self.requests.borrow_mut().remove(&id_collection);
+        self.changed(self.storage.duplicate_request(id, name, id_collection, id_folder))
+    }
+
+    fn delete_request(&self, id : i64) -> Result<()> {
+        self.forget_request(id);
+        self.examples.borrow_mut().remove(&id);
+        self.messages.borrow_mut().remove(&id);
+        self.changed(self.storage.delete_request(id))
+    }
+
+    fn trash(&self, kind : Kind, id : i64) -> Result<()> {
+        match kind {
+            Kind::Request => self.forget_request(id),
+            Kind::Workspace => self.workspaces.borrow_mut().clear(),
+            // What they hold goes out of sight with them.
+            Kind::Collection | Kind::Folder => self.forget_contents(),
+        }
+        self.changed(self.storage.trash(kind, id))
+    }
+
+    fn restore(&self, kind : Kind, id : i64) -> Result<()> {
+        match kind {
+            // Not cached while in the trash, its collection is not known.
+            Kind::Request => self.requests.borrow_mut().clear(),
+            Kind::Workspace => self.workspaces.borrow_mut().clear(),
+            Kind::Collection | Kind::Folder => self.forget_contents(),
+        }
+        self.changed(self.storage.restore(kind, id))
+    }
+
+    fn get_trash(&self, id_user : i64) -> Result<Vec<Trashed>> {
+        self.storage.get_trash(id_user)
+    }
+
+    fn add_command(&self, command : &str) -> Result<()> {
+        self.storage.add_command(command)
+    }
+
+    fn get_commands(&self) -> Result<Vec<String>> {
+        self.storage.get_commands()
+    }
+
+    fn add_sent(&self, sent : &Sent) -> Result<()> {
+        self.storage.add_sent(sent)
+    }
+
+    fn get_sent(&self, id_request : i64) -> Result<Vec<Sent>> {
+        self.storage.get_sent(id_request)
+    }
+
+    fn create_environment(&self, id_workspace : i64, name : &str, variables : &HashMap<String, String>) -> Result<i64> {
+        self.storage.create_environment(id_workspace, name, variables)
+    }
+
+    fn get_environments(&self, id_workspace : i64) -> Result<Vec<Environment>> {
+        self.storage.get_environments(id_workspace)
+    }
+
+    fn update_environment(&self, id : i64, variables : &HashMap<String, String>) -> Result<()> {
+        self.storage.update_environment(id, variables)
+    }
+
+    fn delete_environment(&self, id : i64) -> Result<()> {
+        self.storage.delete_environment(id)
+    }
+
+    fn create_example(&self, example : &Example) -> Result<()> {
+        self.examples.borrow_mut().remove(&example.id_request);
+        self.changed(self.storage.create_example(example))
+    }
+
+    fn update_example(&self, example : &Example) -> Result<()> {
+        self.examples.borrow_mut().remove(&example.id_request);
+        self.changed(self.storage.update_example(example))
+    }
+
+    fn delete_example(&self, id : i64) -> Result<()> {
+        self.examples.borrow_mut().retain(|_, examples| examples.iter().all(|example| example.id != id));
+        self.changed(self.storage.delete_example(id))
+    }
+
+    fn get_examples(&self, id_request : i64) -> Result<Vec<Example>> {
+        cached(&self.examples, id_request, || self.storage.get_examples(id_request))
+    }
+
+    fn get_script(&self, owner : Owner, id_owner : i64, stage : Stage) -> Result<String> {
+        self.storage.get_script(owner, id_owner, stage)
+    }
+
+    fn save_script(&self, owner : Owner, id_owner : i64, stage : Stage, source : &str) -> Result<()> {
+        self.changed(self.storage.save_script(owner, id_owner, stage, source))
+    }
+
+    fn get_schema(&self, url : &str) -> Result<Option<String>> {
+        self.storage.get_schema(url)
+    }
+
+    fn save_schema(&self, url : &str, schema : &str) -> Result<()> {
+        self.changed(self.storage.save_schema(url, schema))
+    }
+
+    fn get_selection(&self, id_user : i64) -> Result<Option<Selection>> {
+        self.storage.get_selection(id_user)
+    }
+
+    fn save_selection(&self, id_user : i64, selection : &Selection) -> Result<()> {
+        self.storage.save_selection(id_user, selection)
+    }
+
+    fn save_messages(&self, id_request : i64, messages : &[Message]) -> Result<()> {
+        self.messages.borrow_mut().remove(&id_request);
+        self.changed(self.storage.save_messages(id_request, messages))
+    }
+
+    fn get_messages(&self, id_request : i64) -> Result<Vec<Message>> {
+        cached(&self.messages, id_request, || self.storage.get_messages(id_request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::dbhandler;
+
+    #[test]
+    fn counts_only_the_changes_that_went_through() {
+        let cache = Cache::new(Box::new(dbhandler::in_memory().unwrap()));
+        cache.create_user(1, "tester").unwrap();
+        let generation = cache.generation();
+        assert!(cache.create_user(1, "again").is_err());
+        assert_eq!(cache.generation(), generation);
+        cache.create_user(2, "other").unwrap();
+        assert_eq!(cache.generation(), generation + 1);
+    }
+}
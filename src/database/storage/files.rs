@@ -0,0 +1,491 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Number, Value as Json};
+use sqlite::Value;
+
+use crate::util::dbhandler::{self, Database};
+use crate::database::container::*;
+use crate::database::environment::Environment;
+use crate::database::example::Example;
+use crate::database::history::Sent;
+use crate::database::script::{Owner, Stage};
+use crate::database::selection::Selection;
+use crate::database::trash::{Kind, Trashed};
+use crate::database::user::User;
+use crate::database::websocket::Message;
+
+use crate::error::{Error, Result};
+use super::Storage;
+
+/// File holding the number of migrations the rows were written with.
+const VERSION : &str = "version";
+
+/// A directory of plain files, easy to read, diff and keep under version
+/// control :
+///
+/// ```text
+/// version
+/// Workspace/1.json
+/// Collection/1.json
+/// Request/1.json
+/// ...
+/// ```
+///
+/// Each table of the sqlite schema is a directory with a JSON object per row,
+/// named after its rowid. The rows are loaded into a database in memory which
+/// does the work. Triggers note the rows each change writes, and only their
+/// files are written back. Other directories, such as `.git`, are left alone.
+pub struct Files {
+    root : PathBuf,
+    db : Database,
+    /// What each file held when it was last read or written.
+    written : RefCell<HashMap<PathBuf, String>>,
+}
+
+/// Opens (or creates) a directory of files, upgrading rows written by an
+/// older version.
+///
+/// * `root` - the directory.
+pub fn open(root : &Path) -> Result<Files> {
+    fs::create_dir_all(root)?;
+    let mut written = HashMap::new();
+    let version = match fs::read_to_string(root.join(VERSION)) {
+        Ok(text) => {
+            let version = text.trim().parse().map_err(|_| Error::Corrupt(root.join(VERSION).display().to_string()))?;
+            written.insert(root.join(VERSION), text);
+            version
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+        Err(e) => return Err(e.into()),
+    };
+    let files = Files {
+        root : root.to_owned(),
+        db : dbhandler::open_at(":memory:", version)?,
+        written : RefCell::new(written),
+    };
+    for table in tables(&files.db)? {
+        files.load(&table)?;
+    }
+    dbhandler::upgrade(&files.db)?;
+    files.save_all()?;
+    track(&files.db)?;
+    Ok(files)
+}
+
+/// Notes the rows written to any table in a temporary `Changed` table, for
+/// `Files::save`.
+fn track(db : &Database) -> Result<()> {
+    db.connection.execute("CREATE TEMP TABLE Changed (name TEXT NOT NULL, id INTEGER NOT NULL);")?;
+    for table in tables(db)? {
+        db.connection.execute(format!(
+                "CREATE TEMP TRIGGER \"{table}_inserted\" AFTER INSERT ON \"{table}\"
+                 BEGIN INSERT INTO Changed VALUES ('{table}', NEW.rowid); END;
+                 CREATE TEMP TRIGGER \"{table}_updated\" AFTER UPDATE ON \"{table}\"
+                 BEGIN INSERT INTO Changed VALUES ('{table}', OLD.rowid), ('{table}', NEW.rowid); END;
+                 CREATE TEMP TRIGGER \"{table}_deleted\" AFTER DELETE ON \"{table}\"
+                 BEGIN INSERT INTO Changed VALUES ('{table}', OLD.rowid); END;",
+                 table = table))?;
+    }
+    Ok(())
+}
+
+/// Names of the tables of a database.
+fn tables(db : &Database) -> Result<Vec<String>> {
+    let mut tables = vec![];
+    let mut cursor = db.connection.prepare("SELECT name FROM sqlite_master
+                                           WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name;")?
+        .into_cursor();
+    while let Some(row) = cursor.next()? {
+        tables.push(dbhandler::text(row, 0, "sqlite_master")?);
+    }
+    Ok(tables)
+}
+
+/// Names of the columns of a table.
+fn columns(
+    table : &str,
+    db : &Database) -> Result<Vec<String>> {
+
+    let statement = db.connection.prepare(format!("SELECT * FROM \"{}\" LIMIT 0;", table))?;
+    Ok(statement.column_names().iter().map(|column| column.to_string()).collect())
+}
+
+/// A row as a JSON object, the columns being those of the statement after
+/// the rowid.
+fn row_to_text(
+    columns : &[String],
+    row : &[Value]) -> String {
+
+    let object : Map<String, Json> = columns.iter().cloned().zip(row[1..].iter().map(to_json)).collect();
+    serde_json::to_string_pretty(&object).unwrap_or_default() + "\n"
+}
+
+fn to_json(value : &Value) -> Json {
+    match value {
+        Value::Integer(i) => Json::from(*i),
+        Value::Float(f) => Number::from_f64(*f).map(Json::Number).unwrap_or(Json::Null),
+        Value::String(s) => Json::from(s.as_str()),
+        Value::Binary(_) | Value::Null => Json::Null,
+    }
+}
+
+fn from_json(json : &Json) -> Option<Value> {
+    match json {
+        Json::Null => Some(Value::Null),
+        Json::String(s) => Some(Value::String(s.clone())),
+        Json::Number(n) => n.as_i64().map(Value::Integer).or_else(|| n.as_f64().map(Value::Float)),
+        _ => None,
+    }
+}
+
+fn write(
+    path : &Path,
+    text : &str) -> Result<()> {
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, text)?;
+    Ok(())
+}
+
+fn remove(path : &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+impl Files {
+
+    /// Inserts the rows of a table's directory, if there is one.
+    fn load(&self, table : &str) -> Result<()> {
+        let entries = match fs::read_dir(self.root.join(table)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let columns = columns(table, &self.db)?;
+        for entry in entries {
+            let path = entry?.path();
+            let rowid = match path.file_name().and_then(|name| name.to_str()).and_then(|name| name.strip_suffix(".json")) {
+                Some(rowid) => rowid,
+                None => continue,
+            };
+            let corrupt = || Error::Corrupt(path.display().to_string());
+            let text = fs::read_to_string(&path)?;
+            let row = match serde_json::from_str::<Json>(&text) {
+                Ok(Json::Object(row)) => row,
+                _ => return Err(corrupt()),
+            };
+
+            let mut names = vec![String::from("rowid")];
+            let mut values = vec![Value::Integer(rowid.parse().map_err(|_| corrupt())?)];
+            for (column, value) in row.iter() {
+                // Only known columns make it into the statement.
+                if !columns.contains(column) {
+                    return Err(corrupt());
+                }
+                names.push(format!("\"{}\"", column));
+                values.push(from_json(value).ok_or_else(corrupt)?);
+            }
+            let statement = format!("INSERT INTO \"{}\" ({}) VALUES ({});",
+                                    table, names.join(", "), vec!["?"; names.len()].join(", "));
+            let mut cursor = self.db.connection.prepare(statement)?.into_cursor();
+            cursor.bind(&values)?;
+            cursor.next()?;
+            self.written.borrow_mut().insert(path, text);
+        }
+        Ok(())
+    }
+
+    fn path(&self, table : &str, rowid : i64) -> PathBuf {
+        self.root.join(table).join(format!("{}.json", rowid))
+    }
+
+    /// Writes every row whose file differs and removes the files of rows that
+    /// are gone, after opening.
+    fn save_all(&self) -> Result<()> {
+        let mut files = HashMap::new();
+        files.insert(self.root.join(VERSION), format!("{}\n", dbhandler::version(&self.db)?));
+        for table in tables(&self.db)? {
+            let columns = columns(&table, &self.db)?;
+            let mut cursor = self.db.connection.prepare(format!("SELECT rowid, * FROM \"{}\";", table))?.into_cursor();
+            while let Some(row) = cursor.next()? {
+                let rowid = dbhandler::integer(row, 0, &table)?;
+                files.insert(self.path(&table, rowid), row_to_text(&columns, row));
+            }
+        }
+
+        let mut written = self.written.borrow_mut();
+        for path in written.keys().filter(|path| !files.contains_key(*path)) {
+            remove(path)?;
+        }
+        for (path, text) in files.iter() {
+            if written.get(path) != Some(text) {
+                write(path, text)?;
+            }
+        }
+        *written = files;
+        Ok(())
+    }
+
+    /// Writes the files of the rows noted by `track` since the last save, or
+    /// removes them for rows that are gone.
+    fn save(&self) -> Result<()> {
+        let mut changed = vec![];
+        let mut cursor = self.db.connection.prepare("SELECT DISTINCT name, id FROM Changed;")?.into_cursor();
+        while let Some(row) = cursor.next()? {
+            changed.push((dbhandler::text(row, 0, "Changed")?, dbhandler::integer(row, 1, "Changed")?));
+        }
+        self.db.connection.execute("DELETE FROM Changed;")?;
+
+        let mut written = self.written.borrow_mut();
+        for (table, rowid) in changed {
+            let path = self.path(&table, rowid);
+            let columns = columns(&table, &self.db)?;
+            let mut cursor = self.db.connection.prepare(format!("SELECT rowid, * FROM \"{}\" WHERE rowid = ?;", table))?.into_cursor();
+            cursor.bind(&[Value::Integer(rowid)])?;
+            match cursor.next()? {
+                Some(row) => {
+                    let text = row_to_text(&columns, row);
+                    if written.get(&path) != Some(&text) {
+                        write(&path, &text)?;
+                        written.insert(path, text);
+                    }
+                }
+                None => {
+                    remove(&path)?;
+                    written.remove(&path);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Saves after a change that went through.
+    fn saved<T>(&self, result : Result<T>) -> Result<T> {
+        let value = result?;
+        self.save()?;
+        Ok(value)
+    }
+}
+
+impl Storage for Files {
+
+    fn get_user(&self, id : i64) -> Result<Option<User>> {
+        self.db.get_user(id)
+    }
+
+    fn create_user(&self, id : i64, name : &str) -> Result<User> {
+        self.saved(self.db.create_user(id, name))
+    }
+
+    fn create_workspace(&self, user : &User, name : &str) -> Result<i64> {
+        self.saved(self.db.create_workspace(user, name))
+    }
+
+    fn get_all_workspaces(&self, id_user : i64) -> Result<Vec<Workspace>> {
+        self.db.get_all_workspaces(id_user)
+    }
+
+    fn rename_workspace(&self, id : i64, name : &str) -> Result<()> {
+        self.saved(self.db.rename_workspace(id, name))
+    }
+
+    fn delete_workspace(&self, id : i64) -> Result<()> {
+        self.saved(self.db.delete_workspace(id))
+    }
+
+    fn create_collection(&self, name : &str, id_workspace : i64) -> Result<i64> {
+        self.saved(self.db.create_collection(name, id_workspace))
+    }
+
+    fn get_all_collections(&self, id_workspace : i64) -> Result<Vec<Collection>> {
+        self.db.get_all_collections(id_workspace)
+    }
+
+    fn get_collection(&self, id : i64) -> Result<Option<Collection>> {
+        self.db.get_collection(id)
+    }
+
+    fn get_collection_workspace(&self, id : i64) -> Result<Option<i64>> {
+        self.db.get_collection_workspace(id)
+    }
+
+    fn rename_collection(&self, id : i64, name : &str) -> Result<()> {
+        self.saved(self.db.rename_collection(id, name))
+    }
+
+    fn move_collection(&self, id : i64, id_workspace : i64) -> Result<()> {
+        self.saved(self.db.move_collection(id, id_workspace))
+    }
+
+    fn duplicate_collection(&self, id : i64, name : &str, id_workspace : i64) -> Result<i64> {
+        self.saved(self.db.duplicate_collection(id, name, id_workspace))
+    }
+
+    fn delete_collection(&self, id : i64) -> Result<()> {
+        self.saved(self.db.delete_collection(id))
+    }
+
+    fn create_folder(&self, name : &str, id_collection : i64, id_parent : Option<i64>) -> Result<i64> {
+        self.saved(self.db.create_folder(name, id_collection, id_parent))
+    }
+
+    fn get_all_folders(&self, id_collection : i64) -> Result<Vec<Folder>> {
+        self.db.get_all_folders(id_collection)
+    }
+
+    fn rename_folder(&self, id : i64, name : &str) -> Result<()> {
+        self.saved(self.db.rename_folder(id, name))
+    }
+
+    fn delete_folder(&self, id : i64) -> Result<()> {
+        self.saved(self.db.delete_folder(id))
+    }
+
+    fn create_request(&self, name : &str, id_collection : i64, id_folder : Option<i64>, method : &str, url : &str) -> Result<i64> {
+        self.saved(self.db.create_request(name, id_collection, id_folder, method, url))
+    }
+
+    fn create_graphql_request(&self, name : &str, id_collection : i64, id_folder : Option<i64>, url : &str) -> Result<i64> {
+        self.saved(self.db.create_graphql_request(name, id_collection, id_folder, url))
+    }
+
+    fn create_websocket_request(&self, name : &str, id_collection : i64, id_folder : Option<i64>, url : &str) -> Result<i64> {
+        self.saved(self.db.create_websocket_request(name, id_collection, id_folder, url))
+    }
+
+    fn get_request(&self, id : i64) -> Result<Request> {
+        self.db.get_request(id)
+    }
+
+    fn get_all_requests(&self, id_collection : i64) -> Result<Vec<Request>> {
+        self.db.get_all_requests(id_collection)
+    }
+
+    fn update_request_body(&self, id : i64, body_mode : &BodyMode, body : &str) -> Result<()> {
+        self.saved(self.db.update_request_body(id, body_mode, body))
+    }
+
+    fn update_request_headers(&self, id : i64, headers : &str) -> Result<()> {
+        self.saved(self.db.update_request_headers(id, headers))
+    }
+
+    fn update_request_variables(&self, id : i64, variables : &str) -> Result<()> {
+        self.saved(self.db.update_request_variables(id, variables))
+    }
+
+    fn rename_request(&self, id : i64, name : &str) -> Result<()> {
+        self.saved(self.db.rename_request(id, name))
+    }
+
+    fn move_request(&self, id : i64, id_collection : i64, id_folder : Option<i64>) -> Result<()> {
+        self.saved(self.db.move_request(id, id_collection, id_folder))
+    }
+
+    fn duplicate_request(&self, id : i64, name : &str, id_collection : i64, id_folder : Option<i64>) -> Result<i64> {
+        self.saved(self.db.duplicate_request(id, name, id_collection, id_folder))
+    }
+
+    fn delete_request(&self, id : i64) -> Result<()> {
+        self.saved(self.db.delete_request(id))
+    }
+
+    fn trash(&self, kind : Kind, id : i64) -> Result<()> {
+        self.saved(self.db.trash(kind, id))
+    }
+
+    fn restore(&self, kind : Kind, id : i64) -> Result<()> {
+        self.saved(self.db.restore(kind, id))
+    }
+
+    fn get_trash(&self, id_user : i64) -> Result<Vec<Trashed>> {
+        self.db.get_trash(id_user)
+    }
+
+    fn add_command(&self, command : &str) -> Result<()> {
+        self.saved(self.db.add_command(command))
+    }
+
+    fn get_commands(&self) -> Result<Vec<String>> {
+        self.db.get_commands()
+    }
+
+    fn add_sent(&self, sent : &Sent) -> Result<()> {
+        self.saved(self.db.add_sent(sent))
+    }
+
+    fn get_sent(&self, id_request : i64) -> Result<Vec<Sent>> {
+        self.db.get_sent(id_request)
+    }
+
+    fn create_environment(&self, id_workspace : i64, name : &str, variables : &HashMap<String, String>) -> Result<i64> {
+        self.saved(self.db.create_environment(id_workspace, name, variables))
+    }
+
+    fn get_environments(&self, id_workspace : i64) -> Result<Vec<Environment>> {
+        self.db.get_environments(id_workspace)
+    }
+
+    fn update_environment(&self, id : i64, variables : &HashMap<String, String>) -> Result<()> {
+        self.saved(self.db.update_environment(id, variables))
+    }
+
+    fn delete_environment(&self, id : i64) -> Result<()> {
+        self.saved(self.db.delete_environment(id))
+    }
+
+    fn create_example(&self, example : &Example) -> Result<()> {
+        self.saved(self.db.create_example(example))
+    }
+
+    fn update_example(&self, example : &Example) -> Result<()> {
+        self.saved(self.db.update_example(example))
+    }
+
+    fn delete_example(&self, id : i64) -> Result<()> {
+        self.saved(self.db.delete_example(id))
+    }
+
+    fn get_examples(&self, id_request : i64) -> Result<Vec<Example>> {
+        self.db.get_examples(id_request)
+    }
+
+    fn get_script(&self, owner : Owner, id_owner : i64, stage : Stage) -> Result<String> {
+        self.db.get_script(owner, id_owner, stage)
+    }
+
+    fn save_script(&self, owner : Owner, id_owner : i64, stage : Stage, source : &str) -> Result<()> {
+        self.saved(self.db.save_script(owner, id_owner, stage, source))
+    }
+
+    fn get_schema(&self, url : &str) -> Result<Option<String>> {
+        self.db.get_schema(url)
+    }
+
+    fn save_schema(&self, url : &str, schema : &str) -> Result<()> {
+        self.saved(self.db.save_schema(url, schema))
+    }
+
+    fn get_selection(&self, id_user : i64) -> Result<Option<Selection>> {
+        self.db.get_selection(id_user)
+    }
+
+    fn save_selection(&self, id_user : i64, selection : &Selection) -> Result<()> {
+        self.saved(self.db.save_selection(id_user, selection))
+    }
+
+    fn save_messages(&self, id_request : i64, messages : &[Message]) -> Result<()> {
+        self.saved(self.db.save_messages(id_request, messages))
+    }
+
+    fn get_messages(&self, id_request : i64) -> Result<Vec<Message>> {
+        self.db.get_messages(id_request)
+    }
+}
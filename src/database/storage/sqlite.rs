@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use crate::util::dbhandler::Database;
+use crate::database::container::*;
+use crate::database::environment::{self, Environment};
+use crate::database::example::{self, Example};
+use crate::database::graphql;
+use crate::database::history::{self, Sent};
+use crate::database::script::{self, Owner, Stage};
+use crate::database::selection::{self, Selection};
+use crate::database::trash::{self, Kind, Trashed};
+use crate::database::user::{self, User};
+use crate::database::websocket::{self, Message};
+
+use crate::error::Result;
+use super::Storage;
+
+/// The sqlite database, on file or in memory, backs the storage through the
+/// functions of the `database` modules.
+impl Storage for Database {
+
+    fn get_user(&self, id : i64) -> Result<Option<User>> {
+        user::get_user(id, self)
+    }
+
+    fn create_user(&self, id : i64, name : &str) -> Result<User> {
+        user::create_user(id, name, self)
+    }
+
+    fn create_workspace(&self, user : &User, name : &str) -> Result<i64> {
+        create_workspace(user, name, self)
+    }
+
+    fn get_all_workspaces(&self, id_user : i64) -> Result<Vec<Workspace>> {
+        get_all_workspaces(id_user, self)
+    }
+
+    fn rename_workspace(&self, id : i64, name : &str) -> Result<()> {
+        rename_workspace(id, name, self)
+    }
+
+    fn delete_workspace(&self, id : i64) -> Result<()> {
+        delete_workspace(id, self)
+    }
+
+    fn create_collection(&self, name : &str, id_workspace : i64) -> Result<i64> {
+        create_collection(name, id_workspace, self)
+    }
+
+    fn get_all_collections(&self, id_workspace : i64) -> Result<Vec<Collection>> {
+        get_all_collections(id_workspace, self)
+    }
+
+    fn get_collection(&self, id : i64) -> Result<Option<Collection>> {
+        get_collection(id, self)
+    }
+
+    fn get_collection_workspace(&self, id : i64) -> Result<Option<i64>> {
+        get_collection_workspace(id, self)
+    }
+
+    fn rename_collection(&self, id : i64, name : &str) -> Result<()> {
+        rename_collection(id, name, self)
+    }
+
+    fn move_collection(&self, id : i64, id_workspace : i64) -> Result<()> {
+        move_collection(id, id_workspace, self)
+    }
+
+    fn duplicate_collection(&self, id : i64, name : &str, id_workspace : i64) -> Result<i64> {
+        duplicate_collection(id, name, id_workspace, self)
+    }
+
+    fn delete_collection(&self, id : i64) -> Result<()> {
+        delete_collection(id, self)
+    }
+
+    fn create_folder(&self, name : &str, id_collection : i64, id_parent : Option<i64>) -> Result<i64> {
+        create_folder(name, id_collection, id_parent, self)
+    }
+
+    fn get_all_folders(&self, id_collection : i64) -> Result<Vec<Folder>> {
+        get_all_folders(id_collection, self)
+    }
+
+    fn rename_folder(&self, id : i64, name : &str) -> Result<()> {
+        rename_folder(id, name, self)
+    }
+
+    fn delete_folder(&self, id : i64) -> Result<()> {
+        delete_folder(id, self)
+    }
+
+    fn create_request(&self, name : &str, id_collection : i64, id_folder : Option<i64>, method : &str, url : &str) -> Result<i64> {
+        create_request(name, id_collection, id_folder, method, url, self)
+    }
+
+    fn create_graphql_request(&self, name : &str, id_collection : i64, id_folder : Option<i64>, url : &str) -> Result<i64> {
+        create_graphql_request(name, id_collection, id_folder, url, self)
+    }
+
+    fn create_websocket_request(&self, name : &str, id_collection : i64, id_folder : Option<i64>, url : &str) -> Result<i64> {
+        create_websocket_request(name, id_collection, id_folder, url, self)
+    }
+
+    fn get_request(&self, id : i64) -> Result<Request> {
+        get_request(id, self)
+    }
+
+    fn get_all_requests(&self, id_collection : i64) -> Result<Vec<Request>> {
+        get_all_requests(id_collection, self)
+    }
+
+    fn update_request_body(&self, id : i64, body_mode : &BodyMode, body : &str) -> Result<()> {
+        update_request_body(id, body_mode, body, self)
+    }
+
+    fn update_request_headers(&self, id : i64, headers : &str) -> Result<()> {
+        update_request_headers(id, headers, self)
+    }
+
+    fn update_request_variables(&self, id : i64, variables : &str) -> Result<()> {
+        update_request_variables(id, variables, self)
+    }
+
+    fn rename_request(&self, id : i64, name : &str) -> Result<()> {
+        rename_request(id, name, self)
+    }
+
+    fn move_request(&self, id : i64, id_collection : i64, id_folder : Option<i64>) -> Result<()> {
+        move_request(id, id_collection, id_folder, self)
+    }
+
+    fn duplicate_request(&self, id : i64, name : &str, id_collection : i64, id_folder : Option<i64>) -> Result<i64> {
+        duplicate_request(id, name, id_collection, id_folder, self)
+    }
+
+    fn delete_request(&self, id : i64) -> Result<()> {
+        delete_request(id, self)
+    }
+
+    fn trash(&self, kind : Kind, id : i64) -> Result<()> {
+        trash::trash(kind, id, self)
+    }
+
+    fn restore(&self, kind : Kind, id : i64) -> Result<()> {
+        trash::restore(kind, id, self)
+    }
+
+    fn get_trash(&self, id_user : i64) -> Result<Vec<Trashed>> {
+        trash::get_trash(id_user, self)
+    }
+
+    fn add_command(&self, command : &str) -> Result<()> {
+        history::add_command(command, self)
+    }
+
+    fn get_commands(&self) -> Result<Vec<String>> {
+        history::get_commands(self)
+    }
+
+    fn add_sent(&self, sent : &Sent) -> Result<()> {
+        history::add_sent(sent, self)
+    }
+
+    fn get_sent(&self, id_request : i64) -> Result<Vec<Sent>> {
+        history::get_sent(id_request, self)
+    }
+
+    fn create_environment(&self, id_workspace : i64, name : &str, variables : &HashMap<String, String>) -> Result<i64> {
+        environment::create_environment(id_workspace, name, variables, self)
+    }
+
+    fn get_environments(&self, id_workspace : i64) -> Result<Vec<Environment>> {
+        environment::get_environments(id_workspace, self)
+    }
+
+    fn update_environment(&self, id : i64, variables : &HashMap<String, String>) -> Result<()> {
+        environment::update_environment(id, variables, self)
+    }
+
+    fn delete_environment(&self, id : i64) -> Result<()> {
+        environment::delete_environment(id, self)
+    }
+
+    fn create_example(&self, example : &Example) -> Result<()> {
+        example::create_example(example, self)
+    }
+
+    fn update_example(&self, example : &Example) -> Result<()> {
+        example::update_example(example, self)
+    }
+
+    fn delete_example(&self, id : i64) -> Result<()> {
+        example::delete_example(id, self)
+    }
+
+    fn get_examples(&self, id_request : i64) -> Result<Vec<Example>> {
+        example::get_examples(id_request, self)
+    }
+
+    fn get_script(&self, owner : Owner, id_owner : i64, stage : Stage) -> Result<String> {
+        script::get_script(owner, id_owner, stage, self)
+    }
+
+    fn save_script(&self, owner : Owner, id_owner : i64, stage : Stage, source : &str) -> Result<()> {
+        script::save_script(owner, id_owner, stage, source, self)
+    }
+
+    fn get_schema(&self, url : &str) -> Result<Option<String>> {
+        graphql::get_schema(url, self)
+    }
+
+    fn save_schema(&self, url : &str, schema : &str) -> Result<()> {
+        graphql::save_schema(url, schema, self)
+    }
+
+    fn get_selection(&self, id_user : i64) -> Result<Option<Selection>> {
+        selection::get_selection(id_user, self)
+    }
+
+    fn save_selection(&self, id_user : i64, selection : &Selection) -> Result<()> {
+        selection::save_selection(id_user, selection, self)
+    }
+
+    fn save_messages(&self, id_request : i64, messages : &[Message]) -> Result<()> {
+        websocket::save_messages(id_request, messages, self)
+    }
+
+    fn get_messages(&self, id_request : i64) -> Result<Vec<Message>> {
+        websocket::get_messages(id_request, self)
+    }
+}
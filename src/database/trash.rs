@@ -0,0 +1,140 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::util::dbhandler::*;
+use crate::util::time::now_millis;
+
+use sqlite::*;
+use crate::error::Result;
+
+/// What can be put in the trash.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Kind {
+    Workspace,
+    Collection,
+    Folder,
+    Request,
+}
+
+impl Kind {
+
+    fn table(&self) -> &'static str {
+        match self {
+            Kind::Workspace => "Workspace",
+            Kind::Collection => "Collection",
+            Kind::Folder => "Folder",
+            Kind::Request => "Request",
+        }
+    }
+
+    /// The table joined up to the users of the workspace holding each row.
+    fn owners(&self) -> &'static str {
+        match self {
+            Kind::Workspace => "Workspace JOIN User_Workspace ON User_Workspace.id_workspace = Workspace.id",
+            Kind::Collection => "Collection JOIN User_Workspace ON User_Workspace.id_workspace = Collection.id_workspace",
+            Kind::Folder => "Folder JOIN Collection ON Collection.id = Folder.id_collection
+                             JOIN User_Workspace ON User_Workspace.id_workspace = Collection.id_workspace",
+            Kind::Request => "Request JOIN Collection ON Collection.id = Request.id_collection
+                              JOIN User_Workspace ON User_Workspace.id_workspace = Collection.id_workspace",
+        }
+    }
+}
+
+impl FromStr for Kind {
+    type Err = ();
+    fn from_str(input : &str) -> std::result::Result<Kind, Self::Err> {
+        match input {
+            "workspace" => Ok(Kind::Workspace),
+            "collection" => Ok(Kind::Collection),
+            "folder" => Ok(Kind::Folder),
+            "request" => Ok(Kind::Request),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.table().to_lowercase())
+    }
+}
+
+/// An item in the trash. What it holds is not listed, it comes back with it.
+#[derive(Clone)]
+pub struct Trashed {
+    pub kind : Kind,
+    pub id : i64,
+    pub name : String,
+    /// When it was put in the trash, in milliseconds.
+    pub deleted : i64,
+}
+
+fn set_deleted(
+    kind : Kind,
+    id : i64,
+    deleted : Value,
+    db : &Database) -> Result<()> {
+
+    let statement = db.connection.prepare(format!("UPDATE {} SET deleted = :deleted WHERE id = :id;", kind.table()))?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":deleted", deleted),
+    (":id", Value::Integer(id)),
+    ])?;
+    cursor.next()?;
+    Ok(())
+}
+
+/// Puts an item in the trash, hiding it and what it holds.
+///
+/// * `kind` - what the item is.
+/// * `id` - the i64 id of the item.
+/// * `db` - Database to work on.
+pub fn trash(
+    kind : Kind,
+    id : i64,
+    db : &Database) -> Result<()> {
+
+    set_deleted(kind, id, Value::Integer(now_millis()), db)
+}
+
+/// Takes an item out of the trash.
+///
+/// * `kind` - what the item is.
+/// * `id` - the i64 id of the item.
+/// * `db` - Database to work on.
+pub fn restore(
+    kind : Kind,
+    id : i64,
+    db : &Database) -> Result<()> {
+
+    set_deleted(kind, id, Value::Null, db)
+}
+
+/// Fetches the items a user put in the trash, last deleted first.
+///
+/// * `id_user` - the i64 id of the user whose workspaces hold the items.
+/// * `db` - Database to work on.
+pub fn get_trash(
+    id_user : i64,
+    db : &Database) -> Result<Vec<Trashed>> {
+
+    let mut trashed = vec![];
+    for kind in [Kind::Workspace, Kind::Collection, Kind::Folder, Kind::Request].iter() {
+        let statement = db.connection.prepare(format!("SELECT {table}.id, {table}.name, {table}.deleted FROM {owners}
+                                                       WHERE {table}.deleted IS NOT NULL AND User_Workspace.id_user = :id_user;",
+                                                       table = kind.table(), owners = kind.owners()))?;
+        let mut cursor = statement.into_cursor();
+        cursor.bind_by_name(vec![(":id_user", Value::Integer(id_user))])?;
+        while let Some(row) = cursor.next()? {
+            trashed.push(Trashed {
+                kind : *kind,
+                id : integer(row, 0, kind.table())?,
+                name : text(row, 1, kind.table())?,
+                deleted : integer(row, 2, kind.table())?,
+            });
+        }
+    }
+    trashed.sort_by_key(|item| std::cmp::Reverse(item.deleted));
+    Ok(trashed)
+}
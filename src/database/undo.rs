@@ -0,0 +1,147 @@
+use std::fmt;
+
+use crate::database::container::*;
+use crate::database::storage::Storage;
+use crate::database::trash::Kind;
+
+use crate::error::Result;
+
+/// Number of edits that can be undone, older ones are forgotten.
+const UNDO_SIZE : usize = 100;
+
+/// An edit made in the session, with what it takes to revert it.
+#[derive(Clone)]
+pub enum Edit {
+    /// An item was added, or copied, with its name.
+    Create(Kind, i64, String),
+    /// An item was put in the trash, with its name.
+    Delete(Kind, i64, String),
+    /// An item was renamed, from the first name to the second.
+    Rename(Kind, i64, String, String),
+    /// A collection moved from a workspace to another.
+    MoveCollection(i64, String, i64, i64),
+    /// A request moved from a collection and folder to others.
+    MoveRequest(i64, String, (i64, Option<i64>), (i64, Option<i64>)),
+    /// The body, headers or variables of a request changed, the request
+    /// before and after.
+    Request(Box<Request>, Box<Request>),
+}
+
+impl fmt::Display for Edit {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Edit::Create(kind, _, name) => write!(f, "add {} {}", kind, name),
+            Edit::Delete(kind, _, name) => write!(f, "rm {} {}", kind, name),
+            Edit::Rename(kind, _, from, to) => write!(f, "rename {} {} to {}", kind, from, to),
+            Edit::MoveCollection(_, name, ..) => write!(f, "mv collection {}", name),
+            Edit::MoveRequest(_, name, ..) => write!(f, "mv request {}", name),
+            Edit::Request(_, after) => write!(f, "edit request {}", after.name),
+        }
+    }
+}
+
+fn rename(
+    kind : Kind,
+    id : i64,
+    name : &str,
+    db : &dyn Storage) -> Result<()> {
+
+    match kind {
+        Kind::Workspace => db.rename_workspace(id, name),
+        Kind::Collection => db.rename_collection(id, name),
+        Kind::Folder => db.rename_folder(id, name),
+        Kind::Request => db.rename_request(id, name),
+    }
+}
+
+/// Saves the fields of a request that can be edited.
+fn update_request(
+    request : &Request,
+    db : &dyn Storage) -> Result<()> {
+
+    db.update_request_body(request.id, &request.body_mode, &request.body)?;
+    db.update_request_headers(request.id, &request.headers)?;
+    db.update_request_variables(request.id, &request.variables)
+}
+
+impl Edit {
+
+    /// Makes the edit again.
+    fn apply(&self, db : &dyn Storage) -> Result<()> {
+        match self {
+            Edit::Create(kind, id, _) => db.restore(*kind, *id),
+            Edit::Delete(kind, id, _) => db.trash(*kind, *id),
+            Edit::Rename(kind, id, _, to) => rename(*kind, *id, to, db),
+            Edit::MoveCollection(id, _, _, to) => db.move_collection(*id, *to),
+            Edit::MoveRequest(id, _, _, (id_collection, id_folder)) => db.move_request(*id, *id_collection, *id_folder),
+            Edit::Request(_, after) => update_request(after, db),
+        }
+    }
+
+    /// Takes the edit back. Created items go to the trash, so that redoing
+    /// brings them back as they were.
+    fn revert(&self, db : &dyn Storage) -> Result<()> {
+        match self {
+            Edit::Create(kind, id, _) => db.trash(*kind, *id),
+            Edit::Delete(kind, id, _) => db.restore(*kind, *id),
+            Edit::Rename(kind, id, from, _) => rename(*kind, *id, from, db),
+            Edit::MoveCollection(id, _, from, _) => db.move_collection(*id, *from),
+            Edit::MoveRequest(id, _, (id_collection, id_folder), _) => db.move_request(*id, *id_collection, *id_folder),
+            Edit::Request(before, _) => update_request(before, db),
+        }
+    }
+}
+
+/// Edits of the session that can be undone, and those undone that can be
+/// redone until a new edit is made.
+#[derive(Default)]
+pub struct Undo {
+    done : Vec<Edit>,
+    undone : Vec<Edit>,
+}
+
+impl Undo {
+
+    pub fn record(&mut self, edit : Edit) {
+        self.done.push(edit);
+        if self.done.len() > UNDO_SIZE {
+            self.done.remove(0);
+        }
+        self.undone.clear();
+    }
+
+    /// Reverts the last edit, returned unless there was none.
+    ///
+    /// * `db` - Database to work on.
+    pub fn undo(&mut self, db : &dyn Storage) -> Result<Option<Edit>> {
+        match self.done.pop() {
+            Some(edit) => {
+                if let Err(e) = edit.revert(db) {
+                    self.done.push(edit);
+                    return Err(e);
+                }
+                self.undone.push(edit.clone());
+                Ok(Some(edit))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Makes the last undone edit again, returned unless there was none.
+    ///
+    /// * `db` - Database to work on.
+    pub fn redo(&mut self, db : &dyn Storage) -> Result<Option<Edit>> {
+        match self.undone.pop() {
+            Some(edit) => {
+                if let Err(e) = edit.apply(db) {
+                    self.undone.push(edit);
+                    return Err(e);
+                }
+                self.done.push(edit.clone());
+                Ok(Some(edit))
+            }
+            None => Ok(None),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::util::dbhandler::*;
 
 use sqlite::*;
+use crate::error::Result;
 use std::*;
 
 pub struct User {
@@ -14,59 +15,69 @@ pub struct User {
 ///
 /// user_id - the user's id
 /// 
-/// Returns a User struct instance.
+/// Returns a User struct instance, None if there is no such user.
 pub fn get_user(
     user_id : i64,
     db : &Database,
     ) -> Result<Option<User>> {
 
-    let statement = db.connection.prepare("SELECT * FROM User WHERE id = :user_id;").unwrap();
+    let statement = db.connection.prepare("SELECT * FROM User WHERE id = :user_id;")?;
     let mut cursor = statement.into_cursor();
     cursor.bind_by_name(vec![(":user_id", Value::Integer(user_id.into()))])?;
 
-    match cursor.next().unwrap() {
-        Some(row) => {
-            let user = User {
-                id : row[0].as_integer().unwrap().to_owned(),
-                name : row[1].as_string().unwrap().to_owned(),
-                email : row[2].as_string().unwrap().to_owned(),
-
-            };
-            Ok(Some(user))
-        }
-        //
-        // This is freaking weird. Trying to make proper error handling but it's
-        // a freaking mess.
-        None => Err(Error { 
-            code : Some(1),
-            message : Some(String::from("Something went wrong")),
-        })
+    match cursor.next()? {
+        Some(row) => Ok(Some(user_from_row(row)?)),
+        None => Ok(None),
     }
 }
 
+/// Builds a User from a `SELECT *` row of the User table.
+fn user_from_row(row : &[Value]) -> Result<User> {
+    Ok(User {
+        id : integer(row, 0, "User")?,
+        name : text(row, 1, "User")?,
+        email : row[2].as_string().unwrap_or_default().to_owned(),
+    })
+}
+
+/// Creates a User with a given id, used to set up a new database.
+///
+/// * `user_id` - the id of the user.
+/// * `name` - the name of the user.
+/// * `db` - Database to work on.
+pub fn create_user(
+    user_id : i64,
+    name : &str,
+    db : &Database) -> Result<User> {
+
+    let statement = db.connection.prepare("INSERT INTO User(id, name, email) VALUES (:id, :name, '');")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id", Value::Integer(user_id)),
+    (":name", Value::String(name.to_owned())),
+    ])?;
+    cursor.next()?;
+
+    Ok(User {
+        id : user_id,
+        name : name.to_owned(),
+        email : String::new(),
+    })
+}
+
 fn get_user_from_credentials(
     user_name : &str,
     db : &Database,
-    ) -> Option<User> {
+    ) -> Result<Option<User>> {
 
     
-    let statement = db.connection.prepare("SELECT * FROM User WHERE id = :user_id;").unwrap();
+    let statement = db.connection.prepare("SELECT * FROM User WHERE id = :user_id;")?;
     let mut cursor = statement.into_cursor();
-    cursor.bind_by_name(vec![(":user_id", Value::String(user_name.into()))]);
+    cursor.bind_by_name(vec![(":user_id", Value::String(user_name.into()))])?;
 
     
-    if let Some(row) = cursor.next().unwrap() {
-
-        let user = User {
-            id : row[0].as_integer().unwrap().to_owned(),
-            name : row[1].as_string().unwrap().to_owned(),
-            email : row[2].as_string().unwrap().to_owned(),
-
-        };
-        Some(user)
-    }
-    else {
-        None
+    match cursor.next()? {
+        Some(row) => Ok(Some(user_from_row(row)?)),
+        None => Ok(None),
     }
 }
 
@@ -0,0 +1,98 @@
+use std::str::FromStr;
+use crate::util::dbhandler::*;
+
+use std::fmt;
+
+use sqlite::*;
+use crate::error::Result;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Direction {
+    Sent,
+    Received,
+    /// Connection events such as opening, pings and closing.
+    Info,
+}
+
+impl FromStr for Direction {
+    type Err = ();
+    fn from_str(input : &str) -> std::result::Result<Direction, Self::Err> {
+        match input {
+            "SENT" => Ok(Direction::Sent),
+            "RECEIVED" => Ok(Direction::Received),
+            "INFO" => Ok(Direction::Info),
+            _      => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&format!("{:?}", self).to_uppercase())
+    }
+}
+
+/// A line of a WebSocket session log.
+#[derive(Clone)]
+pub struct Message {
+    pub direction : Direction,
+    pub content : String,
+    /// Milliseconds since the unix epoch.
+    pub time : i64,
+}
+
+/// Replaces the saved session of a WebSocket Request.
+///
+/// * `id_request` - the i64 id of the request.
+/// * `messages` - the session log to save.
+/// * `db` - Database to work on.
+pub fn save_messages(
+    id_request : i64,
+    messages : &[Message],
+    db : &Database) -> Result<()> {
+
+    transaction(db, || {
+        let statement = db.connection.prepare("DELETE FROM Websocket_Message WHERE id_request = :id_request;")?;
+        let mut cursor = statement.into_cursor();
+        cursor.bind_by_name(vec![(":id_request", Value::Integer(id_request))])?;
+        cursor.next()?;
+
+        for message in messages.iter() {
+            let statement = db.connection.prepare("INSERT INTO Websocket_Message(id_request, direction, content, time)
+                                                   VALUES (:id_request, :direction, :content, :time);")?;
+            let mut cursor = statement.into_cursor();
+            cursor.bind_by_name(vec![(":id_request", Value::Integer(id_request)),
+            (":direction", Value::String(message.direction.to_string())),
+            (":content", Value::String(message.content.clone())),
+            (":time", Value::Integer(message.time)),
+            ])?;
+            cursor.next()?;
+        }
+        Ok(())
+    })
+}
+
+/// Fetches the saved session of a WebSocket Request, oldest message first.
+///
+/// * `id_request` - the i64 id of the request.
+/// * `db` - Database to work on.
+pub fn get_messages(
+    id_request : i64,
+    db : &Database) -> Result<Vec<Message>> {
+
+    let mut messages = vec![];
+    let statement = db.connection.prepare("SELECT direction, content, time FROM Websocket_Message
+                                           WHERE id_request = :id_request ORDER BY id;")?;
+    let mut cursor = statement.into_cursor();
+    cursor.bind_by_name(vec![(":id_request", Value::Integer(id_request))])?;
+
+    while let Some(row) = cursor.next()? {
+        messages.push(Message {
+            direction : Direction::from_str(row[0].as_string().unwrap_or_default()).unwrap_or(Direction::Info),
+            content : row[1].as_string().unwrap_or_default().to_owned(),
+            time : row[2].as_integer().unwrap_or_default(),
+        });
+    }
+    Ok(messages)
+}
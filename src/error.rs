@@ -0,0 +1,52 @@
+use std::fmt;
+use std::io;
+
+/// Primary result code of sqlite for a refused constraint.
+const SQLITE_CONSTRAINT : isize = 19;
+
+/// Errors of the data layer.
+#[derive(Debug)]
+pub enum Error {
+    /// Nothing matched, with what was looked for.
+    NotFound(String),
+    /// The schema refused a change, with sqlite's message.
+    Constraint(String),
+    /// A row does not hold what it should, with where it was read.
+    Corrupt(String),
+    Io(io::Error),
+    /// Any other error of sqlite.
+    Sqlite(sqlite::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NotFound(what) => write!(f, "{} not found", what),
+            Error::Constraint(message) => write!(f, "refused by the database : {}", message),
+            Error::Corrupt(place) => write!(f, "corrupt row in {}", place),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Sqlite(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<sqlite::Error> for Error {
+    fn from(e : sqlite::Error) -> Error {
+        match e.code {
+            // Extended codes keep the primary one in their low byte.
+            Some(code) if code & 0xff == SQLITE_CONSTRAINT => Error::Constraint(e.message.unwrap_or_default()),
+            _ => Error::Sqlite(e),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e : io::Error) -> Error {
+        Error::Io(e)
+    }
+}
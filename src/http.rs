@@ -0,0 +1,9 @@
+pub mod bench;
+pub mod body;
+pub mod client;
+pub mod graphql;
+pub mod mock;
+pub mod runner;
+pub mod script;
+pub mod sse;
+pub mod websocket;
@@ -0,0 +1,431 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Read;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use curl::multi::{EasyHandle, Multi};
+
+use crate::database::container::Request;
+use super::client;
+
+/// How a Request is fired during a benchmark.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Number of requests to send, unless a duration is given.
+    pub count : usize,
+    pub duration : Option<Duration>,
+    /// Requests in flight at the same time.
+    pub concurrency : usize,
+    /// Target requests per second, as fast as possible when None.
+    pub rate : Option<f64>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            count : 100,
+            duration : None,
+            concurrency : 10,
+            rate : None,
+        }
+    }
+}
+
+pub const USAGE : &str = "[-n <count>] [-d <seconds>] [-c <concurrency>] [-r <requests per second>]";
+
+impl Config {
+
+    /// Parses the `-n`, `-d`, `-c` and `-r` flags, see `USAGE`.
+    pub fn from_args(args : &[&str]) -> Result<Config, String> {
+        let mut config = Config::default();
+        let mut args = args.iter();
+        while let Some(&flag) = args.next() {
+            let value = args.next().ok_or(format!("{} needs a value", flag))?;
+            let invalid = || format!("invalid value {} for {}", value, flag);
+            // Durations and rates are finite and above zero.
+            let positive = || value.parse::<f64>().ok().filter(|value| value.is_finite() && *value > 0.0).ok_or_else(invalid);
+            match flag {
+                "-n" | "--count" => config.count = value.parse().map_err(|_| invalid())?,
+                "-d" | "--duration" => config.duration = Some(Duration::try_from_secs_f64(positive()?).map_err(|_| invalid())?),
+                "-c" | "--concurrency" => config.concurrency = value.parse().map_err(|_| invalid())?,
+                "-r" | "--rate" => config.rate = Some(positive()?),
+                _ => return Err(format!("unknown flag {}, usage : {}", flag, USAGE)),
+            }
+        }
+        if config.concurrency == 0 {
+            return Err(String::from("concurrency must be at least 1"));
+        }
+        Ok(config)
+    }
+}
+
+/// Outcome of one request of a benchmark.
+pub struct Sample {
+    /// Time since the benchmark started at which the request finished.
+    pub at : Duration,
+    pub latency : Duration,
+    /// Failed transfers and 4xx/5xx statuses are errors.
+    pub ok : bool,
+}
+
+/// Fires a Request according to a Config using curl's multi interface, handing
+/// each finished request to `on_sample`. Returns early once `stop` is set.
+pub fn run<F>(
+    request : &Request,
+    config : &Config,
+    stop : &AtomicBool,
+    mut on_sample : F) -> Result<(), Box<dyn Error>>
+where
+F : FnMut(Sample),
+{
+    let multi = Multi::new();
+    let start = Instant::now();
+    let mut in_flight : HashMap<usize, EasyHandle> = HashMap::new();
+    let mut launched = 0;
+
+    loop {
+        let elapsed = start.elapsed();
+        let finished_launching = match config.duration {
+            Some(duration) => elapsed >= duration,
+            None => launched >= config.count,
+        } || stop.load(Ordering::Relaxed);
+
+        while !finished_launching && in_flight.len() < config.concurrency {
+            if let Some(rate) = config.rate {
+                if launched as f64 >= rate * elapsed.as_secs_f64() + 1.0 {
+                    break;
+                }
+            }
+            let (mut easy, upload) = client::prepare(request)?;
+            if let Some(mut file) = upload {
+                easy.read_function(move |buf| Ok(file.read(buf).unwrap_or(0)))?;
+            }
+            easy.write_function(|data| Ok(data.len()))?;
+            let mut handle = multi.add(easy)?;
+            handle.set_token(launched)?;
+            in_flight.insert(launched, handle);
+            launched += 1;
+            if config.duration.is_none() && launched >= config.count {
+                break;
+            }
+        }
+
+        if finished_launching && in_flight.is_empty() {
+            return Ok(());
+        }
+
+        multi.perform()?;
+        let mut done = vec![];
+        multi.messages(|message| {
+            if let (Ok(token), Some(result)) = (message.token(), message.result()) {
+                done.push((token, result.is_ok()));
+            }
+        });
+        for (token, transferred) in done {
+            if let Some(mut handle) = in_flight.remove(&token) {
+                let status = handle.response_code().unwrap_or(0);
+                let latency = handle.total_time().unwrap_or_default();
+                multi.remove(handle)?;
+                on_sample(Sample {
+                    at : start.elapsed(),
+                    latency,
+                    ok : transferred && status > 0 && status < 400,
+                });
+            }
+        }
+
+        multi.wait(&mut [], Duration::from_millis(10))?;
+    }
+}
+
+/// Sub-buckets per power of two of `Latencies`, as a number of bits : 32
+/// sub-buckets keep values within about 3 % of what was recorded.
+const SUB_BITS : u32 = 5;
+const SUB : u64 = 1 << SUB_BITS;
+
+/// Latencies counted in buckets whose width grows with their value, in the
+/// manner of an HDR histogram : memory and the cost of reading percentiles
+/// stay bounded however long a run goes on.
+#[derive(Default)]
+struct Latencies {
+    counts : Vec<u64>,
+    total : u64,
+    min : u64,
+    max : u64,
+}
+
+impl Latencies {
+
+    /// The bucket of a value, values under `2 * SUB` having one each.
+    fn index(value : u64) -> usize {
+        if value < 2 * SUB {
+            return value as usize;
+        }
+        let shift = 63 - value.leading_zeros() - SUB_BITS;
+        let mantissa = value >> shift;
+        (2 * SUB + (shift as u64 - 1) * SUB + mantissa - SUB) as usize
+    }
+
+    /// The middle of the values counted in a bucket.
+    fn value(index : usize) -> u64 {
+        let index = index as u64;
+        if index < 2 * SUB {
+            return index;
+        }
+        let shift = (index - 2 * SUB) / SUB + 1;
+        let mantissa = (index - 2 * SUB) % SUB + SUB;
+        (mantissa << shift) + (1 << shift) / 2
+    }
+
+    fn record(&mut self, value : u64) {
+        let index = Latencies::index(value);
+        if self.counts.len() <= index {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+        self.min = if self.total == 0 { value } else { self.min.min(value) };
+        self.max = self.max.max(value);
+        self.total += 1;
+    }
+
+    /// The buckets holding values, as their value and count.
+    fn buckets(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.counts.iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(move |(i, count)| (Latencies::value(i).max(self.min).min(self.max), *count))
+    }
+
+    /// The value under which `p` percent of the values are, to the
+    /// precision of its bucket.
+    fn percentile(&self, p : f64) -> u64 {
+        if self.total == 0 {
+            return 0;
+        }
+        let rank = ((self.total - 1) as f64 * p / 100.0).round() as u64;
+        let mut seen = 0;
+        for (value, count) in self.buckets() {
+            seen += count;
+            if seen > rank {
+                return value;
+            }
+        }
+        self.max
+    }
+}
+
+/// Aggregated results of a benchmark.
+#[derive(Default)]
+pub struct Stats {
+    /// Latencies in microseconds.
+    latencies : Latencies,
+    pub errors : usize,
+    /// Requests finished during each second of the run.
+    pub per_second : Vec<u64>,
+    pub elapsed : Duration,
+}
+
+impl Stats {
+
+    pub fn add(&mut self, sample : &Sample) {
+        self.latencies.record(sample.latency.as_micros() as u64);
+        if !sample.ok {
+            self.errors += 1;
+        }
+        let second = sample.at.as_secs() as usize;
+        if self.per_second.len() <= second {
+            self.per_second.resize(second + 1, 0);
+        }
+        self.per_second[second] += 1;
+        self.elapsed = sample.at;
+    }
+
+    pub fn total(&self) -> usize {
+        self.latencies.total as usize
+    }
+
+    pub fn throughput(&self) -> f64 {
+        match self.elapsed.as_secs_f64() {
+            secs if secs > 0.0 => self.total() as f64 / secs,
+            _ => 0.0,
+        }
+    }
+
+    pub fn error_rate(&self) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => self.errors as f64 / total as f64,
+        }
+    }
+
+    /// Latency under which `p` percent of the requests finished.
+    pub fn percentile(&self, p : f64) -> Duration {
+        Duration::from_micros(self.latencies.percentile(p))
+    }
+
+    /// Request counts for `buckets` latency ranges of equal width, labelled
+    /// with their upper bound in milliseconds.
+    pub fn histogram(&self, buckets : usize) -> Vec<(String, u64)> {
+        if self.latencies.total == 0 {
+            return vec![];
+        }
+        let (min, max) = (self.latencies.min, self.latencies.max);
+        let width = ((max - min) / buckets as u64).max(1);
+        let mut counts = vec![0; buckets];
+        for (latency, count) in self.latencies.buckets() {
+            counts[(((latency - min) / width) as usize).min(buckets - 1)] += count;
+        }
+        counts.into_iter()
+            .enumerate()
+            .map(|(i, count)| (format!("{}", (min + width * (i as u64 + 1)) / 1000), count))
+            .collect()
+    }
+
+    pub fn report(&self) -> String {
+        format!("{} requests in {:.1} s, {:.1} req/s, {:.1} % errors\np50 {} ms  p90 {} ms  p99 {} ms",
+                self.total(),
+                self.elapsed.as_secs_f64(),
+                self.throughput(),
+                self.error_rate() * 100.0,
+                self.percentile(50.0).as_millis(),
+                self.percentile(90.0).as_millis(),
+                self.percentile(99.0).as_millis())
+    }
+}
+
+/// A benchmark running in its own thread, see `Bench::poll`.
+pub struct Bench {
+    samples : Receiver<Sample>,
+    errors : Receiver<String>,
+    stop : Arc<AtomicBool>,
+    pub config : Config,
+    pub stats : Stats,
+    pub error : Option<String>,
+    pub done : bool,
+}
+
+impl Bench {
+
+    /// Starts benchmarking a Request in the background.
+    pub fn spawn(
+        request : &Request,
+        config : Config) -> Bench {
+
+        let (samples_tx, samples) = mpsc::channel();
+        let (errors_tx, errors) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let request = request.clone();
+        let thread_config = config.clone();
+        let thread_stop = stop.clone();
+        thread::spawn(move || {
+            let result = run(&request, &thread_config, &thread_stop, |sample| {
+                let _ = samples_tx.send(sample);
+            });
+            if let Err(e) = result {
+                let _ = errors_tx.send(e.to_string());
+            }
+        });
+
+        Bench {
+            samples,
+            errors,
+            stop,
+            config,
+            stats : Stats::default(),
+            error : None,
+            done : false,
+        }
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// Adds the requests finished since the last call to the stats.
+    pub fn poll(&mut self) {
+        loop {
+            match self.samples.try_recv() {
+                Ok(sample) => self.stats.add(&sample),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+        if let Ok(e) = self.errors.try_recv() {
+            self.error = Some(e);
+        }
+    }
+}
+
+impl Drop for Bench {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(latencies : &[u64]) -> Stats {
+        let mut stats = Stats::default();
+        for (i, latency) in latencies.iter().enumerate() {
+            stats.add(&Sample { latency : Duration::from_micros(*latency), ok : true, at : Duration::from_millis(i as u64) });
+        }
+        stats
+    }
+
+    #[test]
+    fn rejects_durations_that_are_not_positive() {
+        for value in ["-1", "0", "nan", "inf", "1e30", "soon"].iter() {
+            assert_eq!(Config::from_args(&["-d", value]).unwrap_err(), format!("invalid value {} for -d", value));
+        }
+        assert_eq!(Config::from_args(&["-d", "1.5"]).unwrap().duration, Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn rejects_rates_that_are_not_positive() {
+        for value in ["0", "-2", "nan", "inf"].iter() {
+            assert_eq!(Config::from_args(&["--rate", value]).unwrap_err(), format!("invalid value {} for --rate", value));
+        }
+        assert_eq!(Config::from_args(&["-r", "0.5"]).unwrap().rate, Some(0.5));
+    }
+
+    #[test]
+    fn buckets_keep_values_close() {
+        for value in (0..10_000).chain((1..40).map(|i| 1u64 << i)) {
+            let kept = Latencies::value(Latencies::index(value));
+            assert!(kept.abs_diff(value) <= value / SUB + 1, "{} kept as {}", value, kept);
+        }
+    }
+
+    #[test]
+    fn percentiles() {
+        let stats = stats(&(1..=1000).map(|ms| ms * 1000).collect::<Vec<_>>());
+        for (p, expected) in [(50.0, 500.0), (90.0, 900.0), (99.0, 990.0)].iter() {
+            let ms = stats.percentile(*p).as_micros() as f64 / 1000.0;
+            assert!((ms - expected).abs() / expected < 0.04, "p{} is {} ms", p, ms);
+        }
+        assert_eq!(stats.percentile(0.0), Duration::from_millis(1));
+        assert_eq!(stats.percentile(100.0), Duration::from_millis(1000));
+        assert_eq!(Stats::default().percentile(50.0), Duration::default());
+    }
+
+    #[test]
+    fn histogram_counts_every_request() {
+        let stats = stats(&[1000, 1000, 2000, 50_000, 120_000]);
+        let histogram = stats.histogram(8);
+        assert_eq!(histogram.len(), 8);
+        assert_eq!(histogram.iter().map(|(_, count)| count).sum::<u64>(), 5);
+        assert_eq!(histogram[0].1, 3);
+        assert_eq!(histogram[7].1, 1);
+        assert!(Stats::default().histogram(8).is_empty());
+    }
+}
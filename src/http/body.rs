@@ -0,0 +1,143 @@
+use crate::database::container::BodyMode;
+
+/// A single `key=value` line of a form or multipart body.
+pub struct Field {
+    pub name : String,
+    pub value : String,
+    /// Multipart only : the value is the path of a file to upload.
+    pub is_file : bool,
+}
+
+/// Describes the lines of a form or multipart body that are not fields, one
+/// message per line, so that the body can be fixed before it is sent.
+///
+/// A field is `key=value` with a non empty key, and a multipart file part
+/// `key=@path` needs its path.
+///
+/// * `mode` - the body mode, other modes than Form and Multipart have no fields.
+/// * `body` - the body as typed.
+pub fn invalid_fields(mode : &BodyMode, body : &str) -> Vec<String> {
+    if *mode != BodyMode::Form && *mode != BodyMode::Multipart {
+        return vec![];
+    }
+    body.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(i, line)| {
+            let problem = match line.find('=') {
+                None => "is not key=value",
+                Some(j) if line[..j].trim().is_empty() => "has no key",
+                Some(j) if *mode == BodyMode::Multipart && line[j + 1..].trim() == "@" => "names no file",
+                Some(_) => return None,
+            };
+            Some(format!("line {} \"{}\" {}", i + 1, line.trim(), problem))
+        })
+        .collect()
+}
+
+/// Parses the key/value lines of a form or multipart body.
+///
+/// Empty lines are skipped, a line without `=` is a field with an empty value
+/// and a value starting with `@` is a file part. Such lines are refused before
+/// sending, see `invalid_fields`.
+pub fn parse_fields(body : &str) -> Vec<Field> {
+    body.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (name, value) = match line.find('=') {
+                Some(i) => (&line[..i], &line[i + 1..]),
+                None => (line, ""),
+            };
+            match value.strip_prefix('@') {
+                Some(path) => Field { name : name.trim().to_owned(), value : path.to_owned(), is_file : true },
+                None => Field { name : name.trim().to_owned(), value : value.to_owned(), is_file : false },
+            }
+        })
+        .collect()
+}
+
+/// The Content-Type sent for a body mode when the user did not set one.
+///
+/// Multipart is left to curl since the header has to carry the boundary.
+pub fn content_type(mode : &BodyMode) -> Option<&'static str> {
+    match mode {
+        BodyMode::None | BodyMode::Multipart => None,
+        BodyMode::Raw => Some("text/plain"),
+        BodyMode::Json => Some("application/json"),
+        BodyMode::Form => Some("application/x-www-form-urlencoded"),
+        BodyMode::Binary => Some("application/octet-stream"),
+    }
+}
+
+/// Splits `Name: value` header lines, skipping blank ones.
+pub fn header_lines(headers : &str) -> Vec<&str> {
+    headers.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Headers telling how a body went over the wire rather than what it is,
+/// wrong once the body is saved decoded, edited or sent again.
+const FRAMING : [&str; 3] = ["Content-Length", "Transfer-Encoding", "Content-Encoding"];
+
+/// The header lines, without those of `FRAMING`.
+pub fn without_framing(headers : &str) -> Vec<&str> {
+    header_lines(headers).into_iter()
+        .filter(|line| {
+            let name = line.split(':').next().unwrap_or_default().trim();
+            !FRAMING.iter().any(|framing| framing.eq_ignore_ascii_case(name))
+        })
+        .collect()
+}
+
+/// Whether the header lines already set the header `name`, case insensitive.
+pub fn has_header(headers : &str, name : &str) -> bool {
+    header_lines(headers).iter().any(|line| {
+        match line.find(':') {
+            Some(i) => line[..i].trim().eq_ignore_ascii_case(name),
+            None => false,
+        }
+    })
+}
+
+/// Hint shown as the title of the body editor for each mode.
+pub fn editor_title(mode : &BodyMode) -> &'static str {
+    match mode {
+        BodyMode::None => "Body (none)",
+        BodyMode::Raw => "Body (raw text)",
+        BodyMode::Json => "Body (JSON)",
+        BodyMode::Form => "Body (form : key=value per line)",
+        BodyMode::Multipart => "Body (multipart : key=value or key=@file per line)",
+        BodyMode::Binary => "Body (binary : path of the file to send)",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_fields() {
+        let body = "name=turl\n\n  \nempty=\nquery=a=b\nfile=@/tmp/a.txt";
+        assert!(invalid_fields(&BodyMode::Multipart, body).is_empty());
+        assert!(invalid_fields(&BodyMode::Form, body).is_empty());
+        let fields = parse_fields(body);
+        assert_eq!(fields.iter().map(|field| field.name.as_str()).collect::<Vec<_>>(), vec!["name", "empty", "query", "file"]);
+        assert_eq!(fields[2].value, "a=b");
+        assert!(fields[3].is_file && fields[3].value == "/tmp/a.txt");
+    }
+
+    #[test]
+    fn reports_lines_that_are_not_fields() {
+        let body = "name=turl\njust text\n = value\nfile=@";
+        assert_eq!(invalid_fields(&BodyMode::Multipart, body), vec![
+            "line 2 \"just text\" is not key=value",
+            "line 3 \"= value\" has no key",
+            "line 4 \"file=@\" names no file",
+        ]);
+        // A form sends `@` as text.
+        assert_eq!(invalid_fields(&BodyMode::Form, body).len(), 2);
+        assert!(invalid_fields(&BodyMode::Raw, body).is_empty());
+    }
+}
@@ -0,0 +1,407 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::Read;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use curl::easy::{Easy, Form, List};
+
+use crate::database::container::{BodyMode, Methods, Request};
+use super::body;
+
+/// What came back from executing a Request.
+#[derive(Clone, Default)]
+pub struct Response {
+    pub status : u32,
+    pub headers : Vec<String>,
+    pub body : Vec<u8>,
+    pub time : Duration,
+}
+
+impl Response {
+
+    /// Value of a response header, case insensitive.
+    pub fn header(&self, name : &str) -> Option<&str> {
+        self.headers.iter().find_map(|line| {
+            let i = line.find(':')?;
+            if line[..i].trim().eq_ignore_ascii_case(name) {
+                Some(line[i + 1..].trim())
+            }
+            else {
+                None
+            }
+        })
+    }
+}
+
+/// How a method treats the body of a request.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BodyUse {
+    /// POST, PUT, PATCH and custom verbs carry a body, an empty one when the
+    /// request has none.
+    Expected,
+    /// GET, DELETE, OPTIONS and CONNECT may carry one, which many servers
+    /// ignore.
+    Unusual,
+    /// HEAD and TRACE never carry one.
+    Dropped,
+}
+
+impl BodyUse {
+
+    pub fn of(method : &Methods) -> BodyUse {
+        match method {
+            Methods::POST | Methods::PUT | Methods::PATCH | Methods::Custom(_) => BodyUse::Expected,
+            Methods::GET | Methods::DELETE | Methods::OPTIONS | Methods::CONNECT => BodyUse::Unusual,
+            Methods::HEAD | Methods::TRACE => BodyUse::Dropped,
+        }
+    }
+}
+
+/// What to tell the user about the body of a Request about to be sent : that
+/// it is left out, or that servers may ignore it.
+pub fn body_warning(request : &Request) -> Option<String> {
+    if request.body_mode == BodyMode::None {
+        return None;
+    }
+    match BodyUse::of(&request.method) {
+        BodyUse::Expected => None,
+        BodyUse::Unusual => Some(format!("{} with a body, which many servers ignore", request.method)),
+        BodyUse::Dropped => Some(format!("{} is sent without its body", request.method)),
+    }
+}
+
+/// Sets up a curl handle for a Request. Returns the file to stream the body
+/// from for `BodyMode::Binary`.
+///
+/// The body is sent according to the request's BodyMode and a Content-Type
+/// header is added for it unless the request headers already have one, see
+/// `BodyUse` for the methods sending it.
+pub fn prepare(request : &Request) -> Result<(Easy, Option<File>), Box<dyn Error>> {
+    let mut easy = Easy::new();
+    easy.url(&request.url)?;
+
+    let mut headers = List::new();
+    for line in body::header_lines(&request.headers) {
+        headers.append(line)?;
+    }
+    let body_use = BodyUse::of(&request.method);
+    let body_mode = if body_use == BodyUse::Dropped { &BodyMode::None } else { &request.body_mode };
+    if !body::has_header(&request.headers, "Content-Type") {
+        if let Some(content_type) = body::content_type(body_mode) {
+            headers.append(&format!("Content-Type: {}", content_type))?;
+        }
+    }
+    easy.http_headers(headers)?;
+
+    let invalid = body::invalid_fields(body_mode, &request.body);
+    if !invalid.is_empty() {
+        return Err(invalid.join(", ").into());
+    }
+
+    // Binary bodies are streamed from the file during the transfer.
+    let mut upload : Option<File> = None;
+    match body_mode {
+        BodyMode::None => {}
+        BodyMode::Raw | BodyMode::Json => {
+            easy.post_fields_copy(request.body.as_bytes())?;
+        }
+        BodyMode::Form => {
+            let encoded : Vec<String> = body::parse_fields(&request.body)
+                .iter()
+                .map(|field| format!("{}={}",
+                                     easy.url_encode(field.name.as_bytes()),
+                                     easy.url_encode(field.value.as_bytes())))
+                .collect();
+            easy.post_fields_copy(encoded.join("&").as_bytes())?;
+        }
+        BodyMode::Multipart => {
+            let mut form = Form::new();
+            for field in body::parse_fields(&request.body) {
+                if field.is_file {
+                    form.part(&field.name).file(&field.value).add()?;
+                }
+                else {
+                    form.part(&field.name).contents(field.value.as_bytes()).add()?;
+                }
+            }
+            easy.httppost(form)?;
+        }
+        BodyMode::Binary => {
+            let file = File::open(request.body.trim())?;
+            easy.post(true)?;
+            easy.post_field_size(file.metadata()?.len())?;
+            upload = Some(file);
+        }
+    }
+
+    if body_use == BodyUse::Expected && *body_mode == BodyMode::None {
+        easy.post_fields_copy(&[])?;
+    }
+
+    // curl picks GET or POST from the body, anything else is sent as is.
+    match request.method {
+        Methods::GET if *body_mode == BodyMode::None => {}
+        Methods::POST => {}
+        // A HEAD response has headers only, curl must not wait for a body.
+        Methods::HEAD => easy.nobody(true)?,
+        _ => easy.custom_request(&request.method.to_string())?,
+    }
+
+    Ok((easy, upload))
+}
+
+/// Runs a prepared transfer, handing each header block and body chunk over as
+/// they arrive. The transfer is aborted once `stop` is set.
+fn perform<H, D>(
+    easy : &mut Easy,
+    upload : Option<File>,
+    stop : &AtomicBool,
+    mut on_headers : H,
+    mut on_data : D) -> Result<(), curl::Error>
+where
+H : FnMut(u32, Vec<String>),
+D : FnMut(&[u8]),
+{
+    // Called often enough to notice `stop` while the server is quiet.
+    easy.progress(true)?;
+
+    let mut status = 0;
+    let mut headers = Vec::new();
+    let mut transfer = easy.transfer();
+    if let Some(mut file) = upload {
+        transfer.read_function(move |buf| Ok(file.read(buf).unwrap_or(0)))?;
+    }
+    // Redirects and `100 Continue` send several header blocks, each one starts
+    // with a status line and ends with a blank line.
+    transfer.header_function(|header| {
+        let line = String::from_utf8_lossy(header).trim_end().to_owned();
+        if line.starts_with("HTTP/") {
+            status = line.split_whitespace().nth(1).and_then(|code| code.parse().ok()).unwrap_or(0);
+            headers.clear();
+        }
+        if line.is_empty() {
+            on_headers(status, std::mem::take(&mut headers));
+        }
+        else {
+            headers.push(line);
+        }
+        true
+    })?;
+    transfer.write_function(|data| {
+        on_data(data);
+        Ok(data.len())
+    })?;
+    transfer.progress_function(|_, _, _, _| !stop.load(Ordering::Relaxed))?;
+    transfer.perform()
+}
+
+/// Executes a Request with curl and waits for the whole response.
+pub fn execute(request : &Request) -> Result<Response, Box<dyn Error>> {
+    let (mut easy, upload) = prepare(request)?;
+
+    let mut response_headers = Vec::new();
+    let mut response_body = Vec::new();
+    perform(&mut easy, upload, &AtomicBool::new(false),
+            |_, headers| response_headers = headers,
+            |data| response_body.extend_from_slice(data))?;
+
+    Ok(Response {
+        status : easy.response_code()?,
+        headers : response_headers,
+        body : response_body,
+        time : easy.total_time()?,
+    })
+}
+
+/// Progress of a Request running in the background.
+enum Progress {
+    Headers(u32, Vec<String>),
+    Data(Vec<u8>),
+    Done(Result<Duration, String>),
+}
+
+/// A Request executed in its own thread so the body can be shown while it is
+/// still being received, see `Exchange::poll`.
+pub struct Exchange {
+    progress : Receiver<Progress>,
+    stop : Arc<AtomicBool>,
+    /// The response received so far, `time` is only set once done.
+    pub response : Response,
+    pub error : Option<String>,
+    pub done : bool,
+}
+
+fn run(
+    request : Request,
+    stop : Arc<AtomicBool>,
+    progress : Sender<Progress>) {
+
+    let result = prepare(&request).and_then(|(mut easy, upload)| {
+        perform(&mut easy, upload, &stop,
+                |status, headers| { let _ = progress.send(Progress::Headers(status, headers)); },
+                |data| { let _ = progress.send(Progress::Data(data.to_vec())); })?;
+        Ok(easy.total_time()?)
+    });
+
+    let result = match result {
+        Err(_) if stop.load(Ordering::Relaxed) => Err(String::from("stopped")),
+        result => result.map_err(|e| e.to_string()),
+    };
+    let _ = progress.send(Progress::Done(result));
+}
+
+impl Exchange {
+
+    /// Starts executing a Request in the background.
+    pub fn spawn(request : &Request) -> Exchange {
+        let (progress_tx, progress) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let request = request.clone();
+        let thread_stop = stop.clone();
+        thread::spawn(move || run(request, thread_stop, progress_tx));
+
+        Exchange {
+            progress,
+            stop,
+            response : Response {
+                status : 0,
+                headers : vec![],
+                body : vec![],
+                time : Duration::default(),
+            },
+            error : None,
+            done : false,
+        }
+    }
+
+    /// Aborts the transfer, the exchange is done on the next poll.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// Takes in what was received since the last call.
+    ///
+    /// Returns the new body bytes, they are also appended to the response.
+    pub fn poll(&mut self) -> Vec<u8> {
+        let mut received = vec![];
+        loop {
+            match self.progress.try_recv() {
+                Ok(Progress::Headers(status, headers)) => {
+                    self.response.status = status;
+                    self.response.headers = headers;
+                }
+                Ok(Progress::Data(data)) => {
+                    received.extend_from_slice(&data);
+                }
+                Ok(Progress::Done(result)) => {
+                    match result {
+                        Ok(time) => self.response.time = time,
+                        Err(e) => self.error = Some(e),
+                    }
+                    self.done = true;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+        self.response.body.extend_from_slice(&received);
+        received
+    }
+}
+
+impl Drop for Exchange {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::container::Protocol;
+
+    /// What a server got : the method, the Content-Length header and the body.
+    type Received = (String, Option<String>, String);
+
+    /// Sends a request to a server answering once, and returns what it got.
+    fn send(
+        method : Methods,
+        body_mode : BodyMode,
+        body : &str) -> Received {
+
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", server.server_addr().to_ip().unwrap());
+        let received = thread::spawn(move || {
+            let mut request = server.recv().unwrap();
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            let length = request.headers().iter()
+                .find(|header| header.field.equiv("Content-Length"))
+                .map(|header| header.value.to_string());
+            let method = request.method().to_string();
+            request.respond(tiny_http::Response::from_string("ok")).unwrap();
+            (method, length, body)
+        });
+
+        let mut request = Request::new(0, String::from("test"), method, url, String::new(), String::from(body));
+        request.body_mode = body_mode;
+        execute(&request).unwrap();
+        received.join().unwrap()
+    }
+
+    fn received(method : &str, length : Option<&str>, body : &str) -> Received {
+        (method.to_owned(), length.map(str::to_owned), body.to_owned())
+    }
+
+    #[test]
+    fn sends_bodies_of_methods_expecting_one() {
+        assert_eq!(send(Methods::POST, BodyMode::Json, "{}"), received("POST", Some("2"), "{}"));
+        assert_eq!(send(Methods::PATCH, BodyMode::Raw, "patch"), received("PATCH", Some("5"), "patch"));
+        assert_eq!(send(Methods::Custom(String::from("PURGE")), BodyMode::Raw, "all"), received("PURGE", Some("3"), "all"));
+        assert_eq!(send(Methods::PUT, BodyMode::None, "ignored"), received("PUT", Some("0"), ""));
+        assert_eq!(send(Methods::Custom(String::from("LOCK")), BodyMode::None, ""), received("LOCK", Some("0"), ""));
+    }
+
+    #[test]
+    fn sends_unusual_bodies_as_asked() {
+        assert_eq!(send(Methods::GET, BodyMode::None, "ignored"), received("GET", None, ""));
+        assert_eq!(send(Methods::GET, BodyMode::Raw, "query"), received("GET", Some("5"), "query"));
+        assert_eq!(send(Methods::OPTIONS, BodyMode::Raw, "what"), received("OPTIONS", Some("4"), "what"));
+        assert_eq!(send(Methods::OPTIONS, BodyMode::None, ""), received("OPTIONS", None, ""));
+    }
+
+    #[test]
+    fn drops_bodies_of_head_and_trace() {
+        assert_eq!(send(Methods::HEAD, BodyMode::Raw, "body"), received("HEAD", None, ""));
+        assert_eq!(send(Methods::TRACE, BodyMode::Json, "{}"), received("TRACE", None, ""));
+    }
+
+    #[test]
+    fn refuses_forms_with_lines_that_are_not_fields() {
+        assert_eq!(send(Methods::POST, BodyMode::Form, "a=1\nb=x y"), received("POST", Some("11"), "a=1&b=x%20y"));
+        let mut request = Request::new(0, String::new(), Methods::POST, String::from("http://127.0.0.1:9/"), String::new(), String::from("a=1\noops"));
+        request.body_mode = BodyMode::Form;
+        let error = execute(&request).err().unwrap().to_string();
+        assert_eq!(error, "line 2 \"oops\" is not key=value");
+    }
+
+    #[test]
+    fn warns_about_bodies_not_expected() {
+        let mut request = Request::new(0, String::new(), Methods::GET, String::new(), String::new(), String::from("body"));
+        assert_eq!(body_warning(&request), None);
+        request.body_mode = BodyMode::Raw;
+        assert!(body_warning(&request).unwrap().contains("ignore"));
+        request.method = Methods::HEAD;
+        assert_eq!(body_warning(&request).as_deref(), Some("HEAD is sent without its body"));
+        request.method = Methods::PATCH;
+        assert_eq!(body_warning(&request), None);
+    }
+}
@@ -0,0 +1,426 @@
+use std::collections::HashSet;
+use std::error::Error;
+
+use serde_json::{json, Value};
+
+use crate::database::container::{BodyMode, Methods, Request};
+use super::client::{self, Response};
+
+/// Introspection query used to fetch the schema of an endpoint. Type
+/// references are unwrapped four levels deep which covers `[Type!]!`.
+pub const INTROSPECTION_QUERY : &str = "query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types {
+      kind
+      name
+      fields(includeDeprecated: true) { name type { ...TypeRef } }
+      inputFields { name type { ...TypeRef } }
+      enumValues(includeDeprecated: true) { name }
+    }
+  }
+}
+
+fragment TypeRef on __Type {
+  kind name
+  ofType { kind name ofType { kind name ofType { kind name ofType { kind name } } } }
+}";
+
+/// Builds the standard `{"query": ..., "variables": ...}` JSON envelope.
+///
+/// * `query` - the GraphQL document.
+/// * `variables` - a JSON object, blank meaning no variables.
+pub fn envelope(
+    query : &str,
+    variables : &str) -> Result<String, serde_json::Error> {
+
+    let variables : Value = if variables.trim().is_empty() {
+        json!({})
+    }
+    else {
+        serde_json::from_str(variables)?
+    };
+    Ok(json!({ "query" : query, "variables" : variables }).to_string())
+}
+
+/// The HTTP request sending a GraphQL Request as a JSON POST to its url.
+pub fn http_request(request : &Request) -> Result<Request, serde_json::Error> {
+    let mut http_request = request.clone();
+    http_request.method = Methods::POST;
+    http_request.body_mode = BodyMode::Json;
+    http_request.body = envelope(&request.body, &request.variables)?;
+    Ok(http_request)
+}
+
+/// Sends a GraphQL Request as a JSON POST to its url.
+pub fn execute(request : &Request) -> Result<Response, Box<dyn Error>> {
+    client::execute(&http_request(request)?)
+}
+
+/// The HTTP request running the introspection query against the url of a
+/// Request, reusing its headers for authentication.
+pub fn introspection(request : &Request) -> Request {
+    let mut introspection = request.clone();
+    introspection.body = String::from(INTROSPECTION_QUERY);
+    introspection.variables = String::new();
+    // Without variables the envelope can't fail.
+    http_request(&introspection).unwrap_or(introspection)
+}
+
+/// The `__schema` object of an introspection response as JSON text, ready to
+/// be cached.
+pub fn schema_of(response : &Response) -> Result<String, Box<dyn Error>> {
+    let body : Value = serde_json::from_slice(&response.body)?;
+    match body.pointer("/data/__schema") {
+        Some(schema) if !schema.is_null() => Ok(schema.to_string()),
+        _ => Err(format!("introspection failed ({}) : {}", response.status, body["errors"]).into()),
+    }
+}
+
+/// A field of an object or input type, or a value of an enum.
+pub struct SchemaField {
+    pub name : String,
+    /// Type as written in GraphQL, e.g. `[User!]!`. Empty for enum values.
+    pub type_name : String,
+    /// Named type once lists and non-null wrappers are removed.
+    pub base_type : String,
+}
+
+pub struct SchemaType {
+    pub name : String,
+    pub kind : String,
+    pub fields : Vec<SchemaField>,
+}
+
+/// The parts of an introspection result used for completion and browsing.
+pub struct Schema {
+    pub query_type : Option<String>,
+    pub mutation_type : Option<String>,
+    pub subscription_type : Option<String>,
+    pub types : Vec<SchemaType>,
+}
+
+/// A line of the schema tree, `type_name` is set on type rows so they can be
+/// expanded.
+pub struct TreeRow {
+    pub depth : usize,
+    pub label : String,
+    pub type_name : Option<String>,
+}
+
+/// Renders a `__Type` reference as GraphQL, returning it with its named type.
+fn type_ref(value : &Value) -> (String, String) {
+    match value["kind"].as_str() {
+        Some("NON_NULL") => {
+            let (inner, base) = type_ref(&value["ofType"]);
+            (format!("{}!", inner), base)
+        }
+        Some("LIST") => {
+            let (inner, base) = type_ref(&value["ofType"]);
+            (format!("[{}]", inner), base)
+        }
+        _ => {
+            let name = value["name"].as_str().unwrap_or_default().to_owned();
+            (name.clone(), name)
+        }
+    }
+}
+
+fn fields_of(value : &Value) -> Vec<SchemaField> {
+    let mut fields = vec![];
+    for key in ["fields", "inputFields"].iter() {
+        for field in value[*key].as_array().into_iter().flatten() {
+            let (type_name, base_type) = type_ref(&field["type"]);
+            fields.push(SchemaField {
+                name : field["name"].as_str().unwrap_or_default().to_owned(),
+                type_name,
+                base_type,
+            });
+        }
+    }
+    for enum_value in value["enumValues"].as_array().into_iter().flatten() {
+        fields.push(SchemaField {
+            name : enum_value["name"].as_str().unwrap_or_default().to_owned(),
+            type_name : String::new(),
+            base_type : String::new(),
+        });
+    }
+    fields
+}
+
+impl Schema {
+
+    /// Parses a cached `__schema` JSON object.
+    pub fn from_json(schema : &str) -> Option<Schema> {
+        let value : Value = serde_json::from_str(schema).ok()?;
+        let root_name = |key : &str| value[key]["name"].as_str().map(|name| name.to_owned());
+
+        Some(Schema {
+            query_type : root_name("queryType"),
+            mutation_type : root_name("mutationType"),
+            subscription_type : root_name("subscriptionType"),
+            types : value["types"].as_array()?
+                .iter()
+                .map(|t| SchemaType {
+                    name : t["name"].as_str().unwrap_or_default().to_owned(),
+                    kind : t["kind"].as_str().unwrap_or_default().to_owned(),
+                    fields : fields_of(t),
+                })
+                .collect(),
+        })
+    }
+
+    pub fn get_type(&self, name : &str) -> Option<&SchemaType> {
+        self.types.iter().find(|t| t.name == name)
+    }
+
+    /// Type of the selection set the end of `query` is in, or None when it
+    /// can't be resolved from the schema.
+    fn type_at_end(&self, query : &str) -> Option<&SchemaType> {
+        let operation = query.trim_start();
+        let root = if operation.starts_with("mutation") {
+            self.mutation_type.as_deref()
+        }
+        else if operation.starts_with("subscription") {
+            self.subscription_type.as_deref()
+        }
+        else {
+            self.query_type.as_deref()
+        };
+
+        // Stack of the selection sets' types, None where unknown.
+        let mut stack : Vec<Option<&SchemaType>> = vec![];
+        let mut last_word = String::new();
+        let mut previous_word = String::new();
+        let mut word = String::new();
+        let mut parens = 0;
+        let mut in_string = false;
+        let mut escaped = false;
+
+        for c in query.chars() {
+            if in_string {
+                in_string = escaped || c != '"';
+                escaped = !escaped && c == '\\';
+                continue;
+            }
+            if c.is_alphanumeric() || c == '_' {
+                word.push(c);
+                continue;
+            }
+            // Words inside arguments are not field names.
+            if !word.is_empty() && parens == 0 {
+                previous_word = std::mem::replace(&mut last_word, std::mem::take(&mut word));
+            }
+            word.clear();
+            match c {
+                '"' => in_string = true,
+                '(' => parens += 1,
+                ')' => parens -= 1,
+                '{' if parens == 0 => {
+                    let selection = match stack.last() {
+                        None => root.and_then(|name| self.get_type(name)),
+                        // Inline fragment : `... on Type {`
+                        Some(_) if previous_word == "on" => self.get_type(&last_word),
+                        Some(parent) => parent
+                            .and_then(|t| t.fields.iter().find(|f| f.name == last_word))
+                            .and_then(|f| self.get_type(&f.base_type)),
+                    };
+                    stack.push(selection);
+                }
+                '}' if parens == 0 => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+        }
+
+        stack.last().copied().flatten()
+    }
+
+    /// Field names that complete the word at the end of `query`.
+    pub fn completions(&self, query : &str) -> Vec<String> {
+        let prefix : String = query.chars()
+            .rev()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect::<Vec<char>>()
+            .into_iter()
+            .rev()
+            .collect();
+        let before = &query[..query.len() - prefix.len()];
+
+        match self.type_at_end(before) {
+            Some(t) => t.fields.iter()
+                .filter(|f| f.name.starts_with(&prefix))
+                .map(|f| f.name.clone())
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Flattens the schema into tree rows, listing the fields of the types in
+    /// `expanded`. Introspection types are left out.
+    pub fn tree(&self, expanded : &HashSet<String>) -> Vec<TreeRow> {
+        let mut rows = vec![];
+        for t in self.types.iter().filter(|t| !t.name.starts_with("__")) {
+            let open = expanded.contains(&t.name);
+            rows.push(TreeRow {
+                depth : 0,
+                label : format!("{} {} ({})", if open { "-" } else { "+" }, t.name, t.kind),
+                type_name : Some(t.name.clone()),
+            });
+            if open {
+                for field in t.fields.iter() {
+                    let label = if field.type_name.is_empty() {
+                        field.name.clone()
+                    }
+                    else {
+                        format!("{} : {}", field.name, field.type_name)
+                    };
+                    rows.push(TreeRow { depth : 1, label, type_name : None });
+                }
+            }
+        }
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    use crate::database::container::Protocol;
+
+    const SCHEMA : &str = r#"{
+        "queryType" : { "name" : "Query" },
+        "mutationType" : { "name" : "Mutation" },
+        "subscriptionType" : null,
+        "types" : [
+            { "kind" : "OBJECT", "name" : "Query", "fields" : [
+                { "name" : "user", "type" : { "kind" : "OBJECT", "name" : "User" } },
+                { "name" : "node", "type" : { "kind" : "INTERFACE", "name" : "Node" } },
+                { "name" : "users", "type" : { "kind" : "NON_NULL", "ofType" : { "kind" : "LIST", "ofType" : { "kind" : "OBJECT", "name" : "User" } } } }
+            ] },
+            { "kind" : "OBJECT", "name" : "Mutation", "fields" : [
+                { "name" : "rename", "type" : { "kind" : "OBJECT", "name" : "User" } }
+            ] },
+            { "kind" : "INTERFACE", "name" : "Node", "fields" : [
+                { "name" : "id", "type" : { "kind" : "SCALAR", "name" : "ID" } }
+            ] },
+            { "kind" : "OBJECT", "name" : "User", "fields" : [
+                { "name" : "id", "type" : { "kind" : "SCALAR", "name" : "ID" } },
+                { "name" : "name", "type" : { "kind" : "SCALAR", "name" : "String" } },
+                { "name" : "friends", "type" : { "kind" : "LIST", "ofType" : { "kind" : "OBJECT", "name" : "User" } } },
+                { "name" : "role", "type" : { "kind" : "ENUM", "name" : "Role" } }
+            ] },
+            { "kind" : "ENUM", "name" : "Role", "enumValues" : [ { "name" : "ADMIN" } ] }
+        ]
+    }"#;
+
+    fn schema() -> Schema {
+        Schema::from_json(SCHEMA).unwrap()
+    }
+
+    #[test]
+    fn envelope_wraps_query_and_variables() {
+        let blank : Value = serde_json::from_str(&envelope("{ me }", " \n").unwrap()).unwrap();
+        assert_eq!(blank, json!({ "query" : "{ me }", "variables" : {} }));
+        let set : Value = serde_json::from_str(&envelope("{ me }", r#"{"id" : 1}"#).unwrap()).unwrap();
+        assert_eq!(set["variables"], json!({ "id" : 1 }));
+        assert!(envelope("{ me }", "{ id : 1").is_err());
+    }
+
+    #[test]
+    fn type_refs_are_written_as_graphql() {
+        let schema = schema();
+        let users = schema.get_type("Query").unwrap().fields.iter().find(|f| f.name == "users").unwrap();
+        assert_eq!((users.type_name.as_str(), users.base_type.as_str()), ("[User]!", "User"));
+        assert_eq!(schema.get_type("Role").unwrap().fields[0].name, "ADMIN");
+    }
+
+    #[test]
+    fn completes_fields_of_nested_selections() {
+        let schema = schema();
+        assert_eq!(schema.completions("{ u"), vec!["user", "users"]);
+        assert_eq!(schema.completions("query { user { friends { n"), vec!["name"]);
+        assert_eq!(schema.completions("query { user { friends { id } n"), vec!["name"]);
+        assert_eq!(schema.completions("query { users { id } u"), vec!["user", "users"]);
+        assert_eq!(schema.completions("mutation { rename { r"), vec!["role"]);
+        assert!(schema.completions("query { missing { i").is_empty());
+    }
+
+    #[test]
+    fn completes_inside_inline_fragments() {
+        let schema = schema();
+        assert_eq!(schema.completions("{ node { ... on User { f"), vec!["friends"]);
+        assert_eq!(schema.completions("{ node { ... on User { id } i"), vec!["id"]);
+    }
+
+    #[test]
+    fn skips_arguments_and_strings() {
+        let schema = schema();
+        assert_eq!(schema.completions(r#"{ user(name : "a { b", role : ADMIN) { n"#), vec!["name"]);
+        assert_eq!(schema.completions(r#"{ user(name : "say \"}\" {") { r"#), vec!["role"]);
+        assert_eq!(schema.completions(r#"{ user(filter : { name : "x" }) { friends { i"#), vec!["id"]);
+    }
+
+    /// Sends the introspection query in the background, as the UI does, and
+    /// waits for the schema.
+    fn introspect(request : &Request) -> Result<String, Box<dyn Error>> {
+        let mut exchange = client::Exchange::spawn(&introspection(request));
+        while !exchange.done {
+            exchange.poll();
+            thread::sleep(std::time::Duration::from_millis(5));
+        }
+        match exchange.error.take() {
+            Some(e) => Err(e.into()),
+            None => schema_of(&exchange.response),
+        }
+    }
+
+    #[test]
+    fn introspects_an_endpoint() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/graphql", server.server_addr().to_ip().unwrap());
+        let answer = thread::spawn(move || {
+            let mut request = server.recv().unwrap();
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            let authorization = request.headers().iter()
+                .find(|header| header.field.equiv("Authorization"))
+                .map(|header| header.value.to_string());
+            let method = request.method().to_string();
+            let reply = format!(r#"{{"data" : {{"__schema" : {}}}}}"#, SCHEMA);
+            request.respond(tiny_http::Response::from_string(reply)).unwrap();
+            (method, authorization, body)
+        });
+
+        let mut request = Request::new(0, String::from("schema"), Methods::GET, url, String::new(), String::from("{ me }"));
+        request.headers = String::from("Authorization: Bearer token");
+        let schema = Schema::from_json(&introspect(&request).unwrap()).unwrap();
+        assert_eq!(schema.query_type.as_deref(), Some("Query"));
+
+        let (method, authorization, body) = answer.join().unwrap();
+        assert_eq!(method, "POST");
+        assert_eq!(authorization.as_deref(), Some("Bearer token"));
+        let body : Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["query"], INTROSPECTION_QUERY);
+    }
+
+    #[test]
+    fn introspection_reports_errors() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/graphql", server.server_addr().to_ip().unwrap());
+        thread::spawn(move || {
+            let request = server.recv().unwrap();
+            let reply = tiny_http::Response::from_string(r#"{"errors" : [{"message" : "introspection disabled"}]}"#);
+            request.respond(reply.with_status_code(400)).unwrap();
+        });
+
+        let request = Request::new(0, String::from("schema"), Methods::POST, url, String::new(), String::new());
+        let error = introspect(&request).unwrap_err().to_string();
+        assert!(error.contains("introspection disabled"), "{}", error);
+    }
+}
@@ -0,0 +1,248 @@
+use std::error::Error;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use tiny_http::{Header, Response, Server};
+
+use crate::database::example::Example;
+use crate::database::storage::Storage;
+use super::body;
+
+/// A Request of the mocked Collection, answered with one of its Examples.
+pub struct Route {
+    pub name : String,
+    pub method : String,
+    /// Path segments of the request url, see `is_param`.
+    pub segments : Vec<String>,
+    pub examples : Vec<Example>,
+}
+
+/// Path segments of a url, without scheme, host, query or fragment. A
+/// leading `{{base}}` stands for the scheme and host.
+fn path_segments(url : &str) -> Vec<String> {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    let path = match url.find("://") {
+        Some(i) => url[i + 3..].find('/').map(|j| &url[i + 3 + j..]).unwrap_or_default(),
+        None if url.starts_with("{{") => url.find("}}").and_then(|i| url[i..].find('/').map(|j| &url[i + j..])).unwrap_or_default(),
+        None => url,
+    };
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.to_owned())
+        .collect()
+}
+
+/// Path parameters match any segment. They are written `:id`, `{id}`,
+/// `{{id}}` or `*`.
+fn is_param(segment : &str) -> bool {
+    segment == "*"
+        || segment.starts_with(':')
+        || (segment.starts_with('{') && segment.ends_with('}'))
+}
+
+impl Route {
+
+    fn matches(
+        &self,
+        method : &str,
+        path : &[String]) -> bool {
+
+        self.method.eq_ignore_ascii_case(method)
+            && self.segments.len() == path.len()
+            && self.segments.iter().zip(path.iter()).all(|(segment, part)| is_param(segment) || segment == part)
+    }
+
+    /// Number of literal segments, the most specific matching route wins.
+    fn specificity(&self) -> usize {
+        self.segments.iter().filter(|segment| !is_param(segment)).count()
+    }
+}
+
+/// Builds a route for every Request of a Collection.
+///
+/// * `id_collection` - the collection to mock.
+/// * `db` - Database to work on.
+pub fn routes(
+    id_collection : i64,
+    db : &dyn Storage) -> crate::error::Result<Vec<Route>> {
+
+    let mut routes = vec![];
+    for request in db.get_all_requests(id_collection)? {
+        routes.push(Route {
+            segments : path_segments(&request.url),
+            method : request.method.to_string(),
+            examples : db.get_examples(request.id)?,
+            name : request.name,
+        });
+    }
+    Ok(routes)
+}
+
+/// Answers a request with the Example of the best matching route. The
+/// `X-Mock-Example` header picks an example by name, the first one is used
+/// otherwise.
+fn respond(
+    routes : &[Route],
+    request : tiny_http::Request) {
+
+    let path = path_segments(request.url());
+    let method = request.method().to_string();
+    let route = routes.iter()
+        .filter(|route| route.matches(&method, &path))
+        .max_by_key(|route| route.specificity());
+    let wanted = request.headers().iter()
+        .find(|header| header.field.equiv("X-Mock-Example"))
+        .map(|header| header.value.to_string());
+
+    let example = route.and_then(|route| match &wanted {
+        Some(name) => route.examples.iter().find(|example| &example.name == name),
+        None => route.examples.first(),
+    });
+
+    let (response, outcome) = match (route, example) {
+        (Some(route), Some(example)) => {
+            thread::sleep(Duration::from_millis(example.latency.max(0) as u64));
+            // A status saved by hand may be anything.
+            let status = Some(example.status).filter(|status| (100..=599).contains(status)).unwrap_or(500) as u16;
+            let mut response = Response::from_data(example.body.clone().into_bytes())
+                .with_status_code(status);
+            // tiny_http sets the length from the body as it is now.
+            for line in body::without_framing(&example.headers) {
+                if let Ok(header) = line.parse::<Header>() {
+                    response.add_header(header);
+                }
+            }
+            (response, format!("{} ({} : {})", status, route.name, example.name))
+        }
+        (Some(route), None) => {
+            (Response::from_data(format!("No example saved for {}", route.name).into_bytes()).with_status_code(501),
+             format!("501 ({} has no example)", route.name))
+        }
+        (None, _) => {
+            (Response::from_data(b"No matching request in the collection".to_vec()).with_status_code(404),
+             String::from("404"))
+        }
+    };
+
+    println!("{} {} -> {}", method, request.url(), outcome);
+    if let Err(e) = request.respond(response) {
+        eprintln!("{}", e);
+    }
+}
+
+/// Serves the routes until the process is stopped, each request is answered
+/// in its own thread so latencies don't add up, see `listen`.
+///
+/// * `routes` - the routes of the mocked collection.
+/// * `host` - the address to listen on, `127.0.0.1` to stay on this machine.
+/// * `port` - the port to listen on.
+pub fn serve(
+    routes : Vec<Route>,
+    host : &str,
+    port : u16) -> Result<(), Box<dyn Error>> {
+
+    let server = Server::http((host, port)).map_err(|e| e.to_string())?;
+    for route in routes.iter() {
+        println!("{} /{} ({} examples)", route.method, route.segments.join("/"), route.examples.len());
+    }
+    println!("Mock server listening on {}:{}", host, port);
+    listen(&server, routes);
+    Ok(())
+}
+
+/// Answers the requests of a server with the routes, each in its own thread.
+fn listen(
+    server : &Server,
+    routes : Vec<Route>) {
+
+    let routes = Arc::new(routes);
+    for request in server.incoming_requests() {
+        let routes = routes.clone();
+        thread::spawn(move || respond(&routes, request));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::container::{Methods, Protocol, Request};
+    use crate::http::client::{self, Response};
+
+    fn example(name : &str, status : i64, body : &str) -> Example {
+        Example {
+            id : 0,
+            id_request : 0,
+            name : name.to_owned(),
+            status,
+            headers : String::from("Content-Type: text/plain\nContent-Length: 999"),
+            body : body.to_owned(),
+            latency : 0,
+        }
+    }
+
+    fn route(method : &str, url : &str, examples : Vec<Example>) -> Route {
+        Route { name : url.to_owned(), method : method.to_owned(), segments : path_segments(url), examples }
+    }
+
+    /// Serves routes on a free local port, returning its base url.
+    fn start(routes : Vec<Route>) -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        thread::spawn(move || listen(&server, routes));
+        base
+    }
+
+    fn send(
+        method : Methods,
+        url : String,
+        headers : &str) -> Response {
+
+        let mut request = Request::new(0, String::from("test"), method, url, String::new(), String::new());
+        request.headers = headers.to_owned();
+        client::execute(&request).unwrap()
+    }
+
+    fn answer(response : &Response) -> (u32, String) {
+        (response.status, String::from_utf8_lossy(&response.body).into_owned())
+    }
+
+    #[test]
+    fn splits_paths() {
+        assert_eq!(path_segments("https://host:8080/users/:id?page=1#top"), vec!["users", ":id"]);
+        assert_eq!(path_segments("{{base}}/users/{{id}}"), vec!["users", "{{id}}"]);
+        assert_eq!(path_segments("{{base}}"), Vec::<String>::new());
+        assert_eq!(path_segments("/users//me/"), vec!["users", "me"]);
+        assert_eq!(path_segments("http://host"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn answers_with_the_most_specific_route() {
+        let base = start(vec![
+            route("GET", "{{base}}/users/:id", vec![example("any", 200, "user")]),
+            route("GET", "http://example.com/users/me", vec![example("me", 200, "me"), example("gone", 410, "gone")]),
+            route("GET", "/users/*/posts", vec![]),
+            route("delete", "{{base}}/users/{id}", vec![example("deleted", 204, "")]),
+        ]);
+        assert_eq!(answer(&send(Methods::GET, format!("{}/users/7", base), "")), (200, String::from("user")));
+        assert_eq!(answer(&send(Methods::GET, format!("{}/users/me?x=1", base), "")), (200, String::from("me")));
+        assert_eq!(answer(&send(Methods::GET, format!("{}/users/me", base), "X-Mock-Example: gone")), (410, String::from("gone")));
+        assert_eq!(send(Methods::DELETE, format!("{}/users/7", base), "").status, 204);
+        assert_eq!(send(Methods::GET, format!("{}/users/7/posts", base), "").status, 501);
+        assert_eq!(send(Methods::POST, format!("{}/users/7", base), "").status, 404);
+        assert_eq!(send(Methods::GET, format!("{}/users", base), "").status, 404);
+    }
+
+    #[test]
+    fn replaces_invalid_statuses_and_framing() {
+        let base = start(vec![
+            route("GET", "/wrapped", vec![example("wrapped", 65736, "body")]),
+            route("GET", "/low", vec![example("low", 42, "body")]),
+        ]);
+        assert_eq!(send(Methods::GET, format!("{}/wrapped", base), "").status, 500);
+        let response = send(Methods::GET, format!("{}/low", base), "");
+        assert_eq!(answer(&response), (500, String::from("body")));
+        assert_eq!(response.header("Content-Length"), Some("4"));
+        assert_eq!(response.header("Content-Type"), Some("text/plain"));
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::database::container::{Request, RequestKind};
+use crate::database::script::Scripts;
+use super::{client, graphql, script};
+
+/// Variables of one iteration, from a row of the data file.
+pub type Variables = HashMap<String, String>;
+
+/// Replaces `{{name}}` with the value of `name`. Unknown variables are left
+/// untouched so they stand out in the results.
+pub fn substitute(
+    text : &str,
+    variables : &Variables) -> String {
+
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start + 2..].find("}}") {
+            Some(end) => start + 2 + end,
+            None => break,
+        };
+        result.push_str(&rest[..start]);
+        match variables.get(rest[start + 2..end].trim()) {
+            Some(value) => result.push_str(value),
+            None => result.push_str(&rest[start..end + 2]),
+        }
+        rest = &rest[end + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// A copy of a Request with the variables substituted everywhere they can be
+/// used.
+pub fn resolve(
+    request : &Request,
+    variables : &Variables) -> Request {
+
+    let mut request = request.clone();
+    request.url = substitute(&request.url, variables);
+    request.params = substitute(&request.params, variables);
+    request.headers = substitute(&request.headers, variables);
+    request.body = substitute(&request.body, variables);
+    request.variables = substitute(&request.variables, variables);
+    request
+}
+
+/// Splits CSV text into records, each with the line it starts on. Double
+/// quotes enclose fields with commas or line breaks and `""` is an escaped
+/// quote. Blank lines are skipped.
+fn csv_records(text : &str) -> Vec<(usize, Vec<String>)> {
+    let mut records = vec![];
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                fields.push(std::mem::take(&mut field));
+                let record = std::mem::take(&mut fields);
+                if record.len() > 1 || !record[0].trim().is_empty() {
+                    records.push((start, record));
+                }
+                line += 1;
+                start = line;
+            }
+            _ => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if !fields.is_empty() || !field.trim().is_empty() {
+        fields.push(field);
+        records.push((start, fields));
+    }
+    records
+}
+
+/// Rows of a CSV file, the first record naming the variables.
+fn parse_csv(text : &str) -> Result<Vec<Variables>, Box<dyn Error>> {
+    let mut records = csv_records(text).into_iter();
+    let names : Vec<String> = match records.next() {
+        Some((_, header)) => header.iter().map(|name| name.trim().to_owned()).collect(),
+        None => return Ok(vec![]),
+    };
+    records.map(|(line, fields)| {
+        if fields.len() != names.len() {
+            return Err(format!("line {} has {} fields, the header has {}", line, fields.len(), names.len()).into());
+        }
+        Ok(names.iter().cloned().zip(fields).collect())
+    }).collect()
+}
+
+/// Elements of a JSON array of objects. Strings are used as is, other values
+/// as JSON.
+fn parse_json(text : &str) -> Result<Vec<Variables>, Box<dyn Error>> {
+    let value : Value = serde_json::from_str(text)?;
+    let rows = value.as_array().ok_or("the JSON data file must be an array of objects")?;
+    rows.iter().map(|row| {
+        let object = row.as_object().ok_or("the JSON data file must be an array of objects")?;
+        Ok(object.iter()
+           .map(|(name, value)| (name.clone(), match value {
+               Value::String(s) => s.clone(),
+               value => value.to_string(),
+           }))
+           .collect())
+    }).collect()
+}
+
+/// Reads the iterations of a run from a `.json` file, or a CSV file otherwise.
+pub fn load_data(path : &str) -> Result<Vec<Variables>, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    if path.to_ascii_lowercase().ends_with(".json") {
+        parse_json(&text)
+    }
+    else {
+        parse_csv(&text)
+    }
+}
+
+/// Outcome of a Request in an iteration.
+pub struct Outcome {
+    pub request : String,
+    /// Status and time of the response, or why there is none.
+    pub result : Result<(u32, Duration), String>,
+    /// Script logs, tests and error, see `script::Context::report`.
+    pub scripts : String,
+    pub scripts_passed : bool,
+}
+
+/// Results of one iteration of a run.
+pub struct Iteration {
+    /// Position of the data row, from 1.
+    pub index : usize,
+    pub variables : Variables,
+    pub outcomes : Vec<Outcome>,
+}
+
+impl Iteration {
+
+    /// Failed transfers, 4xx/5xx statuses, script errors and failed tests
+    /// fail the iteration.
+    pub fn passed(&self) -> bool {
+        self.outcomes.iter().all(|outcome| outcome.scripts_passed && matches!(outcome.result, Ok((status, _)) if status < 400))
+    }
+
+    pub fn report(&self) -> String {
+        let mut variables : Vec<String> = self.variables.iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        variables.sort();
+        let mut lines = vec![format!("#{} {} {}",
+                                     self.index,
+                                     if self.passed() { "PASS" } else { "FAIL" },
+                                     variables.join(" "))];
+        for outcome in self.outcomes.iter() {
+            lines.push(match &outcome.result {
+                Ok((status, time)) => format!("  {} : {} in {} ms", outcome.request, status, time.as_millis()),
+                Err(e) => format!("  {} : {}", outcome.request, e),
+            });
+            for line in outcome.scripts.lines() {
+                lines.push(format!("    {}", line));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Sends the requests of a collection in order once per data row, handing
+/// each finished iteration to `on_iteration`. Variables set by scripts carry
+/// over to the next requests of the iteration. WebSocket requests are
+/// skipped. Without data the requests are sent once. Returns early once
+/// `stop` is set.
+pub fn run<F>(
+    requests : &[(Request, Scripts)],
+    data : Vec<Variables>,
+    stop : &AtomicBool,
+    mut on_iteration : F)
+where
+F : FnMut(Iteration),
+{
+    let data = if data.is_empty() { vec![Variables::new()] } else { data };
+    for (i, row) in data.into_iter().enumerate() {
+        let mut outcomes = vec![];
+        let mut variables = row.clone();
+        for (request, scripts) in requests.iter() {
+            if stop.load(Ordering::Relaxed) {
+                return;
+            }
+            if request.kind == RequestKind::Websocket {
+                continue;
+            }
+            let mut context = script::before(request, scripts, variables);
+            let result = match &context.error {
+                Some(_) => Err(String::from("not sent")),
+                None => match request.kind {
+                    RequestKind::Graphql => graphql::execute(&context.request),
+                    _ => client::execute(&context.request),
+                }.map_err(|e| e.to_string()),
+            };
+            if let Ok(response) = &result {
+                context = script::after(context, response, scripts);
+            }
+            outcomes.push(Outcome {
+                request : request.name.clone(),
+                result : result.map(|response| (response.status, response.time)),
+                scripts : context.report(),
+                scripts_passed : context.passed(),
+            });
+            variables = context.variables;
+        }
+        on_iteration(Iteration { index : i + 1, variables : row, outcomes });
+    }
+}
+
+/// A collection run in its own thread, see `CollectionRun::poll`.
+pub struct CollectionRun {
+    iterations : Receiver<Iteration>,
+    stop : Arc<AtomicBool>,
+    /// Number of data rows, the run is done after as many iterations.
+    pub total : usize,
+    pub iterations_done : Vec<Iteration>,
+    pub done : bool,
+}
+
+impl CollectionRun {
+
+    /// Starts running the requests in the background.
+    pub fn spawn(
+        requests : Vec<(Request, Scripts)>,
+        data : Vec<Variables>) -> CollectionRun {
+
+        let (iterations_tx, iterations) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let total = data.len().max(1);
+
+        let thread_stop = stop.clone();
+        thread::spawn(move || run(&requests, data, &thread_stop, |iteration| {
+            let _ = iterations_tx.send(iteration);
+        }));
+
+        CollectionRun {
+            iterations,
+            stop,
+            total,
+            iterations_done : vec![],
+            done : false,
+        }
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// Takes in the iterations finished since the last call.
+    pub fn poll(&mut self) {
+        loop {
+            match self.iterations.try_recv() {
+                Ok(iteration) => self.iterations_done.push(iteration),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Iteration reports followed by a summary line.
+    pub fn report(&self) -> String {
+        let passed = self.iterations_done.iter().filter(|iteration| iteration.passed()).count();
+        let mut lines : Vec<String> = self.iterations_done.iter().map(|iteration| iteration.report()).collect();
+        lines.push(format!("{}/{} iterations, {} passed, {} failed{}",
+                           self.iterations_done.len(),
+                           self.total,
+                           passed,
+                           self.iterations_done.len() - passed,
+                           if self.done { "" } else { ", running..." }));
+        lines.join("\n")
+    }
+}
+
+impl Drop for CollectionRun {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables(pairs : &[(&str, &str)]) -> Variables {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn substitutes_known_variables() {
+        let vars = variables(&[("host", "localhost"), ("id", "7")]);
+        assert_eq!(substitute("http://{{host}}/users/{{ id }}", &vars), "http://localhost/users/7");
+        assert_eq!(substitute("{{missing}} and {{id}}", &vars), "{{missing}} and 7");
+        assert_eq!(substitute("{{id}}{{id}}", &vars), "77");
+        assert_eq!(substitute("open {{id", &vars), "open {{id");
+        assert_eq!(substitute("{ {id} }", &vars), "{ {id} }");
+    }
+
+    #[test]
+    fn parses_csv_rows() {
+        let rows = parse_csv("name, id\nann,1\r\n\n\"smith, bob\",\"2\"\n").unwrap();
+        assert_eq!(rows, vec![
+            variables(&[("name", "ann"), ("id", "1")]),
+            variables(&[("name", "smith, bob"), ("id", "2")]),
+        ]);
+        assert!(parse_csv("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parses_quotes_across_lines() {
+        let rows = parse_csv("note,id\n\"first line\nsecond \"\"quoted\"\"\",1\nlast,2").unwrap();
+        assert_eq!(rows, vec![
+            variables(&[("note", "first line\nsecond \"quoted\""), ("id", "1")]),
+            variables(&[("note", "last"), ("id", "2")]),
+        ]);
+    }
+
+    #[test]
+    fn reports_rows_of_the_wrong_length() {
+        let error = parse_csv("a,b\n1,2\n\"x\ny\"\n3,4,5\n").unwrap_err().to_string();
+        assert_eq!(error, "line 3 has 1 fields, the header has 2");
+        assert!(parse_csv("a,b\n1,2,3\n").is_err());
+    }
+
+    #[test]
+    fn parses_json_rows() {
+        let rows = parse_json(r#"[{"name" : "ann", "id" : 1, "admin" : true}, {}]"#).unwrap();
+        assert_eq!(rows, vec![
+            variables(&[("name", "ann"), ("id", "1"), ("admin", "true")]),
+            Variables::new(),
+        ]);
+        assert!(parse_json(r#"{"name" : "ann"}"#).is_err());
+        assert!(parse_json(r#"[1, 2]"#).is_err());
+        assert!(parse_json("[").is_err());
+    }
+}
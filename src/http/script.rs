@@ -0,0 +1,219 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rhai::{Dynamic, Engine};
+use serde_json::Value;
+
+use crate::database::container::Request;
+use crate::database::script::Scripts;
+use super::client::Response;
+use super::runner::{self, Variables};
+
+/// Scripts are stopped after this many operations so a loop can't hang the
+/// app.
+const MAX_OPERATIONS : u64 = 1_000_000;
+
+/// A `test(name, passed)` call of a post-response script.
+pub struct TestResult {
+    pub name : String,
+    pub passed : bool,
+}
+
+/// What scripts can see and change around a Request.
+pub struct Context {
+    pub variables : Variables,
+    /// The request to send, variables are substituted once the pre-request
+    /// scripts ran.
+    pub request : Request,
+    pub response : Option<Response>,
+    pub tests : Vec<TestResult>,
+    /// Output of `print` and `debug`.
+    pub logs : Vec<String>,
+    /// The first script error, later scripts are not run.
+    pub error : Option<String>,
+}
+
+impl Context {
+
+    pub fn new(
+        request : &Request,
+        variables : Variables) -> Context {
+
+        Context {
+            variables,
+            request : request.clone(),
+            response : None,
+            tests : vec![],
+            logs : vec![],
+            error : None,
+        }
+    }
+
+    /// Whether no script failed and every test passed.
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.tests.iter().all(|test| test.passed)
+    }
+
+    /// Logs, test results and error, one per line.
+    pub fn report(&self) -> String {
+        let mut lines : Vec<String> = self.logs.iter().map(|log| format!("log : {}", log)).collect();
+        for test in self.tests.iter() {
+            lines.push(format!("{} {}", if test.passed { "PASS" } else { "FAIL" }, test.name));
+        }
+        if let Some(e) = &self.error {
+            lines.push(format!("script error : {}", e));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Header lines without the ones named `name`, case insensitive.
+fn without_header(
+    headers : &str,
+    name : &str) -> Vec<String> {
+
+    headers.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter(|line| line.split(':').next().map(|n| !n.trim().eq_ignore_ascii_case(name)).unwrap_or(true))
+        .map(|line| line.to_owned())
+        .collect()
+}
+
+/// Builds an engine whose functions act on a shared context :
+///
+/// * `get_var(name)`, `set_var(name, value)` - run variables, used for `{{name}}`.
+/// * `method()`, `url()`, `set_url(url)`, `body()`, `set_body(body)`,
+///   `set_header(name, value)`, `remove_header(name)` - the request to send.
+/// * `status()`, `time_ms()`, `response_body()`, `response_header(name)`,
+///   `response_json()` - the response, in post-response scripts.
+/// * `test(name, passed)` - records a test result.
+fn engine(context : &Rc<RefCell<Context>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    let c = context.clone();
+    engine.on_print(move |text| c.borrow_mut().logs.push(text.to_owned()));
+    let c = context.clone();
+    engine.on_debug(move |text, _, _| c.borrow_mut().logs.push(text.to_owned()));
+
+    let c = context.clone();
+    engine.register_fn("get_var", move |name : &str| -> Dynamic {
+        match c.borrow().variables.get(name) {
+            Some(value) => value.clone().into(),
+            None => Dynamic::UNIT,
+        }
+    });
+    let c = context.clone();
+    engine.register_fn("set_var", move |name : &str, value : Dynamic| {
+        c.borrow_mut().variables.insert(name.to_owned(), value.to_string());
+    });
+
+    let c = context.clone();
+    engine.register_fn("method", move || c.borrow().request.method.to_string());
+    let c = context.clone();
+    engine.register_fn("url", move || c.borrow().request.url.clone());
+    let c = context.clone();
+    engine.register_fn("set_url", move |url : &str| c.borrow_mut().request.url = url.to_owned());
+    let c = context.clone();
+    engine.register_fn("body", move || c.borrow().request.body.clone());
+    let c = context.clone();
+    engine.register_fn("set_body", move |body : &str| c.borrow_mut().request.body = body.to_owned());
+    let c = context.clone();
+    engine.register_fn("set_header", move |name : &str, value : Dynamic| {
+        let mut context = c.borrow_mut();
+        let mut headers = without_header(&context.request.headers, name);
+        headers.push(format!("{}: {}", name, value));
+        context.request.headers = headers.join("\n");
+    });
+    let c = context.clone();
+    engine.register_fn("remove_header", move |name : &str| {
+        let mut context = c.borrow_mut();
+        context.request.headers = without_header(&context.request.headers, name).join("\n");
+    });
+
+    let c = context.clone();
+    engine.register_fn("status", move || -> i64 {
+        c.borrow().response.as_ref().map(|response| response.status as i64).unwrap_or(0)
+    });
+    let c = context.clone();
+    engine.register_fn("time_ms", move || -> i64 {
+        c.borrow().response.as_ref().map(|response| response.time.as_millis() as i64).unwrap_or(0)
+    });
+    let c = context.clone();
+    engine.register_fn("response_body", move || -> String {
+        c.borrow().response.as_ref().map(|response| String::from_utf8_lossy(&response.body).into_owned()).unwrap_or_default()
+    });
+    let c = context.clone();
+    engine.register_fn("response_header", move |name : &str| -> Dynamic {
+        match c.borrow().response.as_ref().and_then(|response| response.header(name)) {
+            Some(value) => value.to_owned().into(),
+            None => Dynamic::UNIT,
+        }
+    });
+    let c = context.clone();
+    engine.register_fn("response_json", move || -> Result<Dynamic, Box<rhai::EvalAltResult>> {
+        let context = c.borrow();
+        let body = context.response.as_ref().map(|response| response.body.as_slice()).unwrap_or_default();
+        let value : Value = serde_json::from_slice(body).map_err(|e| e.to_string())?;
+        rhai::serde::to_dynamic(value)
+    });
+
+    let c = context.clone();
+    engine.register_fn("test", move |name : &str, passed : bool| {
+        c.borrow_mut().tests.push(TestResult { name : name.to_owned(), passed });
+    });
+
+    engine
+}
+
+/// Runs scripts in order on a context, stopping at the first error which is
+/// kept in `Context::error`.
+fn run(
+    scripts : &[String],
+    context : Context) -> Context {
+
+    let shared = Rc::new(RefCell::new(context));
+    {
+        let engine = engine(&shared);
+        for script in scripts.iter() {
+            if let Err(e) = engine.run(script) {
+                shared.borrow_mut().error = Some(e.to_string());
+                break;
+            }
+        }
+    }
+    match Rc::try_unwrap(shared) {
+        Ok(context) => context.into_inner(),
+        Err(_) => unreachable!("the engine holding the context was dropped"),
+    }
+}
+
+/// Runs the pre-request scripts then substitutes the variables, the request
+/// of the returned context is ready to send unless it has an error.
+///
+/// * `request` - the saved request.
+/// * `scripts` - the scripts around the request.
+/// * `variables` - the variables of the run.
+pub fn before(
+    request : &Request,
+    scripts : &Scripts,
+    variables : Variables) -> Context {
+
+    let mut context = run(&scripts.pre_request, Context::new(request, variables));
+    context.request = runner::resolve(&context.request, &context.variables);
+    context
+}
+
+/// Runs the post-response scripts on the response of a request.
+///
+/// * `context` - the context returned by `before`.
+/// * `response` - what came back.
+/// * `scripts` - the scripts around the request.
+pub fn after(
+    mut context : Context,
+    response : &Response,
+    scripts : &Scripts) -> Context {
+
+    context.response = Some(response.clone());
+    run(&scripts.post_response, context)
+}
@@ -0,0 +1,176 @@
+use crate::util::time;
+use super::client::Response;
+
+/// A dispatched Server-Sent Event.
+pub struct Event {
+    /// Milliseconds since the unix epoch at which the event was dispatched.
+    pub time : i64,
+    pub event : String,
+    pub id : Option<String>,
+    pub data : String,
+    pub retry : Option<u64>,
+}
+
+/// Whether a response is an event stream, from its Content-Type.
+pub fn is_event_stream(response : &Response) -> bool {
+    response.header("Content-Type")
+        .map(|content_type| content_type.to_ascii_lowercase().starts_with("text/event-stream"))
+        .unwrap_or(false)
+}
+
+/// Incremental parser of a `text/event-stream` body, keeping the events
+/// dispatched so far.
+#[derive(Default)]
+pub struct EventStream {
+    /// Bytes of the line being received.
+    line : Vec<u8>,
+    /// Whether the last byte was a `\r`, for `\r\n` split across chunks.
+    after_cr : bool,
+    event : String,
+    data : Vec<String>,
+    retry : Option<u64>,
+    /// Id of the last event, sent back as `Last-Event-ID` when reconnecting.
+    pub last_event_id : Option<String>,
+    pub events : Vec<Event>,
+    /// Why the stream ended, an error or `ended`, None while it goes on.
+    pub ended : Option<String>,
+}
+
+impl EventStream {
+
+    /// Parses a chunk of the body, lines may be split across chunks.
+    pub fn feed(&mut self, chunk : &[u8]) {
+        for &byte in chunk.iter() {
+            let after_cr = std::mem::replace(&mut self.after_cr, byte == b'\r');
+            match byte {
+                b'\n' if after_cr => {}
+                b'\r' | b'\n' => {
+                    let line = String::from_utf8_lossy(&std::mem::take(&mut self.line)).into_owned();
+                    self.process_line(&line);
+                }
+                _ => self.line.push(byte),
+            }
+        }
+    }
+
+    fn process_line(&mut self, line : &str) {
+        if line.is_empty() {
+            self.dispatch();
+            return;
+        }
+        // Lines starting with a colon are comments, often used as keep-alives.
+        if line.starts_with(':') {
+            return;
+        }
+        let (field, value) = match line.find(':') {
+            Some(i) => {
+                let value = &line[i + 1..];
+                (&line[..i], value.strip_prefix(' ').unwrap_or(value))
+            }
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = value.to_owned(),
+            "data" => self.data.push(value.to_owned()),
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_owned()),
+            // Only ASCII digits, `+5` or ` 5` are ignored.
+            "retry" if !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit()) => {
+                if let Ok(retry) = value.parse() {
+                    self.retry = Some(retry);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Ends the event being built on a blank line. Events without data are
+    /// dropped as the spec requires.
+    fn dispatch(&mut self) {
+        let event = std::mem::take(&mut self.event);
+        let retry = self.retry.take();
+        if self.data.is_empty() {
+            return;
+        }
+        self.events.push(Event {
+            time : time::now_millis(),
+            event : if event.is_empty() { String::from("message") } else { event },
+            id : self.last_event_id.clone(),
+            data : std::mem::take(&mut self.data).join("\n"),
+            retry,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(chunks : &[&str]) -> EventStream {
+        let mut stream = EventStream::default();
+        for chunk in chunks.iter() {
+            stream.feed(chunk.as_bytes());
+        }
+        stream
+    }
+
+    fn data(stream : &EventStream) -> Vec<&str> {
+        stream.events.iter().map(|event| event.data.as_str()).collect()
+    }
+
+    #[test]
+    fn joins_data_lines() {
+        let stream = parse(&["data: first\ndata:second\ndata\ndata:  indented\n\n"]);
+        assert_eq!(data(&stream), vec!["first\nsecond\n\n indented"]);
+        assert_eq!(stream.events[0].event, "message");
+    }
+
+    #[test]
+    fn skips_comments_and_events_without_data() {
+        let stream = parse(&[": keep-alive\n\nevent: ping\n\n:data: no\ndata: yes\n\n"]);
+        assert_eq!(data(&stream), vec!["yes"]);
+        assert_eq!(stream.events[0].event, "message");
+    }
+
+    #[test]
+    fn keeps_event_names_ids_and_retry() {
+        let stream = parse(&["event: update\nid: 7\nretry: 3000\ndata: a\n\ndata: b\nretry: +5\nretry: 1s\n\nid\ndata: c\n\n"]);
+        let events : Vec<(&str, Option<&str>, Option<u64>)> = stream.events.iter()
+            .map(|event| (event.event.as_str(), event.id.as_deref(), event.retry))
+            .collect();
+        assert_eq!(events, vec![("update", Some("7"), Some(3000)), ("message", Some("7"), None), ("message", Some(""), None)]);
+        assert_eq!(stream.last_event_id.as_deref(), Some(""));
+        // Ids with NUL are ignored.
+        let stream = parse(&["id: 1\n\nid: 2\0\ndata: x\n\n"]);
+        assert_eq!(stream.events[0].id.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn accepts_every_line_ending() {
+        let stream = parse(&["data: crlf\r\n\r\ndata: cr\r\rdata: lf\n\n"]);
+        assert_eq!(data(&stream), vec!["crlf", "cr", "lf"]);
+    }
+
+    #[test]
+    fn reads_fields_split_across_chunks() {
+        let stream = parse(&["da", "ta: spl", "it\r", "\n", "\r", "\nev", "ent: x\ndata: y\n\n"]);
+        assert_eq!(data(&stream), vec!["split", "y"]);
+        assert_eq!(stream.events[1].event, "x");
+        // A `\r\n` split between chunks ends a single line.
+        let stream = parse(&["data: a\r", "\ndata: b\n\n"]);
+        assert_eq!(data(&stream), vec!["a\nb"]);
+        // A multi-byte character split between chunks is kept whole.
+        let mut stream = EventStream::default();
+        let bytes = "data: é\n\n".as_bytes();
+        stream.feed(&bytes[..7]);
+        stream.feed(&bytes[7..]);
+        assert_eq!(data(&stream), vec!["é"]);
+    }
+
+    #[test]
+    fn waits_for_the_blank_line() {
+        let mut stream = parse(&["data: pending\n"]);
+        assert!(stream.events.is_empty());
+        stream.feed(b"\n");
+        assert_eq!(data(&stream), vec!["pending"]);
+    }
+}
@@ -0,0 +1,201 @@
+use std::error::Error;
+use std::io::ErrorKind;
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use tungstenite::client::IntoClientRequest;
+use tungstenite::http::header::{HeaderName, HeaderValue};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::Message as Frame;
+
+use crate::database::container::{BodyMode, Request};
+use crate::database::websocket::{Direction, Message};
+use crate::util::time;
+use super::body;
+
+/// How long the session thread waits for a frame before checking for
+/// commands from the UI.
+const POLL_INTERVAL : Duration = Duration::from_millis(100);
+
+enum Command {
+    Send(String),
+    Ping,
+    Close,
+}
+
+/// A WebSocket connection running in its own thread. Frames and connection
+/// events are collected in `log` each time the session is polled.
+pub struct Session {
+    commands : Sender<Command>,
+    messages : Receiver<Message>,
+    pub log : Vec<Message>,
+    pub open : bool,
+}
+
+/// The text frame to send for the body of a WebSocket Request, JSON bodies
+/// are checked and compacted first.
+pub fn frame(request : &Request) -> Result<String, serde_json::Error> {
+    match request.body_mode {
+        BodyMode::Json => Ok(serde_json::from_str::<serde_json::Value>(&request.body)?.to_string()),
+        _ => Ok(request.body.clone()),
+    }
+}
+
+fn message(direction : Direction, content : String) -> Message {
+    Message { direction, content, time : time::now_millis() }
+}
+
+/// Builds the handshake request with the headers of the Request.
+fn handshake(request : &Request) -> Result<tungstenite::handshake::client::Request, Box<dyn Error>> {
+    let mut handshake = request.url.as_str().into_client_request()?;
+    for line in body::header_lines(&request.headers) {
+        if let Some(i) = line.find(':') {
+            handshake.headers_mut().append(HeaderName::from_bytes(line[..i].trim().as_bytes())?,
+                                           HeaderValue::from_str(line[i + 1..].trim())?);
+        }
+    }
+    Ok(handshake)
+}
+
+fn run(
+    request : Request,
+    commands : Receiver<Command>,
+    messages : Sender<Message>) {
+
+    let log = |direction : Direction, content : String| {
+        messages.send(message(direction, content)).is_ok()
+    };
+
+    let (mut socket, response) = match handshake(&request)
+        .and_then(|handshake| Ok(tungstenite::connect(handshake)?)) {
+        Ok(connection) => connection,
+        Err(e) => {
+            log(Direction::Info, format!("connection failed : {}", e));
+            return;
+        }
+    };
+    // Reads time out so that commands are picked up while the server is quiet.
+    let timeout = match socket.get_ref() {
+        MaybeTlsStream::Plain(stream) => stream.set_read_timeout(Some(POLL_INTERVAL)),
+        MaybeTlsStream::NativeTls(stream) => TcpStream::set_read_timeout(stream.get_ref(), Some(POLL_INTERVAL)),
+        _ => Ok(()),
+    };
+    if let Err(e) = timeout {
+        log(Direction::Info, format!("connection failed : {}", e));
+        return;
+    }
+    log(Direction::Info, format!("connected to {} ({})", request.url, response.status()));
+
+    loop {
+        let sent = match commands.try_recv() {
+            Ok(Command::Send(text)) => socket.send(Frame::Text(text.clone())).map(|_| log(Direction::Sent, text)),
+            Ok(Command::Ping) => socket.send(Frame::Ping(vec![])).map(|_| log(Direction::Info, String::from("ping"))),
+            // The UI dropped the session, close it as well.
+            Ok(Command::Close) | Err(TryRecvError::Disconnected) => socket.close(None).map(|_| log(Direction::Info, String::from("closing"))),
+            Err(TryRecvError::Empty) => Ok(true),
+        };
+        let result = match sent {
+            Ok(_) => socket.read(),
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(Frame::Text(text)) => {
+                log(Direction::Received, text);
+            }
+            Ok(Frame::Binary(data)) => {
+                log(Direction::Received, format!("<{} bytes of binary data>", data.len()));
+            }
+            Ok(Frame::Ping(_)) => {
+                log(Direction::Info, String::from("ping received"));
+            }
+            Ok(Frame::Pong(_)) => {
+                log(Direction::Info, String::from("pong"));
+            }
+            Ok(Frame::Close(frame)) => {
+                log(Direction::Info, match frame {
+                    Some(frame) => format!("close received : {} {}", frame.code, frame.reason),
+                    None => String::from("close received"),
+                });
+            }
+            Ok(Frame::Frame(_)) => {}
+            Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+                // Sends the pongs queued by incoming pings.
+                let _ = socket.flush();
+            }
+            Err(tungstenite::Error::ConnectionClosed) | Err(tungstenite::Error::AlreadyClosed) => {
+                log(Direction::Info, String::from("connection closed"));
+                return;
+            }
+            Err(e) => {
+                log(Direction::Info, format!("connection lost : {}", e));
+                return;
+            }
+        }
+    }
+}
+
+impl Session {
+
+    /// Opens a session to the url of a WebSocket Request using its headers.
+    pub fn connect(request : &Request) -> Session {
+        Session::replay(request, &[])
+    }
+
+    /// Opens a session and sends the sent messages of a saved session again,
+    /// in order.
+    pub fn replay(
+        request : &Request,
+        saved : &[Message]) -> Session {
+
+        let (commands, commands_rx) = mpsc::channel();
+        let (messages_tx, messages) = mpsc::channel();
+        for saved_message in saved.iter().filter(|m| m.direction == Direction::Sent) {
+            let _ = commands.send(Command::Send(saved_message.content.clone()));
+        }
+
+        let request = request.clone();
+        thread::spawn(move || run(request, commands_rx, messages_tx));
+
+        Session {
+            commands,
+            messages,
+            log : vec![],
+            open : true,
+        }
+    }
+
+    pub fn send(&self, text : String) {
+        let _ = self.commands.send(Command::Send(text));
+    }
+
+    pub fn ping(&self) {
+        let _ = self.commands.send(Command::Ping);
+    }
+
+    pub fn close(&self) {
+        let _ = self.commands.send(Command::Close);
+    }
+
+    /// Adds a local note to the log, such as a frame that couldn't be sent.
+    pub fn note(&mut self, content : String) {
+        self.log.push(message(Direction::Info, content));
+    }
+
+    /// Moves the messages received since the last call into the log. The
+    /// session is no longer open once its thread has stopped.
+    pub fn poll(&mut self) {
+        loop {
+            match self.messages.try_recv() {
+                Ok(message) => self.log.push(message),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.open = false;
+                    return;
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+pub mod error;
 pub mod util;
 pub mod database;
 pub mod ui;
+pub mod http;
@@ -1,19 +1,23 @@
 use std::{error::Error, io};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
 
+mod error;
 mod util;
 mod database;
 mod ui;
+mod http;
 
-use util::event::{Event, Events};
-use util::dbhandler::Database;
-use termion::{event::Key, raw::IntoRawMode};
+use util::event::{self, Event, Events};
+use util::config::{self, Config};
+use termion::{event::{Key, MouseButton, MouseEvent}, input::MouseTerminal, raw::IntoRawMode};
 use termion::clear::*;
 use tui::{
     Terminal,
     backend::TermionBackend,
-    widgets::{Widget, Block, Borders, Paragraph, Wrap, Tabs, List, ListState, ListItem},
+    widgets::{Widget, Borders, Paragraph, Wrap, Tabs, List, ListState, ListItem, Sparkline, BarChart, Clear},
     layout::{Layout, Constraint, Direction, Rect, Alignment},
-    style::{Color, Modifier, Style},
+    style::Style,
     symbols::DOT,
     text::{Span, Spans},
 };
@@ -21,69 +25,733 @@ use tui::{
 use unicode_width::UnicodeWidthStr;
 
 use database::container::*;
-use ui::view;
-
+use ui::{command, finder::Finder, keymap::{Action, Keymap, Mode}, mouse::{self, Border}, status::Status, theme::Theme, tree::{self, Node}, view};
+use http::{bench::{self, Bench}, body, runner::{self, CollectionRun}, client::{self, Exchange, Response}, graphql::{self, Schema}, sse::{self, EventStream}, websocket::{self, Session}};
+use database::websocket::Direction as MessageDirection;
+use database::example::Example;
+use database::history::Sent;
+use database::script::{Owner, Scripts, Stage};
+use database::user::User;
+use database::path::*;
+use database::trash::{self, Trashed};
+use database::undo::{Edit, Undo};
+use database::storage::{cache::Cache, Storage};
+use database::websocket::Message;
+use database::selection::Selection;
+use http::script::{self, Context};
+
+#[derive(PartialEq)]
 enum InputMode {
     Normal,
     Command,
     Editing,
+    Schema,
+    Examples,
+    Trash,
+    Finder,
 }
 
-enum SelectionMode {
+/// The field of the selected request being edited in InputMode::Editing.
+#[derive(PartialEq, Clone, Copy)]
+enum EditField {
+    Body,
+    Headers,
+    Variables,
+    ExampleBody,
+    ExampleHeaders,
+    Script(Owner, Stage),
+}
 
-    Collections,
-    Requests,
+/// Where the panes were drawn in the last frame, to find what the mouse
+/// points at.
+#[derive(Default)]
+struct Areas {
+    tabs : Rect,
+    tree : Rect,
+    request : Rect,
+    editors : Vec<(EditField, Rect)>,
+    response : Rect,
 }
 
-struct App<'a> {
+struct App {
     input : String,
     input_mode : InputMode,
-    selection_mode : SelectionMode,
+    /// Position of the selected workspace among the tabs.
     selected_tab : usize,
+    /// Id of the workspace of the selected tab, what the selection follows.
+    selected_workspace : i64,
     tab_len : usize,
-    col_state : ListState,
-    req_state : ListState,
-    collection_list :  Vec<ListItem<'a>>,
-    request_list :  Vec<ListItem<'a>>,
+    workspaces : Vec<Workspace>,
+    /// Rows of the collections tree and the open collections and folders.
+    tree : Vec<tree::Row>,
+    tree_state : ListState,
+    /// The selected item of the tree, which keeps it selected as rows come
+    /// and go.
+    selected_node : Option<Node>,
+    tree_expanded : HashSet<Node>,
+    /// First row of the tree shown, following the list as it scrolls.
+    tree_offset : usize,
+    /// What the tree was built from : the generation of the cache, the
+    /// selected workspace and the open collections and folders.
+    loaded : Option<(u64, i64, HashSet<Node>)>,
+    selected_collection : i64,
+    /// The folder selected, or holding the selected request.
+    selected_folder : Option<i64>,
+    selected_request : Option<Request>,
+    edit_field : EditField,
+    response : String,
+    /// Lines of the response scrolled past with the wheel.
+    response_scroll : u16,
+    schema : Option<Schema>,
+    schema_url : String,
+    schema_state : ListState,
+    schema_expanded : HashSet<String>,
+    completions : Vec<String>,
+    websocket : Option<Session>,
+    websocket_request : i64,
+    exchange : Option<Exchange>,
+    exchange_request : Option<Request>,
+    /// The introspection query in flight, with the url whose schema it
+    /// fetches.
+    introspection : Option<(String, Exchange)>,
+    events : Option<EventStream>,
+    /// Last complete response with the id of its request, for capturing it as
+    /// an Example.
+    last_response : Option<(i64, Response)>,
+    examples : Vec<Example>,
+    examples_state : ListState,
+    /// The saved session of the selected WebSocket request.
+    messages : Vec<Message>,
+    bench : Option<Bench>,
+    run : Option<CollectionRun>,
+    /// Variables set by scripts, kept between sends.
+    variables : runner::Variables,
+    /// Context of the request being sent, for its post-response scripts.
+    pending_scripts : Option<(Context, Scripts)>,
+    script_report : String,
+    theme : Theme,
+    /// Candidates of the last completion of the command line.
+    command_completions : Vec<String>,
+    /// Past command lines, oldest first, and the one shown while walking them.
+    history : Vec<String>,
+    history_index : Option<usize>,
+    /// The line being typed before walking the history.
+    draft : String,
+    status : Status,
+    /// Edits of the session, for undo and redo.
+    undo : Undo,
+    /// Items in the trash while it is open.
+    trash : Vec<Trashed>,
+    trash_state : ListState,
+    finder : Finder,
+    /// Shares of the tree in the width and of the editors in the height of
+    /// the request, in percent, changed by dragging their borders.
+    tree_width : u16,
+    editor_height : u16,
+    areas : Areas,
+    /// The border being dragged.
+    drag : Option<Border>,
+    /// The selection as last saved, and the one waiting to be saved with
+    /// when it was last changed.
+    saved_selection : Selection,
+    unsaved_selection : Option<(Selection, Instant)>,
 }
 
-impl<'a> Default for App<'a> {
-    fn default() -> App<'a> {
+impl Default for App {
+    fn default() -> App {
         App {
             input : String::new(),
             input_mode : InputMode::Normal,
-            selection_mode : SelectionMode::Collections,
             selected_tab : 0,
+            selected_workspace : 0,
             tab_len : 0,
-            col_state : ListState::default(),
-            req_state : ListState::default(),
-            collection_list : Vec::new(),
-            request_list : Vec::new(),
+            workspaces : Vec::new(),
+            tree : Vec::new(),
+            tree_state : ListState::default(),
+            selected_node : None,
+            tree_expanded : HashSet::new(),
+            tree_offset : 0,
+            loaded : None,
+            selected_collection : 0,
+            selected_folder : None,
+            selected_request : None,
+            edit_field : EditField::Body,
+            response : String::new(),
+            response_scroll : 0,
+            schema : None,
+            schema_url : String::new(),
+            schema_state : ListState::default(),
+            schema_expanded : HashSet::new(),
+            completions : Vec::new(),
+            websocket : None,
+            websocket_request : 0,
+            exchange : None,
+            exchange_request : None,
+            introspection : None,
+            events : None,
+            last_response : None,
+            examples : Vec::new(),
+            examples_state : ListState::default(),
+            messages : Vec::new(),
+            bench : None,
+            run : None,
+            variables : runner::Variables::new(),
+            pending_scripts : None,
+            script_report : String::new(),
+            theme : Theme::default(),
+            command_completions : Vec::new(),
+            history : Vec::new(),
+            history_index : None,
+            draft : String::new(),
+            status : Status::default(),
+            undo : Undo::default(),
+            trash : Vec::new(),
+            trash_state : ListState::default(),
+            finder : Finder::default(),
+            tree_width : 20,
+            editor_height : 20,
+            areas : Areas::default(),
+            drag : None,
+            saved_selection : Selection::default(),
+            unsaved_selection : None,
+        }
+    }
+
+}
+
+/// Where the subcommands look names up : the workspace given with
+/// `--workspace`, or the first one of the user.
+///
+/// * `config` - the user and workspace options.
+/// * `db` - the storage to work on.
+fn config_scope(
+    config : &Config,
+    db : &dyn Storage) -> Result<Scope, Box<dyn Error>> {
+
+    let scope = Scope { id_user : config.user, ..Scope::default() };
+    let workspace = match &config.workspace {
+        Some(name) => find_one("workspace", name, find_workspaces(&scope, name, db)?)?,
+        None => db.get_all_workspaces(config.user)?.into_iter().next()
+            .ok_or(format!("user {} has no workspace", config.user))?,
+    };
+    Ok(Scope { id_workspace : workspace.id, ..scope })
+}
+
+/// `turl mock --collection <name> [--port <port>] [--host <address>]` : serves
+/// the requests of a collection with their saved examples, on the loopback
+/// interface unless another address is given.
+fn mock(
+    args : &[String],
+    config : &Config) -> Result<(), Box<dyn Error>> {
+
+    let mut collection = None;
+    let mut port = 8080;
+    let mut host = String::from("127.0.0.1");
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--collection" | "-c" => collection = args.next().cloned(),
+            "--port" | "-p" => port = args.next().ok_or("--port needs a value")?.parse()?,
+            "--host" => host = args.next().ok_or("--host needs a value")?.clone(),
+            _ => return Err(format!("unknown argument {}", arg).into()),
+        }
+    }
+    let name = collection.ok_or("usage : turl mock --collection <name> [--port <port>] [--host <address>]")?;
+
+    let storage = config.open_storage()?;
+    let db = storage.as_ref();
+    let scope = config_scope(config, db)?;
+    let collection = find_one("collection", &name, find_collections(&scope, &name, db)?)?;
+    http::mock::serve(http::mock::routes(collection.id, db)?, &host, port)
+}
+
+/// `turl bench --collection <name> --request <name> [flags]` : benchmarks a
+/// request, see `bench::USAGE` for the flags.
+fn bench(
+    args : &[String],
+    config : &Config) -> Result<(), Box<dyn Error>> {
+
+    let usage = format!("usage : turl bench --collection <name> --request <name> {}", bench::USAGE);
+    let mut collection = None;
+    let mut request = None;
+    let mut flags = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--collection" => collection = args.next().cloned(),
+            "--request" => request = args.next().cloned(),
+            _ => flags.push(arg.as_str()),
+        }
+    }
+    let (collection, request) = match (collection, request) {
+        (Some(collection), Some(request)) => (collection, request),
+        _ => return Err(usage.into()),
+    };
+    let bench_config = bench::Config::from_args(&flags)?;
+
+    let storage = config.open_storage()?;
+    let db = storage.as_ref();
+    let scope = config_scope(config, db)?;
+    let collection = find_one("collection", &collection, find_collections(&scope, &collection, db)?)?;
+    let scope = Scope { id_collection : Some(collection.id), ..scope };
+    let request = find_one("request", &request, find_requests(&scope, &request, db)?)?;
+
+    let mut stats = bench::Stats::default();
+    let mut last_second = 0;
+    let stop = std::sync::atomic::AtomicBool::new(false);
+    bench::run(&request, &bench_config, &stop, |sample| {
+        stats.add(&sample);
+        if stats.elapsed.as_secs() > last_second {
+            last_second = stats.elapsed.as_secs();
+            eprintln!("{} requests, {} errors", stats.total(), stats.errors);
+        }
+    })?;
+    println!("{}", stats.report());
+    Ok(())
+}
+
+/// `turl run --collection <name> [--data <file>]` : sends the requests of a
+/// collection once per row of a CSV or JSON data file.
+fn run(
+    args : &[String],
+    config : &Config) -> Result<(), Box<dyn Error>> {
+
+    let mut collection = None;
+    let mut data = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--collection" | "-c" => collection = args.next().cloned(),
+            "--data" | "-d" => data = args.next().cloned(),
+            _ => return Err(format!("unknown argument {}", arg).into()),
+        }
+    }
+    let name = collection.ok_or("usage : turl run --collection <name> [--data <file>]")?;
+    let data = match data {
+        Some(path) => runner::load_data(&path)?,
+        None => vec![],
+    };
+
+    let storage = config.open_storage()?;
+    let db = storage.as_ref();
+    let scope = config_scope(config, db)?;
+    let collection = find_one("collection", &name, find_collections(&scope, &name, db)?)?;
+    let requests = db.get_requests_with_scripts(collection.id)?;
+
+    let mut failed = 0;
+    let stop = std::sync::atomic::AtomicBool::new(false);
+    runner::run(&requests, data, &stop, |iteration| {
+        if !iteration.passed() {
+            failed += 1;
         }
+        println!("{}", iteration.report());
+    });
+    match failed {
+        0 => Ok(()),
+        failed => Err(format!("{} iterations failed", failed).into()),
+    }
+}
+
+/// What was read from the database, or nothing with the error shown in the
+/// status bar, so that drawing goes on.
+///
+/// * `result` - the result of the read.
+/// * `status` - the status bar to report to.
+fn loaded<T : Default>(
+    result : error::Result<T>,
+    status : &mut Status) -> T {
+
+    result.unwrap_or_else(|e| {
+        status.error(e.to_string());
+        T::default()
+    })
+}
+
+/// Selects the workspace of a tab, and the first row of its tree.
+fn select_tab(
+    app : &mut App,
+    tab : usize) {
+
+    if let Some(workspace) = app.workspaces.get(tab) {
+        app.selected_tab = tab;
+        app.selected_workspace = workspace.id;
+        app.selected_node = None;
+        app.tree_state.select(Some(0));
+    }
+}
+
+/// Selects a row of the tree, and the item it shows.
+fn select_row(
+    app : &mut App,
+    i : usize) {
+
+    app.tree_state.select(Some(i));
+    app.selected_node = app.tree.get(i).map(|row| row.node);
+}
+
+/// Records a send in the history of its request, a failed transfer with the
+/// status 0.
+///
+/// * `sent` - the request as sent, its variables substituted.
+/// * `response` - what came back, None if the transfer failed.
+/// * `db` - the storage to work on.
+fn record_sent(
+    sent : &Request,
+    response : Option<&Response>,
+    db : &dyn Storage) -> error::Result<()> {
+
+    db.add_sent(&Sent {
+        id_request : sent.id,
+        time : util::time::now_millis(),
+        method : sent.method.to_string(),
+        url : sent.url.clone(),
+        status : response.map(|response| response.status as i64).unwrap_or(0),
+        latency : response.map(|response| response.time.as_millis() as i64).unwrap_or(0),
+    })
+}
+
+/// Shows what an exchange ended with, records it and runs the post-response
+/// scripts of its request, once, the exchange being dropped after.
+fn finish_exchange(
+    app : &mut App,
+    mut exchange : Exchange,
+    db : &dyn Storage) -> error::Result<()> {
+
+    let error = exchange.error.take();
+    if let Some(events) = app.events.as_mut() {
+        events.ended = Some(error.unwrap_or_else(|| String::from("ended")));
+        return Ok(());
+    }
+    let pending = app.pending_scripts.take();
+    let sent = pending.as_ref().map(|(context, _)| &context.request).or(app.exchange_request.as_ref());
+    if let Some(e) = error {
+        app.response = e;
+        return sent.map(|sent| record_sent(sent, None, db)).unwrap_or(Ok(()));
+    }
+    let response = std::mem::take(&mut exchange.response);
+    app.response = view::response_to_text(&response);
+    let recorded = sent.map(|sent| record_sent(sent, Some(&response), db)).unwrap_or(Ok(()));
+    if let Some((context, scripts)) = pending {
+        let context = script::after(context, &response, &scripts);
+        app.script_report = context.report();
+        app.variables = context.variables;
+    }
+    if let Some(request) = &app.exchange_request {
+        app.last_response = Some((request.id, response));
+    }
+    recorded
+}
+
+/// Caches the schema an introspection query came back with.
+///
+/// * `url` - the endpoint the schema belongs to.
+/// * `exchange` - the finished introspection query.
+/// * `db` - the storage to work on.
+fn finish_introspection(
+    app : &mut App,
+    url : &str,
+    mut exchange : Exchange,
+    db : &dyn Storage) -> error::Result<()> {
+
+    if let Some(e) = exchange.error.take() {
+        app.status.error(e);
+        return Ok(());
+    }
+    match graphql::schema_of(&exchange.response) {
+        Ok(schema) => {
+            db.save_schema(url, &schema)?;
+            // Reloaded from the cache on the next draw.
+            app.schema_url.clear();
+            app.status.info(format!("schema of {} cached", url));
+        }
+        Err(e) => app.status.error(e.to_string()),
+    }
+    Ok(())
+}
+
+/// Starts editing a field of the selected request, from its stored value.
+fn edit(
+    app : &mut App,
+    field : EditField) {
+
+    let request = match &app.selected_request {
+        Some(request) => request,
+        None => return,
+    };
+    app.input = match field {
+        EditField::Body => request.body.clone(),
+        EditField::Headers => request.headers.clone(),
+        EditField::Variables if request.kind == RequestKind::Graphql => request.variables.clone(),
+        _ => return,
+    };
+    app.edit_field = field;
+    app.input_mode = InputMode::Editing;
+}
+
+/// The border of the last frame under a cell, the one between the tree and
+/// the request, or the one above the response.
+fn border_at(
+    areas : &Areas,
+    column : u16,
+    row : u16) -> Option<Border> {
+
+    let (tree, response) = (areas.tree, areas.response);
+    if row >= tree.top() && row < tree.bottom() && (column + 1 == tree.right() || column == areas.request.left()) {
+        Some(Border::Tree)
+    }
+    else if column >= response.left() && column < response.right() && (row + 1 == response.top() || row == response.top()) {
+        Some(Border::Response)
+    }
+    else {
+        None
+    }
+}
+
+/// Selects what a click of the normal mode lands on : a workspace tab, a row
+/// of the tree or a field of the request to edit.
+fn click(
+    app : &mut App,
+    column : u16,
+    row : u16) {
+
+    let areas = &app.areas;
+    if mouse::contains(areas.tabs, column, row) {
+        let names : Vec<&str> = app.workspaces.iter().map(|workspace| workspace.name.as_str()).collect();
+        if let Some(tab) = mouse::tab_at(&names, areas.tabs, column) {
+            select_tab(app, tab);
+        }
+    }
+    else if mouse::contains(areas.tree, column, row) {
+        // A click on the selected collection or folder opens or closes it.
+        if let Some(i) = mouse::row_at(areas.tree, app.tree_offset, app.tree.len(), column, row) {
+            let clicked = &app.tree[i];
+            if app.tree_state.selected() == Some(i) && clicked.request.is_none() {
+                let node = clicked.node;
+                if !app.tree_expanded.remove(&node) {
+                    app.tree_expanded.insert(node);
+                }
+            }
+            select_row(app, i);
+        }
+    }
+    else if let Some(&(field, _)) = areas.editors.iter().find(|(_, area)| mouse::contains(*area, column, row)) {
+        edit(app, field);
+    }
+}
+
+/// Handles a mouse event. Clicks go to `click` in the normal mode, dragging a
+/// border resizes the panes around it, and the wheel scrolls the response.
+fn handle_mouse(
+    mouse : MouseEvent,
+    app : &mut App) {
+
+    match mouse {
+        MouseEvent::Press(button, column, row) => {
+            // Terminals count from 1, tui from 0.
+            let (column, row) = (column.saturating_sub(1), row.saturating_sub(1));
+            let areas = &app.areas;
+            match button {
+                MouseButton::Left => {
+                    if let Some(border) = border_at(areas, column, row) {
+                        app.drag = Some(border);
+                    }
+                    else if app.input_mode == InputMode::Normal {
+                        click(app, column, row);
+                    }
+                }
+                MouseButton::WheelUp if mouse::contains(areas.response, column, row) => {
+                    app.response_scroll = app.response_scroll.saturating_sub(3);
+                }
+                MouseButton::WheelDown if mouse::contains(areas.response, column, row) => {
+                    // Stop with the last line at the top, long lines taking
+                    // as many lines as they wrap to.
+                    let width = areas.response.width.max(1) as usize;
+                    let lines : usize = app.response.lines()
+                        .chain(app.script_report.lines())
+                        .map(|line| line.width().div_ceil(width).max(1))
+                        .sum();
+                    app.response_scroll = (app.response_scroll + 3).min(lines.saturating_sub(1) as u16);
+                }
+                _ => {}
+            }
+        }
+        MouseEvent::Hold(column, row) => {
+            let (column, row) = (column.saturating_sub(1), row.saturating_sub(1));
+            let areas = &app.areas;
+            match app.drag {
+                Some(Border::Tree) => {
+                    app.tree_width = mouse::percent(areas.tree.left(), areas.tree.width + areas.request.width, column + 1);
+                }
+                Some(Border::Response) => {
+                    // The editors and the response share the request pane
+                    // inside its border.
+                    app.editor_height = mouse::percent(areas.request.top() + 1, areas.request.height.saturating_sub(2), row);
+                }
+                None => {}
+            }
+        }
+        MouseEvent::Release(..) => app.drag = None,
+    }
+}
+
+/// Brings what the next frame shows up to date with the storage. The tree is
+/// built again only when something was written, or another workspace or
+/// other collections and folders were opened, the rest comes out of the
+/// cache.
+///
+/// * `app` - the state drawn.
+/// * `user` - the user whose workspaces are shown.
+/// * `cache` - the storage, kept in memory.
+fn refresh(
+    app : &mut App,
+    user : &User,
+    cache : &Cache) {
+
+    let current = app.loaded.as_ref()
+        .map(|(generation, id_workspace, expanded)| {
+            *generation == cache.generation() && *id_workspace == app.selected_workspace && *expanded == app.tree_expanded
+        })
+        .unwrap_or(false);
+    if !current {
+        app.workspaces = loaded(cache.get_all_workspaces(user.id), &mut app.status);
+        app.tab_len = app.workspaces.len();
+        // A workspace that is gone leaves its place to the first one.
+        app.selected_tab = app.workspaces.iter().position(|workspace| workspace.id == app.selected_workspace).unwrap_or(0);
+        app.selected_workspace = app.workspaces.get(app.selected_tab).map(|workspace| workspace.id).unwrap_or_default();
+
+        // The collections of the workspace, with the folders and requests
+        // of the expanded ones.
+        let mut collections : Vec<(Collection, Option<tree::Contents>)> = vec![];
+        for collection in loaded(cache.get_all_collections(app.selected_workspace), &mut app.status) {
+            let contents = if app.tree_expanded.contains(&Node::Collection(collection.id)) {
+                Some(tree::Contents {
+                    folders : loaded(cache.get_all_folders(collection.id), &mut app.status),
+                    requests : loaded(cache.get_all_requests(collection.id), &mut app.status),
+                })
+            }
+            else {
+                None
+            };
+            collections.push((collection, contents));
+        }
+        app.tree = tree::rows(&collections, &app.tree_expanded);
+        app.loaded = Some((cache.generation(), app.selected_workspace, app.tree_expanded.clone()));
+    }
+
+    // The selected item keeps its row as the tree changes, once it is gone
+    // the row where it was is selected.
+    let found = app.selected_node.and_then(|node| app.tree.iter().position(|row| row.node == node));
+    let selected = found.or_else(|| app.tree_state.selected()).unwrap_or(0).min(app.tree.len().saturating_sub(1));
+    app.tree_state.select(Some(selected));
+    let row = app.tree.get(selected);
+    app.selected_node = row.map(|row| row.node);
+    app.selected_collection = row.map(|row| row.id_collection).unwrap_or(0);
+    app.selected_folder = row.and_then(|row| row.id_folder);
+    app.selected_request = row.and_then(|row| row.request.clone());
+
+    let request = match app.selected_request.clone() {
+        Some(request) => request,
+        None => {
+            app.examples.clear();
+            app.messages.clear();
+            return;
+        }
+    };
+
+    // Load the cached schema of a GraphQL endpoint when it changes.
+    if request.kind == RequestKind::Graphql && app.schema_url != request.url {
+        app.schema = loaded(cache.get_schema(&request.url), &mut app.status)
+            .and_then(|schema| Schema::from_json(&schema));
+        app.schema_url = request.url.clone();
+        app.schema_expanded.clear();
+        app.schema_state.select(Some(0));
+    }
+
+    if app.input_mode == InputMode::Examples || app.edit_field == EditField::ExampleBody || app.edit_field == EditField::ExampleHeaders {
+        app.examples = loaded(cache.get_examples(request.id), &mut app.status);
+    }
+
+    if request.kind == RequestKind::Websocket {
+        app.messages = loaded(cache.get_messages(request.id), &mut app.status);
     }
+}
 
+/// Renders an editable field. While it is being edited it shows the input
+/// buffer instead of the stored value.
+fn editor<'a>(
+    app : &App,
+    field : EditField,
+    stored : &str,
+    title : &'a str) -> Paragraph<'a> {
+
+    let editing = app.input_mode == InputMode::Editing && app.edit_field == field;
+    Paragraph::new(if editing { app.input.clone() } else { stored.to_owned() })
+        .style(if editing { app.theme.editing } else { Style::default() })
+        .block(app.theme.block().title(title).borders(Borders::ALL))
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let stdout = io::stdout().into_raw_mode()?;
-    let backend = TermionBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    let args : Vec<String> = std::env::args().skip(1).collect();
+    let (config, args) = Config::load(&args)?;
+    match args.first().map(|command| command.as_str()) {
+        Some("mock") => return mock(&args[1..], &config),
+        Some("bench") => return bench(&args[1..], &config),
+        Some("run") => return run(&args[1..], &config),
+        Some(arg) => return Err(format!("unknown argument {}, usage : turl {} [mock|bench|run ...]", arg, config::USAGE).into()),
+        None => {}
+    }
 
-    let mut app = App::default();
+    let keymap = Keymap::load()?;
 
-    let events = Events::new();
+    let mut app = App {
+        theme : Theme::named(&config.theme, &config.themes)?,
+        ..App::default()
+    };
+
+    app.tree_state.select(Some(0));
 
-    app.col_state.select(Some(0));
-    app.req_state.select(Some(0));
+    let cache = Cache::new(config.open_storage()?);
+    let db : &dyn Storage = &cache;
 
-    let db = &Database {
-        filename : String::from("./.database"),
-        connection : sqlite::open("./.database").unwrap(),
+    // A new database gets the default user and a first workspace.
+    let user = &match db.get_user(config.user) {
+        Ok(Some(user)) => user,
+        _ if config.user == Config::default().user => {
+            let name = std::env::var("USER").unwrap_or_else(|_| String::from("turl"));
+            let user = db.create_user(config.user, &name)?;
+            db.create_workspace(&user, "default")?;
+            user
+        }
+        _ => return Err(format!("user {} not found in {}", config.user, config.database).into()),
     };
 
-    // This is here for tests mainly. 
-    let user = &database::user::get_user(1, db).unwrap().unwrap();
+    // Open where the user left, unless another workspace is asked for.
+    if let Some(selection) = loaded(db.get_selection(user.id), &mut app.status) {
+        app.selected_workspace = selection.id_workspace;
+        app.selected_node = selection.item.and_then(Node::from_item);
+        app.tree_expanded = selection.expanded.iter().copied().filter_map(Node::from_item).collect();
+        app.tree_offset = selection.tree_offset;
+        app.response_scroll = selection.response_scroll;
+        app.saved_selection = selection;
+    }
+    if let Some(name) = &config.workspace {
+        let workspaces = db.get_all_workspaces(user.id)?;
+        let workspace = workspaces.iter()
+            .find(|workspace| &workspace.name == name)
+            .ok_or(format!("workspace {} not found", name))?;
+        if workspace.id != app.selected_workspace {
+            app.selected_workspace = workspace.id;
+            app.selected_node = None;
+            app.tree_offset = 0;
+        }
+    }
+    app.history = db.get_commands()?;
+
+    let stdout = MouseTerminal::from(io::stdout().into_raw_mode()?);
+    let backend = TermionBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
 
+    let events = Events::with_config(event::Config { tick_rate : config.tick_rate });
 
     terminal.clear()?;
 
@@ -93,6 +761,64 @@ fn main() -> Result<(), Box<dyn Error>> {
         //So this is probably not needed.
         terminal.autoresize()?;
 
+        if let Some(session) = app.websocket.as_mut() {
+            session.poll();
+        }
+
+        if let Some(bench) = app.bench.as_mut() {
+            bench.poll();
+        }
+
+        if let Some(run) = app.run.as_mut() {
+            run.poll();
+        }
+
+        // Event streams are parsed as they arrive, other bodies are shown
+        // once complete.
+        let done = match app.exchange.as_mut() {
+            Some(exchange) => {
+                let received = exchange.poll();
+                match app.events.as_mut() {
+                    Some(events) => events.feed(&received),
+                    None if sse::is_event_stream(&exchange.response) => {
+                        let mut events = EventStream::default();
+                        events.feed(&exchange.response.body);
+                        app.events = Some(events);
+                    }
+                    None if !received.is_empty() => {
+                        app.response = format!("receiving... ({} bytes)", exchange.response.body.len());
+                    }
+                    None => {}
+                }
+                exchange.done
+            }
+            None => false,
+        };
+        if done {
+            if let Some(exchange) = app.exchange.take() {
+                if let Err(e) = finish_exchange(&mut app, exchange, db) {
+                    app.status.error(e.to_string());
+                }
+            }
+        }
+
+        let introspected = match app.introspection.as_mut() {
+            Some((_, exchange)) => {
+                exchange.poll();
+                exchange.done
+            }
+            None => false,
+        };
+        if introspected {
+            if let Some((url, exchange)) = app.introspection.take() {
+                if let Err(e) = finish_introspection(&mut app, &url, exchange, db) {
+                    app.status.error(e.to_string());
+                }
+            }
+        }
+
+        refresh(&mut app, user, &cache);
+
         //render UI
         terminal.draw(|f| {
 
@@ -103,91 +829,73 @@ fn main() -> Result<(), Box<dyn Error>> {
                              Constraint::Percentage(10),
                              Constraint::Percentage(80),
                              Constraint::Percentage(10),
+                             Constraint::Length(1),
                 ].as_ref()
                 )
                 .split(f.size());
 
-            let workspaces = get_all_workspaces(1, db).unwrap();
-            app.tab_len = workspaces.len();
-            let workspace_spans = view::container_to_spans(workspaces);
+            let workspace_spans = view::container_to_spans(app.workspaces.clone());
 
             // tabs for Workspaces
             let tabs = Tabs::new(workspace_spans)
-                .block(Block::default().title("Workspaces").borders(Borders::ALL))
-                .highlight_style(Style::default().fg(Color::Black).bg(Color::White))
+                .block(app.theme.block().title("Workspaces").borders(Borders::ALL))
+                .highlight_style(app.theme.tab_highlight)
                 .select(app.selected_tab)
                 .divider(DOT);
             f.render_widget(tabs, chunks[0]);
+            app.areas.tabs = chunks[0];
 
             let horizontal_chunks = Layout::default()
                 .direction(Direction::Horizontal)
                 .margin(1)
                 .constraints(
                     [
-                    Constraint::Percentage(10),
-                    Constraint::Percentage(90)
+                    Constraint::Percentage(app.tree_width),
+                    Constraint::Percentage(100 - app.tree_width)
                     ].as_ref()
                     )
                 .split(chunks[1]);
 
 
-            let left_bar_chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints(
-                    [
-                    Constraint::Percentage(50),
-                    Constraint::Percentage(50),
-                    ].as_ref()
-                    )
-                .split(horizontal_chunks[0]);
-            // fetch the collections corresponding to the workspace. 
-            let mut collections = get_all_collections(app.selected_tab as i64 + 1 , db).unwrap();
-            let mut collections_for_id = collections.clone();
-            if collections.len() < 1 {
-                collections.push(Collection::new(0, String::from("Empty")));
-            }
-            let collection_items = view::container_to_ListItem(collections);
-            app.collection_list = collection_items.clone();
-
-            // Render the collections of a workspace in a Widget::List
-            let collection_list = List::new(app.collection_list.clone())
-                .block(Block::default().title("Collections").borders(Borders::ALL))
-                .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
-                .highlight_symbol(">>");
-            f.render_stateful_widget(collection_list, left_bar_chunks[0],&mut app.col_state);
-
-            // let mut collections_for_id = get_all_collections(app.selected_tab as i64 + 1, db).unwrap();
-            if collections_for_id.len() < 1 {
-                collections_for_id.push(Collection::new(0, String::from("Empty")));
-            }
-            let id_selected_col = collections_for_id[app.col_state.selected().unwrap()].id;
-
-            //fetch all requests
-            //TODO fix the id when getting all requests. 
-            let requests = get_all_requests(id_selected_col, db).unwrap();
-            let mut r = requests.clone();
-
-            let request_items = view::request_to_ListItem(requests);
-            app.request_list = request_items;
-            let request_list = List::new(app.request_list.clone())
-                .block(Block::default().title("Requests").borders(Borders::ALL))
-                .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
-                .highlight_symbol(">>");
-            f.render_stateful_widget(request_list, left_bar_chunks[1],&mut app.req_state);
+            // Render the tree of the workspace in a Widget::List, or the
+            // trash while it is open.
+            if app.input_mode == InputMode::Trash {
+                let trash_items = if app.trash.is_empty() { vec![ListItem::new("Empty")] } else { view::trash_to_list_items(&app.trash, &app.theme) };
+                let trash_list = List::new(trash_items)
+                    .block(app.theme.block().title("Trash").borders(Borders::ALL))
+                    .highlight_style(app.theme.list_highlight)
+                    .highlight_symbol(">>");
+                f.render_stateful_widget(trash_list, horizontal_chunks[0], &mut app.trash_state);
+            }
+            else {
+                let tree_items = if app.tree.is_empty() { vec![ListItem::new("Empty")] } else { view::tree_to_list_items(&app.tree, &app.theme) };
+                let height = horizontal_chunks[0].height.saturating_sub(2) as usize;
+                app.tree_offset = mouse::list_offset(app.tree_offset, app.tree_state.selected(), tree_items.len(), height);
+                // The list keeps its offset private : show it from ours, so a
+                // restored offset holds, with the selection always in view.
+                let mut tree_state = ListState::default();
+                tree_state.select(app.tree_state.selected().map(|i| i - app.tree_offset));
+                let tree_list = List::new(tree_items.into_iter().skip(app.tree_offset).collect::<Vec<_>>())
+                    .block(app.theme.block().title("Collections").borders(Borders::ALL))
+                    .highlight_style(app.theme.list_highlight)
+                    .highlight_symbol(">>");
+                f.render_stateful_widget(tree_list, horizontal_chunks[0], &mut tree_state);
+            }
+            app.areas.tree = horizontal_chunks[0];
+            app.areas.request = horizontal_chunks[1];
 
             // render request method and name.
-            if r.len() < 1 {
-                r.push(Request::new(0,String::from("add Request"),Methods::GET,String::from("This collection is empty, add a request !"), String::from(" "), String::from(" ")));
-            }
-            let request = &r[app.req_state.selected().unwrap()];
+            let request = &app.selected_request.clone().unwrap_or_else(|| {
+                Request::new(0,String::from("add Request"),Methods::GET,String::from("Select a request, or add one !"), String::from(" "), String::from(" "))
+            });
 
             let request_paragraph = Paragraph::new(vec![
-                                                   Spans::from(vec![Span::styled(request.method.to_string(), Style::default().add_modifier(Modifier::ITALIC)),
+                                                   Spans::from(vec![Span::styled(request.method.to_string(), app.theme.method(&request.method)),
                                                    Span::styled("   ", Style::default()),
                                                    Span::styled(request.url.clone(), Style::default()),
                                                    ]),
             ])
-                .block(Block::default()
+                .block(app.theme.block()
                        .title("Edit Request")
                        .borders(Borders::ALL));
             f.render_widget(request_paragraph, horizontal_chunks[1]);
@@ -197,15 +905,217 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .margin(1)
                 .constraints(
                     [
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(80),
+                    Constraint::Percentage(app.editor_height),
+                    Constraint::Percentage(100 - app.editor_height),
                     ])
                 .split(horizontal_chunks[1]);
 
-            let block = Block::default()
-                .title("Response")
-                .borders(Borders::TOP);
-            f.render_widget(block, request_chunks[1]);
+            // GraphQL requests get a variables editor next to the query.
+            let editor_constraints = match request.kind {
+                RequestKind::Http | RequestKind::Websocket => vec![Constraint::Percentage(40), Constraint::Percentage(60)],
+                RequestKind::Graphql => vec![Constraint::Percentage(30), Constraint::Percentage(40), Constraint::Percentage(30)],
+            };
+            let editor_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(editor_constraints)
+                .split(Layout::default()
+                       .direction(Direction::Vertical)
+                       .constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+                       .split(request_chunks[0])[1]);
+            let fields = match request.kind {
+                RequestKind::Http | RequestKind::Websocket => vec![EditField::Headers, EditField::Body],
+                RequestKind::Graphql => vec![EditField::Headers, EditField::Body, EditField::Variables],
+            };
+            app.areas.editors = fields.into_iter().zip(editor_chunks.iter().copied()).collect();
+            app.areas.response = request_chunks[1];
+
+            f.render_widget(editor(&app, EditField::Headers, &request.headers, "Headers"), editor_chunks[0]);
+            match request.kind {
+                RequestKind::Http => {
+                    f.render_widget(editor(&app, EditField::Body, &request.body, body::editor_title(&request.body_mode)), editor_chunks[1]);
+                }
+                RequestKind::Graphql => {
+                    f.render_widget(editor(&app, EditField::Body, &request.body, "Query"), editor_chunks[1]);
+                    f.render_widget(editor(&app, EditField::Variables, &request.variables, "Variables"), editor_chunks[2]);
+                }
+                RequestKind::Websocket => {
+                    let title = if request.body_mode == BodyMode::Json { "Message (JSON)" } else { "Message (text)" };
+                    f.render_widget(editor(&app, EditField::Body, &request.body, title), editor_chunks[1]);
+                }
+            }
+
+            match (&app.input_mode, &app.schema) {
+                // Saved examples of the request, viewable without a connection.
+                _ if app.input_mode == InputMode::Examples
+                    || (app.input_mode == InputMode::Editing
+                        && (app.edit_field == EditField::ExampleBody || app.edit_field == EditField::ExampleHeaders)) => {
+                    let example_chunks = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints([Constraint::Percentage(25), Constraint::Percentage(75)].as_ref())
+                        .split(request_chunks[1]);
+                    let example_list = List::new(view::examples_to_list_items(&app.examples))
+                        .block(app.theme.block().title("Examples").borders(Borders::TOP | Borders::RIGHT))
+                        .highlight_style(app.theme.list_highlight)
+                        .highlight_symbol(">>");
+                    f.render_stateful_widget(example_list, example_chunks[0], &mut app.examples_state);
+
+                    if let Some(example) = app.examples_state.selected().and_then(|i| app.examples.get(i)) {
+                        let detail_chunks = Layout::default()
+                            .direction(Direction::Vertical)
+                            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+                            .split(example_chunks[1]);
+                        let title = format!("{} : {} ({} ms latency)", example.name, example.status, example.latency);
+                        f.render_widget(editor(&app, EditField::ExampleHeaders, &example.headers, "Headers")
+                                        .block(app.theme.block().title(title).borders(Borders::TOP)), detail_chunks[0]);
+                        f.render_widget(editor(&app, EditField::ExampleBody, &example.body, "Body")
+                                        .block(app.theme.block().title("Body").borders(Borders::TOP)), detail_chunks[1]);
+                    }
+                }
+                _ if app.input_mode == InputMode::Editing && matches!(app.edit_field, EditField::Script(..)) => {
+                    let title = match app.edit_field {
+                        EditField::Script(Owner::Request, Stage::PreRequest) => "Pre-request script",
+                        EditField::Script(Owner::Request, Stage::PostResponse) => "Post-response script",
+                        EditField::Script(Owner::Collection, Stage::PreRequest) => "Collection pre-request script",
+                        _ => "Collection post-response script",
+                    };
+                    f.render_widget(editor(&app, app.edit_field, "", title)
+                                    .block(app.theme.block().title(title).borders(Borders::TOP)), request_chunks[1]);
+                }
+                _ if app.bench.is_some() => {
+                    let bench = app.bench.as_ref().unwrap();
+                    let bench_chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([Constraint::Length(4), Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+                        .split(request_chunks[1]);
+                    let status = match (&bench.error, bench.done) {
+                        (Some(e), _) => e.clone(),
+                        (None, true) => String::from("done"),
+                        (None, false) => String::from("running"),
+                    };
+                    let report = Paragraph::new(bench.stats.report())
+                        .block(app.theme.block()
+                               .title(format!("Bench, concurrency {} ({})", bench.config.concurrency, status))
+                               .borders(Borders::TOP));
+                    f.render_widget(report, bench_chunks[0]);
+
+                    let throughput = Sparkline::default()
+                        .data(&bench.stats.per_second)
+                        .style(app.theme.accent)
+                        .block(app.theme.block().title("Requests per second").borders(Borders::TOP));
+                    f.render_widget(throughput, bench_chunks[1]);
+
+                    let histogram = bench.stats.histogram(8);
+                    let bars : Vec<(&str, u64)> = histogram.iter()
+                        .map(|(label, count)| (label.as_str(), *count))
+                        .collect();
+                    let bar_width = (bench_chunks[2].width / 8).saturating_sub(1).max(1);
+                    let latencies = BarChart::default()
+                        .data(&bars)
+                        .bar_width(bar_width)
+                        .bar_style(app.theme.accent)
+                        .block(app.theme.block().title("Latency (ms)").borders(Borders::TOP));
+                    f.render_widget(latencies, bench_chunks[2]);
+                }
+                _ if app.run.is_some() => {
+                    let report = app.run.as_ref().unwrap().report();
+                    // Keep the latest iterations in view.
+                    let height = request_chunks[1].height.saturating_sub(1) as usize;
+                    let scroll = report.lines().count().saturating_sub(height) as u16;
+                    let run_report = Paragraph::new(report)
+                        .scroll((scroll, 0))
+                        .block(app.theme.block()
+                               .title("Collection run")
+                               .borders(Borders::TOP));
+                    f.render_widget(run_report, request_chunks[1]);
+                }
+                _ if request.kind == RequestKind::Websocket => {
+                    // The live session of this request, or the one saved with it.
+                    let (saved, status) = match &app.websocket {
+                        Some(session) if app.websocket_request == request.id => {
+                            (session.log.clone(), if session.open { "connected" } else { "closed" })
+                        }
+                        _ => (app.messages.clone(), "saved"),
+                    };
+                    let lines : Vec<Spans> = saved.iter().map(|message| {
+                        let (arrow, style) = match message.direction {
+                            MessageDirection::Sent => (">>", app.theme.accent),
+                            MessageDirection::Received => ("<<", app.theme.status.success),
+                            MessageDirection::Info => ("--", app.theme.muted),
+                        };
+                        Spans::from(vec![
+                                    Span::styled(format!("{} {} ", util::time::clock(message.time), arrow), style),
+                                    Span::raw(message.content.clone()),
+                        ])
+                    }).collect();
+                    // Keep the latest messages in view.
+                    let height = request_chunks[1].height.saturating_sub(1) as usize;
+                    let scroll = lines.len().saturating_sub(height) as u16;
+                    let session_log = Paragraph::new(lines)
+                        .scroll((scroll, 0))
+                        .block(app.theme.block()
+                               .title(format!("Session ({})", status))
+                               .borders(Borders::TOP));
+                    f.render_widget(session_log, request_chunks[1]);
+                }
+                _ if app.events.is_some() => {
+                    let mut lines : Vec<Spans> = vec![];
+                    for event in app.events.iter().flat_map(|events| events.events.iter()) {
+                        let mut head = format!("{} {}", util::time::clock(event.time), event.event);
+                        if let Some(id) = &event.id {
+                            head.push_str(&format!(" id={}", id));
+                        }
+                        if let Some(retry) = event.retry {
+                            head.push_str(&format!(" retry={}", retry));
+                        }
+                        lines.push(Spans::from(Span::styled(head, app.theme.muted)));
+                        for data in event.data.lines() {
+                            lines.push(Spans::from(Span::raw(format!("  {}", data))));
+                        }
+                    }
+                    let status = app.events.as_ref()
+                        .and_then(|events| events.ended.clone())
+                        .unwrap_or_else(|| String::from("streaming"));
+                    // Keep the latest events in view.
+                    let height = request_chunks[1].height.saturating_sub(1) as usize;
+                    let scroll = lines.len().saturating_sub(height) as u16;
+                    let event_list = Paragraph::new(lines)
+                        .scroll((scroll, 0))
+                        .block(app.theme.block()
+                               .title(format!("Events ({})", status))
+                               .borders(Borders::TOP));
+                    f.render_widget(event_list, request_chunks[1]);
+                }
+                (InputMode::Schema, Some(schema)) => {
+                    let schema_tree = List::new(view::schema_to_list_items(schema.tree(&app.schema_expanded)))
+                        .block(app.theme.block()
+                               .title(format!("Schema of {}", app.schema_url))
+                               .borders(Borders::TOP))
+                        .highlight_style(app.theme.list_highlight)
+                        .highlight_symbol(">>");
+                    f.render_stateful_widget(schema_tree, request_chunks[1], &mut app.schema_state);
+                }
+                _ if !app.script_report.is_empty() => {
+                    let mut text = view::highlight_response(&app.response, &app.theme);
+                    text.push(Spans::default());
+                    text.extend(app.script_report.lines().map(|line| Spans::from(line.to_owned())));
+                    let response = Paragraph::new(text)
+                        .wrap(Wrap { trim : false })
+                        .scroll((app.response_scroll, 0))
+                        .block(app.theme.block()
+                               .title("Response")
+                               .borders(Borders::TOP));
+                    f.render_widget(response, request_chunks[1]);
+                }
+                _ => {
+                    let response = Paragraph::new(view::highlight_response(&app.response, &app.theme))
+                        .wrap(Wrap { trim : false })
+                        .scroll((app.response_scroll, 0))
+                        .block(app.theme.block()
+                               .title("Response")
+                               .borders(Borders::TOP));
+                    f.render_widget(response, request_chunks[1]);
+                }
+            }
 
             //input chunk (block ? I don't know)
 
@@ -219,15 +1129,66 @@ fn main() -> Result<(), Box<dyn Error>> {
                     )
                 .split(chunks[2]);
 
-            let input = Paragraph::new(app.input.as_ref())
+            // Completions of the GraphQL query being edited take the place of
+            // the input line.
+            let input_text = if app.input_mode == InputMode::Editing && !app.completions.is_empty() {
+                app.completions.join("  ")
+            }
+            else {
+                app.input.clone()
+            };
+            let input = Paragraph::new(input_text)
                 .style(match app.input_mode {
                     InputMode::Normal => Style::default(),
                     InputMode::Command => Style::default(),
-                    InputMode::Editing => Style::default().fg(Color::Yellow),
+                    InputMode::Editing => app.theme.editing,
+                    InputMode::Schema => Style::default(),
+                    InputMode::Examples => Style::default(),
+                    InputMode::Trash => Style::default(),
+                    InputMode::Finder => Style::default(),
                 })
-            .block(Block::default().borders(Borders::ALL).title("Input"));
+            .block(app.theme.block().borders(Borders::ALL).title(match app.input_mode {
+                InputMode::Command if !app.command_completions.is_empty() => app.command_completions.join("  "),
+                _ => String::from("Input"),
+            }));
             f.render_widget(input, input_chunk[0]);
 
+            // Status bar, the last message until it expires.
+            let status = match app.status.current() {
+                Some(message) => Span::styled(message.text.clone(), message.severity.style(&app.theme)),
+                None => Span::styled(match app.input_mode {
+                    InputMode::Normal => "NORMAL",
+                    InputMode::Command => "COMMAND",
+                    InputMode::Editing => "EDITING",
+                    InputMode::Schema => "SCHEMA",
+                    InputMode::Examples => "EXAMPLES",
+                    InputMode::Trash => "TRASH",
+                    InputMode::Finder => "FIND",
+                }, app.theme.muted),
+            };
+            f.render_widget(Paragraph::new(Spans::from(status)), chunks[3]);
+
+            // Finder popup over everything else.
+            if app.input_mode == InputMode::Finder {
+                let area = view::centered(f.size(), 70, 60);
+                let finder_chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
+                    .split(area);
+                f.render_widget(Clear, area);
+                let query = Paragraph::new(app.finder.query.clone())
+                    .block(app.theme.block().title("Find a request").borders(Borders::ALL));
+                f.render_widget(query, finder_chunks[0]);
+                let matches = app.finder.matches();
+                let finder_items = if matches.is_empty() { vec![ListItem::new("No match")] } else { view::finder_to_list_items(&matches, &app.theme) };
+                let finder_list = List::new(finder_items)
+                    .block(app.theme.block().borders(Borders::ALL))
+                    .highlight_style(app.theme.list_highlight)
+                    .highlight_symbol(">>");
+                f.render_stateful_widget(finder_list, finder_chunks[1], &mut app.finder.state);
+                f.set_cursor(finder_chunks[0].x + app.finder.query.width() as u16 + 1, finder_chunks[0].y + 1);
+            }
+
             //Move cursor to the bottom of the page.
             match app.input_mode {
                 InputMode::Normal => {}
@@ -241,221 +1202,1009 @@ fn main() -> Result<(), Box<dyn Error>> {
                         )
                 }
                 InputMode::Editing => {}
+                InputMode::Schema => {}
+                InputMode::Examples => {}
+                InputMode::Trash => {}
+                InputMode::Finder => {}
 
             };
         })?;
 
         //call of the input event handler
-        if let Event::Input(input) = events.next()? {
-            match app.input_mode {
-
-                InputMode::Normal => match input {
+        match events.next()? {
+            Event::Input(input) => match handle_input(input, &mut app, &keymap, user, db) {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(e) => app.status.error(e.to_string()),
+            },
+            Event::Mouse(mouse) => handle_mouse(mouse, &mut app),
+            Event::Tick => {}
+        }
 
+        if let Err(e) = save_selection(&mut app, user, db, false) {
+            app.status.error(e.to_string());
+        }
+    }
 
-                    //TODO 
-                    //Get rid of this
-                    Key::Char('w') => {
-                        create_workspace(user, "test", db)?;
-                    }
-                    Key::Char('r') => {
-                        // create_request(collection.name(), 1, String::from("GET"), String::from("http://localhost"),db)?;
-                    }
-                    Key::Char('i') => {
-                        create_collection("test", 1, db)?;
-                    }
-                    //
-                    
-                    Key::Char(' ') => {
-                        match app.selection_mode {
-                            SelectionMode::Collections => {
-                                app.selection_mode = SelectionMode::Requests;
-                            }
-                            SelectionMode::Requests => {
-                                app.selection_mode = SelectionMode::Collections;
-                            }
-                        }
+    // Reopen here next time.
+    save_selection(&mut app, user, db, true)?;
+    Ok(())
+}
 
-                    }
-                    Key::Char(':') => {
-                        app.input_mode = InputMode::Command;
-                    }
+/// How long the selection must stay the same before it is saved, so walking
+/// through the tree writes it once.
+const SELECTION_DELAY : Duration = Duration::from_millis(500);
+
+/// Where the user is in the UI, to open it there again.
+fn selection(app : &App) -> Selection {
+    let mut expanded : Vec<(trash::Kind, i64)> = app.tree_expanded.iter().map(Node::item).collect();
+    expanded.sort_by_key(|(kind, id)| (kind.to_string(), *id));
+    Selection {
+        id_workspace : app.selected_workspace,
+        item : app.selected_node.map(|node| node.item()),
+        expanded,
+        tree_offset : app.tree_offset,
+        response_scroll : app.response_scroll,
+    }
+}
 
+/// Saves the selection once it has not changed for `SELECTION_DELAY`.
+///
+/// * `now` - save a changed selection without waiting, when quitting.
+fn save_selection(
+    app : &mut App,
+    user : &User,
+    db : &dyn Storage,
+    now : bool) -> error::Result<()> {
+
+    let current = selection(app);
+    if current == app.saved_selection {
+        app.unsaved_selection = None;
+        return Ok(());
+    }
+    match &app.unsaved_selection {
+        Some((unsaved, _)) if *unsaved == current => {}
+        _ => app.unsaved_selection = Some((current, Instant::now())),
+    }
+    if let Some((unsaved, changed)) = app.unsaved_selection.take() {
+        if now || changed.elapsed() >= SELECTION_DELAY {
+            db.save_selection(user.id, &unsaved)?;
+            app.saved_selection = unsaved;
+        }
+        else {
+            app.unsaved_selection = Some((unsaved, changed));
+        }
+    }
+    Ok(())
+}
 
-                    // ---- Workspaces -----
-                    Key::Char('1') => {
-                        // Go to workspace 1
-                        app.selected_tab = 0;
-                    }
-                    Key::Char('2') => {
-                        // Go to workspace 2
-                        app.selected_tab = 1;
-                    }
-                    Key::Char('3') => {
-                        // Go to workspace 2
-                        app.selected_tab = 2;
-                    }
-                    Key::Char('4') => {
-                        // Go to workspace 2
-                        app.selected_tab = 3;
+/// Handles a key press in the current input mode. Errors are shown in the
+/// status bar.
+///
+/// Returns whether to quit.
+fn handle_input(
+    input : Key,
+    app : &mut App,
+    keymap : &Keymap,
+    user : &User,
+    db : &dyn Storage) -> Result<bool, Box<dyn Error>> {
+
+    match app.input_mode {
+
+        InputMode::Normal => match keymap.action(Mode::Normal, input) {
+
+            // Open or close the selected collection or folder.
+            Some(Action::ToggleSelection) => {
+                let tree = &app.tree;
+                let selected = app.tree_state.selected().and_then(|i| tree.get(i));
+                if let Some(node) = selected.filter(|row| row.request.is_none()).map(|row| row.node) {
+                    if !app.tree_expanded.remove(&node) {
+                        app.tree_expanded.insert(node);
                     }
+                }
+            }
+            Some(Action::Command) => {
+                app.input_mode = InputMode::Command;
+            }
 
-                    Key::Right => {
-                        app.col_state.select(Some(0));
-                        if app.selected_tab < app.tab_len -1 {
-                            app.selected_tab += 1;
-                        }
-                        else {
-                            app.selected_tab = 0;
-                        }
-                    }
-                    Key::Left => {
-                        app.col_state.select(Some(0));
-                        if app.selected_tab == 0 {
-                            app.selected_tab = app.tab_len-1;
-                        }
-                        else {
-                            app.selected_tab -= 1;
+            // Fill the command line with the selected collection, folder or
+            // request, the new name or destination is left to type.
+            Some(action @ Action::Rename) | Some(action @ Action::Move) => {
+                if let Some(kind) = selected_kind(app) {
+                    let verb = if action == Action::Rename { "rename" } else { "mv" };
+                    app.input = format!("{} {} . ", verb, kind);
+                    app.input_mode = InputMode::Command;
+                }
+            }
+            Some(Action::Duplicate) => {
+                match selected_kind(app) {
+                    Some("folder") => app.status.warning("folders cannot be copied, copy their collection"),
+                    Some(kind) => execute_command(&format!("cp {} .", kind), app, user, db)?,
+                    None => {}
+                }
+            }
+
+            // ---- Selected request -----
+            Some(Action::Send) => {
+                if let Some(request) = &app.selected_request {
+                    // Pre-request scripts run on HTTP and GraphQL
+                    // requests, which are then sent with the
+                    // variables substituted.
+                    app.script_report.clear();
+                    app.response_scroll = 0;
+                    let scripts = db.get_scripts(app.selected_collection, request.id)?;
+                    let context = script::before(request, &scripts, app.variables.clone());
+                    if request.kind != RequestKind::Websocket {
+                        if context.error.is_some() {
+                            app.response = String::from("Not sent.");
+                            app.script_report = context.report();
+                            return Ok(false);
                         }
+                        app.variables = context.variables.clone();
                     }
-
-                    // ----- Collections & Requests ----
-                    Key::Char('k') => {
-                        match app.selection_mode {
-                            SelectionMode::Requests => {
-                                let i = app.req_state.selected().unwrap();
-                                if i == 0 {
-                                    app.req_state.select(Some(app.request_list.len() - 1));
-                                }
-                                else {
-                                    app.req_state.select(Some(i -1));
-                                }
+                    match request.kind {
+                        // GraphQL requests are sent as JSON POSTs, in the
+                        // background like HTTP ones.
+                        RequestKind::Http | RequestKind::Graphql => {
+                            let sent = match request.kind {
+                                RequestKind::Graphql => match graphql::http_request(&context.request) {
+                                    Ok(sent) => sent,
+                                    Err(e) => {
+                                        app.response = format!("invalid variables : {}", e);
+                                        return Ok(false);
+                                    }
+                                },
+                                _ => context.request.clone(),
+                            };
+                            let invalid = body::invalid_fields(&sent.body_mode, &sent.body);
+                            if !invalid.is_empty() && client::BodyUse::of(&sent.method) != client::BodyUse::Dropped {
+                                app.response = format!("Not sent, the body has lines that are not fields :\n{}", invalid.join("\n"));
+                                return Ok(false);
                             }
-                            SelectionMode::Collections => {
-                                let i = app.col_state.selected().unwrap();
-                                if i == 0 {
-                                    app.col_state.select(Some(app.collection_list.len() - 1));
-                                }
-                                else {
-                                    app.col_state.select(Some(i -1));
-                                }
+                            app.events = None;
+                            app.response = String::from("receiving... (0 bytes)");
+                            if let Some(warning) = client::body_warning(&sent) {
+                                app.status.warning(warning);
                             }
+                            // Kept as sent, for reconnecting to event streams.
+                            app.exchange = Some(Exchange::spawn(&sent));
+                            app.exchange_request = Some(sent);
+                            app.pending_scripts = Some((context, scripts));
                         }
-                    }
-                    Key::Char('j') => {
-                        match app.selection_mode {
-                            SelectionMode::Requests => {
-                                let i = app.req_state.selected().unwrap();
-                                if i >= app.request_list.len() - 1 {
-                                    app.req_state.select(Some(0));
-                                }
-                                else {
-                                    app.req_state.select(Some(i +1));
-                                }
-                            }
-                            SelectionMode::Collections => {
-                                let i = app.col_state.selected().unwrap();
-                                if i >= app.collection_list.len() - 1 {
-                                    app.col_state.select(Some(0));
+                        // Sends the message if connected, connects otherwise.
+                        RequestKind::Websocket => {
+                            match app.websocket.as_mut() {
+                                Some(session) if session.open && app.websocket_request == request.id => {
+                                    match websocket::frame(request) {
+                                        Ok(frame) => session.send(frame),
+                                        Err(e) => session.note(format!("invalid JSON : {}", e)),
+                                    }
                                 }
-                                else {
-                                    app.col_state.select(Some(i +1));
+                                _ => {
+                                    app.websocket = Some(Session::connect(request));
+                                    app.websocket_request = request.id;
                                 }
                             }
                         }
                     }
-                    // Quit the application
-                    Key::Char('q') => {
-                        break;
+                }
+            }
+            Some(Action::CycleBodyMode) => {
+                if let Some(request) = app.selected_request.clone() {
+                    db.update_request_body(request.id, &request.body_mode.next(), &request.body)?;
+                    record_request_edit(app, request, db)?;
+                }
+            }
+            Some(Action::EditBody) => edit(app, EditField::Body),
+            Some(Action::EditHeaders) => edit(app, EditField::Headers),
+            Some(Action::EditVariables) => edit(app, EditField::Variables),
+            Some(Action::Ping) => {
+                if let Some(session) = &app.websocket {
+                    session.ping();
+                }
+            }
+            Some(Action::Stop) => {
+                if let Some(session) = &app.websocket {
+                    session.close();
+                }
+                if let Some(exchange) = &app.exchange {
+                    exchange.stop();
+                }
+                if let Some((_, exchange)) = &app.introspection {
+                    exchange.stop();
+                }
+                // A running bench is stopped, a finished one dismissed.
+                match &app.bench {
+                    Some(bench) if !bench.done => bench.stop(),
+                    Some(_) => app.bench = None,
+                    None => {}
+                }
+                match &app.run {
+                    Some(run) if !run.done => run.stop(),
+                    Some(_) => app.run = None,
+                    None => {}
+                }
+            }
+            // Reconnect to the event stream, resuming after the last
+            // event received.
+            Some(Action::Reconnect) => {
+                if let (Some(events), Some(request)) = (app.events.as_mut(), &app.exchange_request) {
+                    let mut request = request.clone();
+                    if let Some(id) = &events.last_event_id {
+                        request.headers.push_str(&format!("\nLast-Event-ID: {}", id));
                     }
-                    _ => {}
-                },
-
-                // Command line to add/delete stuff in the database
-                InputMode::Command => match input {
-                    // Enter key press
-                    Key::Char('\n') => {
-                        let v : Vec<&str> = app.input.split_whitespace().collect();
-                        if let Some((&name, args)) = v.split_first() {
-                            match name {
-                                "add" => {
-                                    for (i, &arg) in args.iter().enumerate() {
-                                        match arg {
-                                            "workspace" => {
-
-                                                let name = args[i+1];
-                                                create_workspace(user, name, db)?;
-                                            }
-
-                                            "collection" => {
-
-                                                let name = args[i+1];
-                                                // let id = args[i+2].parse()?;
-                                                create_collection(name, app.selected_tab as i64 + 1, db)?;
-
-                                            }
-
-                                            "request" => {
-
-                                                let name = args[i+1];
-                                                let id = args[i+2].parse()?;
-                                                let method = args[i+3];
-                                                let url = args[i+4];
-                                                create_request(name, id, method, url,db)?;
-                                            }
-
-                                            &_ => ()
-                                        }
-                                    }
-
-                                },
-                                "rm" => {
-                                    for (i, &arg) in args.iter().enumerate() {
-                                        match arg {
-                                            "workspace" => {
-                                                let name = args[i+1];
-                                                delete_workspace(name, db)?;
-                                            }
+                    events.ended = None;
+                    app.exchange = Some(Exchange::spawn(&request));
+                }
+            }
+            // Capture the last response as an Example of its request.
+            Some(Action::CaptureExample) => {
+                if let Some((id_request, response)) = &app.last_response {
+                    let example = Example {
+                        id : 0,
+                        id_request : *id_request,
+                        name : format!("{} at {}", response.status, util::time::clock(util::time::now_millis())),
+                        status : response.status as i64,
+                        headers : body::without_framing(&response.headers.get(1..).unwrap_or(&[]).join("\n")).join("\n"),
+                        body : String::from_utf8_lossy(&response.body).into_owned(),
+                        latency : response.time.as_millis() as i64,
+                    };
+                    db.create_example(&example)?;
+                    app.status.info(format!("example \"{}\" saved", example.name));
+                }
+            }
+            Some(Action::OpenExamples) if app.selected_request.is_some() => {
+                app.examples_state.select(Some(0));
+                app.input_mode = InputMode::Examples;
+            }
+            Some(Action::OpenSchema) if app.schema.is_some() => {
+                app.input_mode = InputMode::Schema;
+            }
+            Some(Action::Undo) => undo(app, db)?,
+            Some(Action::Redo) => redo(app, db)?,
+            Some(Action::OpenTrash) => open_trash(app, user, db)?,
+            Some(Action::Find) => {
+                app.finder = Finder::load(user.id, db)?;
+                app.input_mode = InputMode::Finder;
+            }
 
-                                            "collection" => {
-                                                let name = args[i+1];
-                                                delete_collection(name, db)?;
 
-                                            }
+            // ---- Workspaces -----
+            Some(Action::Workspace(n)) if n >= 1 && n <= app.tab_len => {
+                // Go to workspace n
+                select_tab(app, n - 1);
+            }
 
-                                            "request" => {
-                                                let name = args[i+1];
-                                                delete_request(name, db)?;
+            Some(Action::NextWorkspace) if app.tab_len > 0 => {
+                select_tab(app, (app.selected_tab + 1) % app.tab_len);
+            }
+            Some(Action::PreviousWorkspace) if app.tab_len > 0 => {
+                select_tab(app, (app.selected_tab + app.tab_len - 1) % app.tab_len);
+            }
 
-                                            }
+            // ----- Collections & Requests ----
+            Some(Action::Up) => {
+                let i = app.tree_state.selected().unwrap_or(0);
+                select_row(app, if i == 0 { app.tree.len().saturating_sub(1) } else { i - 1 });
+            }
+            Some(Action::Down) => {
+                let i = app.tree_state.selected().unwrap_or(0);
+                select_row(app, if i + 1 >= app.tree.len() { 0 } else { i + 1 });
+            }
+            // Quit the application
+            Some(Action::Quit) => {
+                return Ok(true);
+            }
+            _ => {}
+        },
+
+        // Command line to add/delete stuff in the database
+        InputMode::Command => match input {
+            // Enter key press
+            Key::Char('\n') => {
+                let line = std::mem::take(&mut app.input);
+                app.input_mode = InputMode::Normal;
+                app.command_completions.clear();
+                app.history_index = None;
+                if line.trim().is_empty() {
+                    return Ok(false);
+                }
+                db.add_command(&line)?;
+                app.history = db.get_commands()?;
+                execute_command(&line, app, user, db)?;
+            }
+            // Complete the word being typed, or its common prefix
+            // when there are several candidates.
+            Key::Char('\t') => {
+                let scope = scope(app, user);
+                let paths = |kind, partial : &str| -> Vec<String> {
+                    let kind = match kind {
+                        command::Kind::Workspace => trash::Kind::Workspace,
+                        command::Kind::Collection => trash::Kind::Collection,
+                        command::Kind::Folder => trash::Kind::Folder,
+                        command::Kind::Request => trash::Kind::Request,
+                        _ => return vec![],
+                    };
+                    complete_paths(&scope, kind, partial, db).unwrap_or_default()
+                };
+                let completion = command::complete(&app.input, paths);
+                match completion.candidates.as_slice() {
+                    [] => {}
+                    [candidate] => {
+                        app.input.truncate(completion.start);
+                        app.input.push_str(&command::quote(candidate));
+                        if !candidate.ends_with('/') {
+                            app.input.push(' ');
+                        }
+                    }
+                    candidates => {
+                        let prefix = command::common_prefix(candidates);
+                        if !prefix.contains(|c : char| c.is_whitespace() || c == '"' || c == '\'' || c == '\\') {
+                            app.input.truncate(completion.start);
+                            app.input.push_str(&prefix);
+                        }
+                    }
+                }
+                app.command_completions = completion.candidates;
+            }
+            // Walk the history, keeping the line being typed to come
+            // back to it.
+            Key::Up => {
+                let index = match app.history_index {
+                    Some(index) => index.saturating_sub(1),
+                    None => {
+                        app.draft = app.input.clone();
+                        app.history.len().saturating_sub(1)
+                    }
+                };
+                if let Some(line) = app.history.get(index) {
+                    app.input = line.clone();
+                    app.history_index = Some(index);
+                }
+            }
+            Key::Down => {
+                match app.history_index {
+                    Some(index) if index + 1 < app.history.len() => {
+                        app.input = app.history[index + 1].clone();
+                        app.history_index = Some(index + 1);
+                    }
+                    Some(_) => {
+                        app.input = std::mem::take(&mut app.draft);
+                        app.history_index = None;
+                    }
+                    None => {}
+                }
+            }
+            Key::Char(c) => {
 
-                                            &_ => ()
-                                        }
-                                    }
-                                },
-                                &_ => println!("command {} not found", name)
+                app.input.push(c);
+                app.command_completions.clear();
+            }
+            Key::Backspace => {
+                app.input.pop();
+                app.command_completions.clear();
+            }
+            Key::Esc => {
 
+                app.input.clear();
+                app.command_completions.clear();
+                app.history_index = None;
+                app.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        },
+        // Multi-line editor for the selected request, saved on Esc.
+        InputMode::Editing => {
+            match input {
+                // Complete the GraphQL field being typed with the
+                // first suggestion.
+                Key::Char('\t') => {
+                    if let Some(completion) = app.completions.first() {
+                        let typed = app.input.chars().rev().take_while(|c| c.is_alphanumeric() || *c == '_').count();
+                        let completion : String = completion.chars().skip(typed).collect();
+                        app.input.push_str(&completion);
+                    }
+                }
+                Key::Char(c) => {
+                    app.input.push(c);
+                }
+                Key::Backspace => {
+                    app.input.pop();
+                }
+                Key::Esc => {
+                    let example = app.examples_state.selected().and_then(|i| app.examples.get(i)).cloned();
+                    app.input_mode = InputMode::Normal;
+                    if let EditField::Script(owner, stage) = app.edit_field {
+                        let id_owner = match owner {
+                            Owner::Request => app.selected_request.as_ref().map(|request| request.id).unwrap_or_default(),
+                            Owner::Collection => app.selected_collection,
+                        };
+                        db.save_script(owner, id_owner, stage, &app.input)?;
+                    }
+                    match (app.selected_request.clone(), example) {
+                        (Some(request), _) if app.edit_field == EditField::Body => {
+                            db.update_request_body(request.id, &request.body_mode, &app.input)?;
+                            record_request_edit(app, request, db)?;
+                        }
+                        (Some(request), _) if app.edit_field == EditField::Headers => {
+                            db.update_request_headers(request.id, &app.input)?;
+                            record_request_edit(app, request, db)?;
+                        }
+                        (Some(request), _) if app.edit_field == EditField::Variables => {
+                            db.update_request_variables(request.id, &app.input)?;
+                            record_request_edit(app, request, db)?;
+                        }
+                        // Back to the examples after editing one.
+                        (_, Some(mut example)) if app.edit_field == EditField::ExampleBody || app.edit_field == EditField::ExampleHeaders => {
+                            if app.edit_field == EditField::ExampleBody {
+                                example.body = app.input.clone();
+                            }
+                            else {
+                                example.headers = app.input.clone();
                             }
+                            db.update_example(&example)?;
+                            app.input_mode = InputMode::Examples;
                         }
-                        app.input.drain(..);
-                        app.input_mode = InputMode::Normal;
+                        _ => {}
                     }
-                    Key::Char(c) => {
+                    app.input.drain(..);
+                    app.edit_field = EditField::Body;
+                }
+                _ => {}
+            }
 
-                        app.input.push(c);
+            app.completions = match (&app.schema, &app.selected_request) {
+                (Some(schema), Some(request)) if request.kind == RequestKind::Graphql
+                    && app.input_mode == InputMode::Editing
+                    && app.edit_field == EditField::Body => schema.completions(&app.input),
+                _ => vec![],
+            };
+        },
+
+        // Browse, edit and delete the examples of the selected request.
+        InputMode::Examples => {
+            let i = app.examples_state.selected().unwrap_or(0);
+            let example = app.examples.get(i).cloned();
+            match keymap.action(Mode::Examples, input) {
+                Some(Action::Down) => {
+                    app.examples_state.select(Some(if i + 1 >= app.examples.len() { 0 } else { i + 1 }));
+                }
+                Some(Action::Up) => {
+                    app.examples_state.select(Some(if i == 0 { app.examples.len().saturating_sub(1) } else { i - 1 }));
+                }
+                Some(Action::EditBody) => {
+                    if let Some(example) = example {
+                        app.input = example.body;
+                        app.edit_field = EditField::ExampleBody;
+                        app.input_mode = InputMode::Editing;
+                    }
+                }
+                Some(Action::EditHeaders) => {
+                    if let Some(example) = example {
+                        app.input = example.headers;
+                        app.edit_field = EditField::ExampleHeaders;
+                        app.input_mode = InputMode::Editing;
+                    }
+                }
+                Some(Action::Delete) => {
+                    if let Some(example) = example {
+                        db.delete_example(example.id)?;
+                        app.examples_state.select(Some(i.saturating_sub(1)));
+                    }
+                }
+                Some(Action::Close) => {
+                    app.input_mode = InputMode::Normal;
+                }
+                _ => {}
+            }
+        },
+
+        // Narrow down the requests of every workspace and jump to one.
+        InputMode::Finder => match input {
+            Key::Down | Key::Ctrl('n') => app.finder.next(),
+            Key::Up | Key::Ctrl('p') => app.finder.previous(),
+            Key::Char('\n') => {
+                app.input_mode = InputMode::Normal;
+                if let Some(entry) = app.finder.selected() {
+                    app.selected_workspace = entry.id_workspace;
+                    app.tree_expanded.insert(Node::Collection(entry.id_collection));
+                    app.tree_expanded.extend(entry.folders.iter().map(|id| Node::Folder(*id)));
+                    app.selected_node = Some(Node::Request(entry.id_request));
+                }
+            }
+            Key::Char(c) => {
+                app.finder.query.push(c);
+                app.finder.update();
+            }
+            Key::Backspace => {
+                app.finder.query.pop();
+                app.finder.update();
+            }
+            Key::Esc => {
+                app.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        },
+
+        // Restore or purge the items in the trash.
+        InputMode::Trash => {
+            let i = app.trash_state.selected().unwrap_or(0);
+            let item = app.trash.get(i).cloned();
+            match keymap.action(Mode::Trash, input) {
+                Some(Action::Down) => {
+                    app.trash_state.select(Some(if i + 1 >= app.trash.len() { 0 } else { i + 1 }));
+                }
+                Some(Action::Up) => {
+                    app.trash_state.select(Some(if i == 0 { app.trash.len().saturating_sub(1) } else { i - 1 }));
+                }
+                Some(Action::Restore) => {
+                    if let Some(item) = item {
+                        db.restore(item.kind, item.id)?;
+                        app.status.info(format!("{} {} restored", item.kind, item.name));
+                        open_trash(app, user, db)?;
                     }
-                    Key::Backspace => {
-                        app.input.pop();
+                }
+                Some(Action::Purge) => {
+                    if let Some(item) = item {
+                        db.purge(item.kind, item.id)?;
+                        app.status.info(format!("{} {} deleted for good", item.kind, item.name));
+                        open_trash(app, user, db)?;
                     }
-                    Key::Esc => {
+                }
+                Some(Action::Close) => {
+                    app.input_mode = InputMode::Normal;
+                }
+                _ => {}
+            }
+        },
 
-                        app.input_mode = InputMode::Normal;
+        // Browse the schema tree of the selected GraphQL endpoint.
+        InputMode::Schema => {
+            let rows = match &app.schema {
+                Some(schema) => schema.tree(&app.schema_expanded),
+                None => vec![],
+            };
+            let i = app.schema_state.selected().unwrap_or(0);
+            match keymap.action(Mode::Schema, input) {
+                Some(Action::Down) => {
+                    app.schema_state.select(Some(if i + 1 >= rows.len() { 0 } else { i + 1 }));
+                }
+                Some(Action::Up) => {
+                    app.schema_state.select(Some(if i == 0 { rows.len().saturating_sub(1) } else { i - 1 }));
+                }
+                Some(Action::ToggleSelection) => {
+                    if let Some(name) = rows.get(i).and_then(|row| row.type_name.clone()) {
+                        if !app.schema_expanded.remove(&name) {
+                            app.schema_expanded.insert(name);
+                        }
                     }
-                    _ => {}
-                },
-                InputMode::Editing => {},
+                }
+                Some(Action::Close) => {
+                    app.input_mode = InputMode::Normal;
+                }
+                _ => {}
+            }
+        },
 
+    }
+    Ok(false)
+}
+
+/// Where the command line looks names up : the selection.
+fn scope(
+    app : &App,
+    user : &User) -> Scope {
+
+    Scope {
+        id_user : user.id,
+        id_workspace : app.selected_workspace,
+        id_collection : Some(app.selected_collection).filter(|id| *id != 0),
+        id_folder : app.selected_folder,
+        id_request : app.selected_request.as_ref().map(|request| request.id),
+    }
+}
+
+/// Runs a line of the command line, see `ui::command::COMMANDS`. Mistakes in
+/// the command are reported in the status bar.
+fn execute_command(
+    line : &str,
+    app : &mut App,
+    user : &User,
+    db : &dyn Storage) -> Result<(), Box<dyn Error>> {
+
+    let command = match command::parse(line) {
+        Ok(command) => command,
+        Err(e) => {
+            app.status.error(e);
+            return Ok(());
+        }
+    };
+    let scope = scope(app, user);
+    // Collection named by `--collection`, the selected one
+    // otherwise.
+    let id_collection = match command.flag("collection") {
+        Some(path) => find_one("collection", path, find_collections(&scope, path, db)?)?.id,
+        None => app.selected_collection,
+    };
+    // New requests and folders go in the selected folder, at the root of
+    // the collection named by `--collection`.
+    let id_folder = if command.flag("collection").is_some() { None } else { app.selected_folder };
+    // Workspace named by `--workspace`, the selected one otherwise.
+    let id_workspace = match command.flag("workspace") {
+        Some(path) => find_one("workspace", path, find_workspaces(&scope, path, db)?)?.id,
+        None => app.selected_workspace,
+    };
+    // Commands working on the selected request.
+    let needs_request = ["schema", "example", "bench", "replay", "history"].contains(&command.name())
+        || (command.name() == "script" && command.opt(1).is_none());
+    if needs_request && app.selected_request.is_none() {
+        app.status.warning(format!("{} needs a selected request", command.name()));
+        return Ok(());
+    }
+    let name = command.arg(0);
+    match command.name() {
+        "add workspace" => {
+            let id = db.create_workspace(user, name)?;
+            app.undo.record(Edit::Create(trash::Kind::Workspace, id, name.to_owned()));
+            app.status.info(format!("workspace {} added", name));
+        }
+        "add collection" => {
+            let id = db.create_collection(name, id_workspace)?;
+            app.undo.record(Edit::Create(trash::Kind::Collection, id, name.to_owned()));
+            app.status.info(format!("collection {} added", name));
+        }
+        "add request" | "add graphql" | "add websocket" => {
+            let id = match command.name() {
+                "add request" => db.create_request(name, id_collection, id_folder, command.arg(1), command.arg(2))?,
+                "add graphql" => db.create_graphql_request(name, id_collection, id_folder, command.arg(1))?,
+                _ => db.create_websocket_request(name, id_collection, id_folder, command.arg(1))?,
+            };
+            app.undo.record(Edit::Create(trash::Kind::Request, id, name.to_owned()));
+            app.status.info(format!("request {} added", name));
+        }
+        "add folder" => {
+            let id = db.create_folder(name, id_collection, id_folder)?;
+            app.undo.record(Edit::Create(trash::Kind::Folder, id, name.to_owned()));
+            app.status.info(format!("folder {} added", name));
+        }
+        // Deleted items go to the trash with everything they hold.
+        "rm workspace" | "rm collection" | "rm folder" | "rm request" => {
+            let kind = command.name().trim_start_matches("rm ");
+            let (id, name) = match kind {
+                "workspace" => find_one(kind, name, find_workspaces(&scope, name, db)?).map(|workspace| (workspace.id, workspace.name))?,
+                "collection" => find_one(kind, name, find_collections(&scope, name, db)?).map(|collection| (collection.id, collection.name))?,
+                "folder" => find_one(kind, name, find_folders(&scope, name, db)?).map(|folder| (folder.id, folder.name))?,
+                _ => find_one(kind, name, find_requests(&scope, name, db)?).map(|request| (request.id, request.name))?,
+            };
+            let kind : trash::Kind = kind.parse().unwrap_or(trash::Kind::Request);
+            db.trash(kind, id)?;
+            app.status.info(format!("{} {} moved to the trash", kind, name));
+            app.undo.record(Edit::Delete(kind, id, name));
+        }
+        "rename workspace" | "rename collection" | "rename folder" | "rename request" => {
+            let kind = command.name().trim_start_matches("rename ");
+            let new_name = command.arg(1);
+            let (id, old_name) = match kind {
+                "workspace" => {
+                    let workspace = find_one(kind, name, find_workspaces(&scope, name, db)?)?;
+                    db.rename_workspace(workspace.id, new_name)?;
+                    (workspace.id, workspace.name)
+                }
+                "collection" => {
+                    let collection = find_one(kind, name, find_collections(&scope, name, db)?)?;
+                    db.rename_collection(collection.id, new_name)?;
+                    (collection.id, collection.name)
+                }
+                "folder" => {
+                    let folder = find_one(kind, name, find_folders(&scope, name, db)?)?;
+                    db.rename_folder(folder.id, new_name)?;
+                    (folder.id, folder.name)
+                }
+                _ => {
+                    let request = find_one(kind, name, find_requests(&scope, name, db)?)?;
+                    db.rename_request(request.id, new_name)?;
+                    (request.id, request.name)
+                }
+            };
+            app.status.info(format!("{} {} renamed to {}", kind, old_name, new_name));
+            let kind = kind.parse().unwrap_or(trash::Kind::Request);
+            app.undo.record(Edit::Rename(kind, id, old_name, new_name.to_owned()));
+        }
+        // Copies go to the selected workspace or collection unless a flag
+        // names another one.
+        "cp collection" => {
+            let collection = find_one("collection", name, find_collections(&scope, name, db)?)?;
+            let copy = command.opt(1).map(|copy| copy.to_owned()).unwrap_or_else(|| format!("{} copy", collection.name));
+            let id = db.duplicate_collection(collection.id, &copy, id_workspace)?;
+            app.status.info(format!("collection {} copied to {}", collection.name, copy));
+            app.undo.record(Edit::Create(trash::Kind::Collection, id, copy));
+        }
+        "cp request" => {
+            let request = find_one("request", name, find_requests(&scope, name, db)?)?;
+            let copy = command.opt(1).map(|copy| copy.to_owned()).unwrap_or_else(|| format!("{} copy", request.name));
+            // The copy stays next to the request unless it goes to another
+            // collection.
+            let (id_collection, id_folder) = match command.flag("collection") {
+                Some(_) => (id_collection, None),
+                None => (request.id_collection, request.id_folder),
+            };
+            let id = db.duplicate_request(request.id, &copy, id_collection, id_folder)?;
+            app.status.info(format!("request {} copied to {}", request.name, copy));
+            app.undo.record(Edit::Create(trash::Kind::Request, id, copy));
+        }
+        "mv collection" => {
+            let collection = find_one("collection", name, find_collections(&scope, name, db)?)?;
+            let path = command.arg(1);
+            let workspace = find_one("workspace", path, find_workspaces(&scope, path, db)?)?;
+            let id_from = db.get_collection_workspace(collection.id)?.unwrap_or_default();
+            db.move_collection(collection.id, workspace.id)?;
+            app.status.info(format!("collection {} moved to {}", collection.name, workspace.name));
+            app.undo.record(Edit::MoveCollection(collection.id, collection.name, id_from, workspace.id));
+        }
+        "mv request" => {
+            let request = find_one("request", name, find_requests(&scope, name, db)?)?;
+            let path = command.arg(1);
+            let collection = find_one("collection", path, find_collections(&scope, path, db)?)?;
+            // Folders named by `--folder` are looked up in the target
+            // collection.
+            let folder = match command.flag("folder") {
+                Some(path) => {
+                    let scope = Scope { id_collection : Some(collection.id), ..scope };
+                    let folder = find_one("folder", path, find_folders(&scope, path, db)?)?;
+                    if db.get_all_folders(collection.id)?.iter().all(|other| other.id != folder.id) {
+                        return Err(format!("folder {} is not in {}", folder.name, collection.name).into());
+                    }
+                    Some(folder)
+                }
+                None => None,
+            };
+            let to = (collection.id, folder.as_ref().map(|folder| folder.id));
+            db.move_request(request.id, to.0, to.1)?;
+            match folder {
+                Some(folder) => app.status.info(format!("request {} moved to {}/{}", request.name, collection.name, folder.name)),
+                None => app.status.info(format!("request {} moved to {}", request.name, collection.name)),
+            }
+            app.undo.record(Edit::MoveRequest(request.id, request.name, (request.id_collection, request.id_folder), to));
+        }
+        // Fetch and cache the schema of the selected
+        // GraphQL request's endpoint.
+        "schema" => {
+            if let Some(request) = &app.selected_request {
+                let exchange = Exchange::spawn(&graphql::introspection(request));
+                app.introspection = Some((request.url.clone(), exchange));
+                app.status.info(format!("fetching the schema of {}...", request.url));
+            }
+        }
+        // Write an example by hand.
+        "example" => {
+            if let Some(request) = &app.selected_request {
+                let example = Example {
+                    id : 0,
+                    id_request : request.id,
+                    name : name.to_owned(),
+                    status : command.arg(1).parse()?,
+                    headers : String::new(),
+                    body : String::new(),
+                    latency : command.opt(2).map(|latency| latency.parse()).transpose()?.unwrap_or(0),
+                };
+                db.create_example(&example)?;
+                app.examples_state.select(Some(app.examples.len()));
+                app.input_mode = InputMode::Examples;
+            }
+        }
+        // Load test the selected request, see
+        // `bench::USAGE` for the flags.
+        "bench" => {
+            if let Some(request) = &app.selected_request {
+                let args : Vec<&str> = command.rest.iter().map(|arg| arg.as_str()).collect();
+                match bench::Config::from_args(&args) {
+                    Ok(config) => app.bench = Some(Bench::spawn(request, config)),
+                    Err(e) => app.status.error(e),
+                }
             }
         }
+        // Edit a script of the selected request, or of
+        // its collection.
+        "script" => {
+            let stage = if name == "pre" { Stage::PreRequest } else { Stage::PostResponse };
+            let owner = if command.opt(1).is_some() { Owner::Collection } else { Owner::Request };
+            let id_owner = match owner {
+                Owner::Request => app.selected_request.as_ref().map(|request| request.id),
+                Owner::Collection => Some(app.selected_collection),
+            };
+            if let Some(id_owner) = id_owner {
+                app.input = db.get_script(owner, id_owner, stage)?;
+                app.edit_field = EditField::Script(owner, stage);
+                app.input_mode = InputMode::Editing;
+            }
+        }
+        // Run the selected collection, once per row
+        // of the data file if one is given.
+        "run" => {
+            let data = match command.opt(0) {
+                Some(path) => runner::load_data(path).map_err(|e| format!("{} : {}", path, e)),
+                None => Ok(vec![]),
+            };
+            match data {
+                Ok(data) => {
+                    let requests = db.get_requests_with_scripts(app.selected_collection)?;
+                    app.run = Some(CollectionRun::spawn(requests, data));
+                }
+                Err(e) => app.status.error(e),
+            }
+        }
+        // Save the current WebSocket session with its
+        // request.
+        "save" => {
+            match &app.websocket {
+                Some(session) => {
+                    db.save_messages(app.websocket_request, &session.log)?;
+                    app.status.info(format!("{} messages saved", session.log.len()));
+                }
+                None => app.status.warning("no WebSocket session to save"),
+            }
+        }
+        // Past sends of the selected request.
+        "history" => {
+            if let Some(request) = &app.selected_request {
+                let sent = db.get_sent(request.id)?;
+                app.response = if sent.is_empty() {
+                    format!("{} was never sent", request.name)
+                }
+                else {
+                    sent.iter()
+                        .map(|sent| match sent.status {
+                            0 => format!("{}  {} {}  failed", util::time::clock(sent.time), sent.method, sent.url),
+                            status => format!("{}  {} {}  {} in {} ms", util::time::clock(sent.time), sent.method, sent.url, status, sent.latency),
+                        })
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                };
+                app.script_report.clear();
+                app.response_scroll = 0;
+            }
+        }
+        // Load the variables of an environment of the workspace, or list
+        // them.
+        "env" => {
+            let environments = db.get_environments(app.selected_workspace)?;
+            match command.opt(0) {
+                Some(name) => {
+                    let environment = find_one("environment", name, environments.into_iter().filter(|environment| environment.name == name).collect())?;
+                    app.variables = environment.variables;
+                    app.status.info(format!("environment {} loaded, {} variables", name, app.variables.len()));
+                }
+                None if environments.is_empty() => app.status.info("no environment, env save <name> saves the variables as one"),
+                None => app.status.info(environments.iter().map(|environment| environment.name.as_str()).collect::<Vec<&str>>().join("  ")),
+            }
+        }
+        // Save the current variables as an environment of the workspace.
+        "env save" => {
+            let name = command.arg(0);
+            match db.get_environments(app.selected_workspace)?.into_iter().find(|environment| environment.name == name) {
+                Some(environment) => db.update_environment(environment.id, &app.variables)?,
+                None => {
+                    db.create_environment(app.selected_workspace, name, &app.variables)?;
+                }
+            }
+            app.status.info(format!("{} variables saved to environment {}", app.variables.len(), name));
+        }
+        "env rm" => {
+            let name = command.arg(0);
+            let environments = db.get_environments(app.selected_workspace)?;
+            let environment = find_one("environment", name, environments.into_iter().filter(|environment| environment.name == name).collect())?;
+            db.delete_environment(environment.id)?;
+            app.status.info(format!("environment {} deleted", name));
+        }
+        "trash" => {
+            match command.opt(0) {
+                Some(_) => {
+                    let count = db.empty_trash(user.id)?;
+                    app.status.info(format!("{} items deleted for good", count));
+                }
+                None => open_trash(app, user, db)?,
+            }
+        }
+        "undo" => undo(app, db)?,
+        "redo" => redo(app, db)?,
+        // Connect again and resend the messages of the
+        // saved session.
+        "replay" => {
+            match app.selected_request.as_ref().filter(|r| r.kind == RequestKind::Websocket) {
+                Some(request) => {
+                    let saved = db.get_messages(request.id)?;
+                    app.websocket = Some(Session::replay(request, &saved));
+                    app.websocket_request = request.id;
+                }
+                None => app.status.warning("replay needs a WebSocket request"),
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Takes back the last edit of the session.
+fn undo(
+    app : &mut App,
+    db : &dyn Storage) -> Result<(), Box<dyn Error>> {
+
+    match app.undo.undo(db)? {
+        Some(edit) => app.status.info(format!("undone : {}", edit)),
+        None => app.status.warning("nothing to undo"),
+    }
+    Ok(())
+}
+
+/// Makes the last undone edit again.
+fn redo(
+    app : &mut App,
+    db : &dyn Storage) -> Result<(), Box<dyn Error>> {
+
+    match app.undo.redo(db)? {
+        Some(edit) => app.status.info(format!("redone : {}", edit)),
+        None => app.status.warning("nothing to redo"),
+    }
+    Ok(())
+}
+
+/// Records the change of a request's fields for undo, once it is saved.
+///
+/// * `before` - the request as it was before the change.
+fn record_request_edit(
+    app : &mut App,
+    before : Request,
+    db : &dyn Storage) -> Result<(), Box<dyn Error>> {
+
+    let after = db.get_request(before.id)?;
+    let changed = after.body != before.body
+        || after.body_mode != before.body_mode
+        || after.headers != before.headers
+        || after.variables != before.variables;
+    if changed {
+        app.undo.record(Edit::Request(Box::new(before), Box::new(after)));
     }
     Ok(())
 }
+
+/// Shows the trash in place of the tree, reloaded after each change.
+fn open_trash(
+    app : &mut App,
+    user : &User,
+    db : &dyn Storage) -> Result<(), Box<dyn Error>> {
+
+    app.trash = db.get_trash(user.id)?;
+    let selected = app.trash_state.selected().unwrap_or(0).min(app.trash.len().saturating_sub(1));
+    app.trash_state.select(Some(selected));
+    app.input_mode = InputMode::Trash;
+    Ok(())
+}
+
+/// The only item a path resolved to, an error naming the path when there are
+/// none or several.
+///
+/// * `kind` - what the path names, for the error.
+/// * `path` - the path as typed.
+/// * `found` - the items it resolved to.
+fn find_one<T>(
+    kind : &str,
+    path : &str,
+    found : Vec<T>) -> Result<T, String> {
+
+    let count = found.len();
+    match (count, found.into_iter().next()) {
+        (1, Some(item)) => Ok(item),
+        (0, _) if path == "." => Err(format!("no {} selected", kind)),
+        (0, _) => Err(format!("no {} {}", kind, path)),
+        _ => Err(format!("{} matches {} {}s, use its path", path, count, kind)),
+    }
+}
+
+/// Kind of the collection, folder or request selected in the tree.
+fn selected_kind(app : &App) -> Option<&'static str> {
+    app.tree_state.selected().and_then(|i| app.tree.get(i)).map(|row| match row.node {
+        Node::Collection(_) => "collection",
+        Node::Folder(_) => "folder",
+        Node::Request(_) => "request",
+    })
+}
@@ -1 +1,8 @@
+pub mod keymap;
+pub mod theme;
 pub mod view;
+pub mod command;
+pub mod status;
+pub mod tree;
+pub mod finder;
+pub mod mouse;
@@ -0,0 +1,588 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::database::container::{Methods, METHODS};
+
+/// What an argument holds, for validation and completion.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Kind {
+    Text,
+    Number,
+    Method,
+    Workspace,
+    Collection,
+    Folder,
+    Request,
+    File,
+    /// One of the given words.
+    Keyword(&'static [&'static str]),
+}
+
+/// A positional argument or a named flag taking a value.
+pub struct Arg {
+    pub name : &'static str,
+    pub kind : Kind,
+    pub optional : bool,
+}
+
+const fn required(name : &'static str, kind : Kind) -> Arg {
+    Arg { name, kind, optional : false }
+}
+
+const fn optional(name : &'static str, kind : Kind) -> Arg {
+    Arg { name, kind, optional : true }
+}
+
+/// A command of the `:` command line.
+pub struct Spec {
+    /// One or two words, e.g. `add request`.
+    pub name : &'static str,
+    pub args : &'static [Arg],
+    /// Flags are always optional, written `--name <value>`.
+    pub flags : &'static [Arg],
+    /// Extra arguments handed over as they are, see `Command::rest`.
+    pub rest : Option<&'static str>,
+}
+
+impl Spec {
+
+    pub fn usage(&self) -> String {
+        let mut usage = String::from(self.name);
+        for arg in self.args.iter() {
+            match arg.kind {
+                Kind::Keyword(words) if !arg.optional => usage.push_str(&format!(" {}", words.join("|"))),
+                Kind::Keyword(words) => usage.push_str(&format!(" [{}]", words.join("|"))),
+                _ if arg.optional => usage.push_str(&format!(" [<{}>]", arg.name)),
+                _ => usage.push_str(&format!(" <{}>", arg.name)),
+            }
+        }
+        for flag in self.flags.iter() {
+            usage.push_str(&format!(" [--{} <{}>]", flag.name, flag.name));
+        }
+        if let Some(rest) = self.rest {
+            usage.push(' ');
+            usage.push_str(rest);
+        }
+        usage
+    }
+}
+
+pub const COMMANDS : &[Spec] = &[
+    Spec {
+        name : "add workspace",
+        args : &[required("name", Kind::Text)],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "add collection",
+        args : &[required("name", Kind::Text)],
+        flags : &[optional("workspace", Kind::Workspace)],
+        rest : None,
+    },
+    Spec {
+        name : "add request",
+        args : &[required("name", Kind::Text), required("method", Kind::Method), required("url", Kind::Text)],
+        flags : &[optional("collection", Kind::Collection)],
+        rest : None,
+    },
+    Spec {
+        name : "add graphql",
+        args : &[required("name", Kind::Text), required("url", Kind::Text)],
+        flags : &[optional("collection", Kind::Collection)],
+        rest : None,
+    },
+    Spec {
+        name : "add websocket",
+        args : &[required("name", Kind::Text), required("url", Kind::Text)],
+        flags : &[optional("collection", Kind::Collection)],
+        rest : None,
+    },
+    Spec {
+        name : "add folder",
+        args : &[required("name", Kind::Text)],
+        flags : &[optional("collection", Kind::Collection)],
+        rest : None,
+    },
+    Spec {
+        name : "rm workspace",
+        args : &[required("workspace", Kind::Workspace)],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "rm collection",
+        args : &[required("collection", Kind::Collection)],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "rm folder",
+        args : &[required("folder", Kind::Folder)],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "rm request",
+        args : &[required("request", Kind::Request)],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "rename workspace",
+        args : &[required("workspace", Kind::Workspace), required("name", Kind::Text)],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "rename collection",
+        args : &[required("collection", Kind::Collection), required("name", Kind::Text)],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "rename folder",
+        args : &[required("folder", Kind::Folder), required("name", Kind::Text)],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "rename request",
+        args : &[required("request", Kind::Request), required("name", Kind::Text)],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "cp collection",
+        args : &[required("collection", Kind::Collection), optional("name", Kind::Text)],
+        flags : &[optional("workspace", Kind::Workspace)],
+        rest : None,
+    },
+    Spec {
+        name : "cp request",
+        args : &[required("request", Kind::Request), optional("name", Kind::Text)],
+        flags : &[optional("collection", Kind::Collection)],
+        rest : None,
+    },
+    Spec {
+        name : "mv collection",
+        args : &[required("collection", Kind::Collection), required("workspace", Kind::Workspace)],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "mv request",
+        args : &[required("request", Kind::Request), required("collection", Kind::Collection)],
+        flags : &[optional("folder", Kind::Folder)],
+        rest : None,
+    },
+    Spec {
+        name : "schema",
+        args : &[],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "example",
+        args : &[required("name", Kind::Text), required("status", Kind::Number), optional("latency", Kind::Number)],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "bench",
+        args : &[],
+        flags : &[],
+        rest : Some(crate::http::bench::USAGE),
+    },
+    Spec {
+        name : "run",
+        args : &[optional("data", Kind::File)],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "script",
+        args : &[required("stage", Kind::Keyword(&["pre", "post"])), optional("owner", Kind::Keyword(&["collection"]))],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "save",
+        args : &[],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "replay",
+        args : &[],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "history",
+        args : &[],
+        flags : &[],
+        rest : None,
+    },
+    // Before `env`, which would take `save` and `rm` for a name.
+    Spec {
+        name : "env save",
+        args : &[required("name", Kind::Text)],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "env rm",
+        args : &[required("name", Kind::Text)],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "env",
+        args : &[optional("name", Kind::Text)],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "trash",
+        args : &[optional("action", Kind::Keyword(&["empty"]))],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "undo",
+        args : &[],
+        flags : &[],
+        rest : None,
+    },
+    Spec {
+        name : "redo",
+        args : &[],
+        flags : &[],
+        rest : None,
+    },
+];
+
+/// A word of the command line and the byte offset it starts at.
+struct Token {
+    text : String,
+    start : usize,
+}
+
+/// Splits a command line into words. Single and double quotes group words
+/// with spaces, a backslash escapes the next character outside single quotes.
+///
+/// Returns the words and whether the last one is still being typed. With
+/// `lenient` an unclosed quote ends the last word instead of being an error.
+fn lex(
+    input : &str,
+    lenient : bool) -> Result<(Vec<Token>, bool), String> {
+
+    let mut tokens = vec![];
+    let mut current : Option<Token> = None;
+    let mut quote : Option<char> = None;
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                let token = current.get_or_insert(Token { text : String::new(), start : i });
+                if let Some((_, escaped)) = chars.next() {
+                    token.text.push(escaped);
+                }
+            }
+            (Some(_), c) => current.get_or_insert(Token { text : String::new(), start : i }).text.push(c),
+            (None, '"') | (None, '\'') => {
+                current.get_or_insert(Token { text : String::new(), start : i });
+                quote = Some(c);
+            }
+            (None, c) if c.is_whitespace() => tokens.extend(current.take()),
+            (None, c) => current.get_or_insert(Token { text : String::new(), start : i }).text.push(c),
+        }
+    }
+    if quote.is_some() && !lenient {
+        return Err(String::from("unclosed quote"));
+    }
+    let open = current.is_some();
+    tokens.extend(current);
+    Ok((tokens, open))
+}
+
+/// Quotes a word if it would otherwise be split or unescaped.
+pub fn quote(word : &str) -> String {
+    if !word.is_empty() && !word.chars().any(|c| c.is_whitespace() || c == '"' || c == '\'' || c == '\\') {
+        return word.to_owned();
+    }
+    format!("\"{}\"", word.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A parsed and validated command.
+pub struct Command {
+    pub spec : &'static Spec,
+    /// Positional arguments, optional ones may be missing.
+    pub args : Vec<String>,
+    pub flags : Vec<(&'static str, String)>,
+    pub rest : Vec<String>,
+}
+
+impl Command {
+
+    pub fn name(&self) -> &'static str {
+        self.spec.name
+    }
+
+    /// A required argument, or an empty string past the given ones.
+    pub fn arg(&self, i : usize) -> &str {
+        self.args.get(i).map(|arg| arg.as_str()).unwrap_or_default()
+    }
+
+    pub fn opt(&self, i : usize) -> Option<&str> {
+        self.args.get(i).map(|arg| arg.as_str())
+    }
+
+    pub fn flag(&self, name : &str) -> Option<&str> {
+        self.flags.iter().find(|(flag, _)| *flag == name).map(|(_, value)| value.as_str())
+    }
+}
+
+/// The specs whose name starts with the given words, with how many words
+/// their name has.
+fn find_spec(words : &[String]) -> Option<(&'static Spec, usize)> {
+    COMMANDS.iter().find_map(|spec| {
+        let name : Vec<&str> = spec.name.split(' ').collect();
+        let matches = name.len() <= words.len() && name.iter().zip(words.iter()).all(|(a, b)| a == b);
+        if matches { Some((spec, name.len())) } else { None }
+    })
+}
+
+fn validate(
+    arg : &Arg,
+    value : &str) -> Result<String, String> {
+
+    match arg.kind {
+        Kind::Number if value.parse::<i64>().is_err() => Err(format!("<{}> must be a number, not {}", arg.name, value)),
+        Kind::Method => Methods::from_str(&value.to_uppercase())
+            .map(|method| method.to_string())
+            .map_err(|_| format!("unknown method {}", value)),
+        Kind::Keyword(words) if !words.contains(&value) => Err(format!("<{}> must be one of {}", arg.name, words.join(", "))),
+        _ => Ok(value.to_owned()),
+    }
+}
+
+/// Parses a command line, checking the arguments against the command's spec.
+/// Errors end with the usage of the command.
+pub fn parse(input : &str) -> Result<Command, String> {
+    let (tokens, _) = lex(input, false)?;
+    let words : Vec<String> = tokens.into_iter().map(|token| token.text).collect();
+    let first = words.first().ok_or("no command")?;
+    let (spec, skip) = match find_spec(&words) {
+        Some(found) => found,
+        None if COMMANDS.iter().any(|spec| spec.name.split(' ').next() == Some(first)) => {
+            let subcommands : Vec<&str> = COMMANDS.iter()
+                .filter(|spec| spec.name.split(' ').next() == Some(first))
+                .filter_map(|spec| spec.name.split(' ').nth(1))
+                .collect();
+            return Err(format!("usage : {} {}", first, subcommands.join("|")));
+        }
+        None => return Err(format!("unknown command {}", first)),
+    };
+    let usage = |e : String| format!("{}, usage : {}", e, spec.usage());
+
+    let mut command = Command { spec, args : vec![], flags : vec![], rest : vec![] };
+    let mut words = words.into_iter().skip(skip);
+    while let Some(word) = words.next() {
+        let flag = word.strip_prefix("--").and_then(|name| spec.flags.iter().find(|flag| flag.name == name));
+        if let Some(flag) = flag {
+            let value = words.next().ok_or_else(|| usage(format!("--{} needs a value", flag.name)))?;
+            command.flags.push((flag.name, validate(flag, &value).map_err(usage)?));
+        }
+        else if let Some(arg) = spec.args.get(command.args.len()) {
+            command.args.push(validate(arg, &word).map_err(usage)?);
+        }
+        else if spec.rest.is_some() {
+            command.rest.push(word);
+        }
+        else {
+            return Err(usage(format!("unexpected {}", word)));
+        }
+    }
+    if let Some(missing) = spec.args.iter().skip(command.args.len()).find(|arg| !arg.optional) {
+        return Err(usage(format!("missing <{}>", missing.name)));
+    }
+    Ok(command)
+}
+
+/// Files and directories completing a partial path.
+fn complete_path(partial : &str) -> Vec<String> {
+    let (dir, prefix) = match partial.rfind('/') {
+        Some(i) => (&partial[..=i], &partial[i + 1..]),
+        None => ("", partial),
+    };
+    let entries = match fs::read_dir(if dir.is_empty() { Path::new(".") } else { Path::new(dir) }) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    let mut paths : Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let slash = if entry.path().is_dir() { "/" } else { "" };
+            if name.starts_with(prefix) { Some(format!("{}{}{}", dir, name, slash)) } else { None }
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Completions of the word being typed at the end of a command line.
+pub struct Completion {
+    /// Byte offset of the word being completed.
+    pub start : usize,
+    pub candidates : Vec<String>,
+}
+
+/// Completes the command name, subcommand, flag name or argument at the end
+/// of a command line.
+///
+/// * `input` - the command line typed so far.
+/// * `paths` - the paths of the workspaces, collections, folders or requests
+///   for a Kind completing the word typed, used for arguments of those kinds.
+pub fn complete<F>(
+    input : &str,
+    paths : F) -> Completion
+where
+F : Fn(Kind, &str) -> Vec<String>,
+{
+    let (tokens, open) = lex(input, true).unwrap_or_default();
+    let (done, partial, start) = match tokens.split_last() {
+        Some((last, done)) if open => (done, last.text.clone(), last.start),
+        _ => (&tokens[..], String::new(), input.len()),
+    };
+    let words : Vec<String> = done.iter().map(|token| token.text.clone()).collect();
+
+    let mut candidates : Vec<String> = match (find_spec(&words), words.len()) {
+        (None, 0) => COMMANDS.iter().filter_map(|spec| spec.name.split(' ').next()).map(|word| word.to_owned()).collect(),
+        (None, 1) => COMMANDS.iter()
+            .filter(|spec| spec.name.split(' ').next() == Some(words[0].as_str()))
+            .filter_map(|spec| spec.name.split(' ').nth(1))
+            .map(|word| word.to_owned())
+            .collect(),
+        (None, _) => vec![],
+        (Some((spec, skip)), _) => {
+            // Walk the typed arguments to find what the next word is.
+            let mut position = 0;
+            let mut expecting : Option<&Arg> = None;
+            for word in words.iter().skip(skip) {
+                match expecting.take() {
+                    Some(_) => {}
+                    None => match word.strip_prefix("--").and_then(|name| spec.flags.iter().find(|flag| flag.name == name)) {
+                        Some(flag) => expecting = Some(flag),
+                        None => position += 1,
+                    },
+                }
+            }
+            let flag_names = || spec.flags.iter().map(|flag| format!("--{}", flag.name)).collect::<Vec<String>>();
+            let arg = expecting.or_else(|| if partial.starts_with('-') { None } else { spec.args.get(position) });
+            match arg.map(|arg| arg.kind) {
+                Some(Kind::Text) | Some(Kind::Number) => vec![],
+                Some(Kind::Method) => METHODS.iter().map(|m| m.to_string()).collect(),
+                Some(Kind::Keyword(words)) => words.iter().map(|word| word.to_string()).collect(),
+                Some(Kind::File) => complete_path(&partial),
+                Some(kind) => paths(kind, &partial),
+                None => flag_names(),
+            }
+        }
+    };
+    let lower = partial.to_lowercase();
+    candidates.retain(|candidate| candidate.to_lowercase().starts_with(&lower));
+    candidates.dedup();
+    Completion { start, candidates }
+}
+
+/// The longest prefix shared by all candidates.
+pub fn common_prefix(candidates : &[String]) -> String {
+    let first = match candidates.first() {
+        Some(first) => first,
+        None => return String::new(),
+    };
+    let mut prefix : String = first.clone();
+    for candidate in candidates.iter().skip(1) {
+        let len = prefix.chars().zip(candidate.chars()).take_while(|(a, b)| a == b).count();
+        prefix = prefix.chars().take(len).collect();
+    }
+    prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(input : &str) -> (Vec<String>, bool) {
+        let (tokens, open) = lex(input, true).unwrap();
+        (tokens.into_iter().map(|token| token.text).collect(), open)
+    }
+
+    #[test]
+    fn lexes_quotes_and_escapes() {
+        assert_eq!(words("rm  request get"), (vec![String::from("rm"), String::from("request"), String::from("get")], true));
+        assert_eq!(words("add workspace \"my ws\" "), (vec![String::from("add"), String::from("workspace"), String::from("my ws")], false));
+        assert_eq!(words(r#"a 'b \ "c"' d\ e "f \"g\"""#).0, vec!["a", "b \\ \"c\"", "d e", "f \"g\""]);
+        assert_eq!(words("a \"unclosed wor").0, vec!["a", "unclosed wor"]);
+        assert!(lex("a \"unclosed", false).is_err());
+        let (tokens, _) = lex("rm request  \"x y", true).unwrap();
+        assert_eq!(tokens.iter().map(|token| token.start).collect::<Vec<_>>(), vec![0, 3, 12]);
+    }
+
+    fn error(input : &str) -> String {
+        parse(input).err().unwrap()
+    }
+
+    #[test]
+    fn parses_arguments_and_flags() {
+        let command = parse("add request \"list users\" get http://localhost --collection ws/api").ok().unwrap();
+        assert_eq!(command.name(), "add request");
+        assert_eq!(command.args, vec!["list users", "GET", "http://localhost"]);
+        assert_eq!(command.flag("collection"), Some("ws/api"));
+        assert_eq!(command.opt(3), None);
+
+        assert!(error("add request x get").starts_with("missing <url>"));
+        assert_eq!(parse("add request x propfind url").ok().unwrap().arg(1), "PROPFIND");
+        assert_eq!(error("rm request a b"), "unexpected b, usage : rm request <request>");
+        assert!(error("add folder x --collection").starts_with("--collection needs a value"));
+        assert_eq!(error("rm"), "usage : rm workspace|collection|folder|request");
+        assert_eq!(error("frobnicate"), "unknown command frobnicate");
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn common_prefix_of_candidates() {
+        let candidates = |words : &[&str]| words.iter().map(|word| word.to_string()).collect::<Vec<String>>();
+        assert_eq!(common_prefix(&candidates(&["ws/api", "ws/app", "ws/a"])), "ws/a");
+        assert_eq!(common_prefix(&candidates(&["éte", "étè"])), "ét");
+        assert_eq!(common_prefix(&candidates(&["one"])), "one");
+        assert_eq!(common_prefix(&candidates(&["a", "b"])), "");
+        assert_eq!(common_prefix(&[]), "");
+    }
+
+    #[test]
+    fn completes_commands_flags_and_paths() {
+        let paths = |kind, partial : &str| -> Vec<String> {
+            match (kind, partial.contains('/')) {
+                (Kind::Request, false) => vec![String::from("list"), String::from("ws/")],
+                (Kind::Request, true) => vec![String::from("ws/api/"), String::from("ws/apps/")],
+                (Kind::Collection, _) => vec![String::from("api"), String::from("ws/")],
+                _ => vec![],
+            }
+        };
+        let complete = |input : &str| complete(input, paths);
+        assert_eq!(complete("re").candidates, vec!["rename", "replay", "redo"]);
+        assert_eq!(complete("rm r").candidates, vec!["request"]);
+        assert_eq!(complete("rm request w").candidates, vec!["ws/"]);
+        let completion = complete("rm request ws/a");
+        assert_eq!((completion.start, completion.candidates), (11, vec![String::from("ws/api/"), String::from("ws/apps/")]));
+        assert_eq!(complete("add folder x --").candidates, vec!["--collection"]);
+        assert_eq!(complete("add folder x --collection ").candidates, vec!["api", "ws/"]);
+        assert!(complete("add request x ").candidates.contains(&String::from("GET")));
+    }
+}
@@ -0,0 +1,146 @@
+use tui::widgets::ListState;
+
+use crate::database::container::*;
+use crate::database::storage::Storage;
+
+/// Number of matches listed, the best ones.
+const MAX_MATCHES : usize = 50;
+
+/// A request the finder can jump to.
+pub struct Entry {
+    pub id_workspace : i64,
+    pub id_collection : i64,
+    /// The folders holding the request, outermost first.
+    pub folders : Vec<i64>,
+    pub id_request : i64,
+    pub method : Methods,
+    /// `workspace/collection/folder/.../request`
+    pub path : String,
+    pub url : String,
+    /// What the query is matched against, lowercased.
+    text : String,
+}
+
+/// Scores how well `query` matches `text`, None when its characters do not
+/// all appear in order. Consecutive characters and characters starting a
+/// word score higher. Both are expected lowercased.
+///
+/// * `query` - what was typed.
+/// * `text` - the text to look in.
+pub fn score(
+    query : &str,
+    text : &str) -> Option<i64> {
+
+    let mut score = 0;
+    let mut chars = text.chars().enumerate();
+    let mut previous : Option<char> = None;
+    let mut last_match : Option<usize> = None;
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        loop {
+            let (i, c) = chars.next()?;
+            let word_start = previous.map(|c| !c.is_alphanumeric()).unwrap_or(true);
+            previous = Some(c);
+            if c != q {
+                continue;
+            }
+            score += 1;
+            if last_match.map(|last| last + 1 == i).unwrap_or(false) {
+                score += 5;
+            }
+            if word_start {
+                score += 3;
+            }
+            last_match = Some(i);
+            break;
+        }
+    }
+    // Shorter texts are closer matches.
+    Some(score * 100 - text.len() as i64)
+}
+
+/// The requests of every workspace of a user, with the query narrowing them
+/// down.
+#[derive(Default)]
+pub struct Finder {
+    pub query : String,
+    entries : Vec<Entry>,
+    /// Indexes of the entries matching the query, best first.
+    matches : Vec<usize>,
+    pub state : ListState,
+}
+
+impl Finder {
+
+    /// Lists the requests a user can reach, out of the trash.
+    ///
+    /// * `id_user` - the user whose workspaces are searched.
+    /// * `db` - Database to work on.
+    pub fn load(
+        id_user : i64,
+        db : &dyn Storage) -> crate::error::Result<Finder> {
+
+        let mut entries = vec![];
+        for workspace in db.get_all_workspaces(id_user)? {
+            for collection in db.get_all_collections(workspace.id)? {
+                let folders = db.get_all_folders(collection.id)?;
+                for request in db.get_all_requests(collection.id)? {
+                    // Walk up to the root of the collection.
+                    let mut chain = vec![];
+                    let mut id_folder = request.id_folder;
+                    while let Some(folder) = id_folder.and_then(|id| folders.iter().find(|folder| folder.id == id)) {
+                        chain.insert(0, folder);
+                        id_folder = folder.id_parent;
+                    }
+                    let mut path = vec![workspace.name.as_str(), collection.name.as_str()];
+                    path.extend(chain.iter().map(|folder| folder.name.as_str()));
+                    path.push(&request.name);
+                    let path = path.join("/");
+                    entries.push(Entry {
+                        id_workspace : workspace.id,
+                        id_collection : collection.id,
+                        folders : chain.iter().map(|folder| folder.id).collect(),
+                        id_request : request.id,
+                        text : format!("{} {} {}", request.method, path, request.url).to_lowercase(),
+                        method : request.method.clone(),
+                        path,
+                        url : request.url.clone(),
+                    });
+                }
+            }
+        }
+        let mut finder = Finder { entries, ..Finder::default() };
+        finder.update();
+        Ok(finder)
+    }
+
+    /// Matches the entries against the query again, selecting the best one.
+    pub fn update(&mut self) {
+        let query = self.query.to_lowercase();
+        let mut scored : Vec<(i64, usize)> = self.entries.iter()
+            .enumerate()
+            .filter_map(|(i, entry)| score(&query, &entry.text).map(|score| (score, i)))
+            .collect();
+        scored.sort_by_key(|(score, i)| (std::cmp::Reverse(*score), *i));
+        self.matches = scored.into_iter().take(MAX_MATCHES).map(|(_, i)| i).collect();
+        self.state.select(if self.matches.is_empty() { None } else { Some(0) });
+    }
+
+    /// The entries matching the query, best first.
+    pub fn matches(&self) -> Vec<&Entry> {
+        self.matches.iter().map(|i| &self.entries[*i]).collect()
+    }
+
+    pub fn selected(&self) -> Option<&Entry> {
+        self.state.selected().and_then(|i| self.matches.get(i)).map(|i| &self.entries[*i])
+    }
+
+    pub fn next(&mut self) {
+        let i = self.state.selected().unwrap_or(0);
+        self.state.select(Some(if i + 1 >= self.matches.len() { 0 } else { i + 1 }));
+    }
+
+    pub fn previous(&mut self) {
+        let i = self.state.selected().unwrap_or(0);
+        self.state.select(Some(if i == 0 { self.matches.len().saturating_sub(1) } else { i - 1 }));
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+
+use termion::event::Key;
+
+use crate::util::config::config_dir;
+
+/// What a key does, in the modes where it is bound.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Action {
+    Quit,
+    /// Opens the command line.
+    Command,
+    /// Opens or closes the selected collection or folder.
+    ToggleSelection,
+    Up,
+    Down,
+    NextWorkspace,
+    PreviousWorkspace,
+    /// Goes to the workspace tab at this position, from 1.
+    Workspace(usize),
+    Send,
+    CycleBodyMode,
+    EditBody,
+    EditHeaders,
+    EditVariables,
+    Ping,
+    Stop,
+    Reconnect,
+    CaptureExample,
+    OpenExamples,
+    OpenSchema,
+    /// Fill the command line to rename or move the selected collection,
+    /// folder or request.
+    Rename,
+    Move,
+    /// Copies the selected collection, folder or request next to it.
+    Duplicate,
+    /// Takes back or makes again the last edit of the session.
+    Undo,
+    Redo,
+    OpenTrash,
+    /// Opens the finder over the requests of every workspace.
+    Find,
+    /// Deletes the selected example.
+    Delete,
+    /// Takes the selected item out of the trash, or deletes it for good.
+    Restore,
+    Purge,
+    /// Goes back to the normal mode.
+    Close,
+}
+
+const ACTIONS : &[(&str, Action)] = &[
+    ("quit", Action::Quit),
+    ("command", Action::Command),
+    ("toggle_selection", Action::ToggleSelection),
+    ("up", Action::Up),
+    ("down", Action::Down),
+    ("next_workspace", Action::NextWorkspace),
+    ("previous_workspace", Action::PreviousWorkspace),
+    ("send", Action::Send),
+    ("cycle_body_mode", Action::CycleBodyMode),
+    ("edit_body", Action::EditBody),
+    ("edit_headers", Action::EditHeaders),
+    ("edit_variables", Action::EditVariables),
+    ("ping", Action::Ping),
+    ("stop", Action::Stop),
+    ("reconnect", Action::Reconnect),
+    ("capture_example", Action::CaptureExample),
+    ("open_examples", Action::OpenExamples),
+    ("open_schema", Action::OpenSchema),
+    ("rename", Action::Rename),
+    ("move", Action::Move),
+    ("duplicate", Action::Duplicate),
+    ("undo", Action::Undo),
+    ("redo", Action::Redo),
+    ("open_trash", Action::OpenTrash),
+    ("find", Action::Find),
+    ("delete", Action::Delete),
+    ("restore", Action::Restore),
+    ("purge", Action::Purge),
+    ("close", Action::Close),
+];
+
+impl FromStr for Action {
+    type Err = ();
+    fn from_str(input : &str) -> Result<Action, Self::Err> {
+        if let Some(n) = input.strip_prefix("workspace_") {
+            return match n.parse() {
+                Ok(n) if n > 0 => Ok(Action::Workspace(n)),
+                _ => Err(()),
+            };
+        }
+        ACTIONS.iter()
+            .find(|(name, _)| *name == input)
+            .map(|(_, action)| *action)
+            .ok_or(())
+    }
+}
+
+impl fmt::Display for Action {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::Workspace(n) => write!(f, "workspace_{}", n),
+            action => f.write_str(ACTIONS.iter().find(|(_, a)| a == action).map(|(name, _)| *name).unwrap_or_default()),
+        }
+    }
+}
+
+/// The modes with keys of their own, each a table of the keymap file.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Mode {
+    Normal,
+    Examples,
+    Trash,
+    Schema,
+}
+
+const MODES : &[(&str, Mode)] = &[
+    ("normal", Mode::Normal),
+    ("examples", Mode::Examples),
+    ("trash", Mode::Trash),
+    ("schema", Mode::Schema),
+];
+
+impl Mode {
+
+    /// Whether an action means something in this mode.
+    pub fn allows(self, action : Action) -> bool {
+        use Action::*;
+        match self {
+            Mode::Normal => !matches!(action, Delete | Restore | Purge | Close),
+            Mode::Examples => matches!(action, Up | Down | EditBody | EditHeaders | Delete | Close),
+            Mode::Trash => matches!(action, Up | Down | Restore | Purge | Close),
+            Mode::Schema => matches!(action, Up | Down | ToggleSelection | Close),
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = ();
+    fn from_str(input : &str) -> Result<Mode, Self::Err> {
+        MODES.iter()
+            .find(|(name, _)| *name == input)
+            .map(|(_, mode)| *mode)
+            .ok_or(())
+    }
+}
+
+impl fmt::Display for Mode {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(MODES.iter().find(|(_, mode)| mode == self).map(|(name, _)| *name).unwrap_or_default())
+    }
+}
+
+/// Parses a key written as in the keymap file : a character, `ctrl-x`,
+/// `alt-x`, `f1` to `f12`, or one of `space`, `enter`, `tab`, `esc`,
+/// `backspace`, `delete`, `insert`, `home`, `end`, `pageup`, `pagedown`,
+/// `up`, `down`, `left` and `right`.
+pub fn parse_key(input : &str) -> Option<Key> {
+    let single = |s : &str| {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    if let Some(c) = single(input) {
+        return Some(Key::Char(c));
+    }
+    let lower = input.to_ascii_lowercase();
+    if let Some(c) = lower.strip_prefix("ctrl-").and_then(single) {
+        return Some(Key::Ctrl(c));
+    }
+    if let Some(c) = input.get(4..).filter(|_| lower.starts_with("alt-")).and_then(single) {
+        return Some(Key::Alt(c));
+    }
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse().ok()) {
+        return if (1..=12).contains(&n) { Some(Key::F(n)) } else { None };
+    }
+    match lower.as_str() {
+        "space" => Some(Key::Char(' ')),
+        "enter" => Some(Key::Char('\n')),
+        "tab" => Some(Key::Char('\t')),
+        "esc" => Some(Key::Esc),
+        "backspace" => Some(Key::Backspace),
+        "delete" => Some(Key::Delete),
+        "insert" => Some(Key::Insert),
+        "home" => Some(Key::Home),
+        "end" => Some(Key::End),
+        "pageup" => Some(Key::PageUp),
+        "pagedown" => Some(Key::PageDown),
+        "up" => Some(Key::Up),
+        "down" => Some(Key::Down),
+        "left" => Some(Key::Left),
+        "right" => Some(Key::Right),
+        _ => None,
+    }
+}
+
+/// Keys bound to actions, per mode.
+pub struct Keymap {
+    bindings : HashMap<(Mode, Key), Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        let defaults = [
+            (Key::Char('q'), Action::Quit),
+            (Key::Char(':'), Action::Command),
+            (Key::Char(' '), Action::ToggleSelection),
+            (Key::Char('k'), Action::Up),
+            (Key::Char('j'), Action::Down),
+            (Key::Right, Action::NextWorkspace),
+            (Key::Left, Action::PreviousWorkspace),
+            (Key::Char('1'), Action::Workspace(1)),
+            (Key::Char('2'), Action::Workspace(2)),
+            (Key::Char('3'), Action::Workspace(3)),
+            (Key::Char('4'), Action::Workspace(4)),
+            (Key::Char('\n'), Action::Send),
+            (Key::Char('b'), Action::CycleBodyMode),
+            (Key::Char('e'), Action::EditBody),
+            (Key::Char('h'), Action::EditHeaders),
+            (Key::Char('v'), Action::EditVariables),
+            (Key::Char('p'), Action::Ping),
+            (Key::Char('x'), Action::Stop),
+            (Key::Char('c'), Action::Reconnect),
+            (Key::Char('y'), Action::CaptureExample),
+            (Key::Char('o'), Action::OpenExamples),
+            (Key::Char('s'), Action::OpenSchema),
+            (Key::Char('r'), Action::Rename),
+            (Key::Char('m'), Action::Move),
+            (Key::Char('d'), Action::Duplicate),
+            (Key::Char('u'), Action::Undo),
+            (Key::Ctrl('r'), Action::Redo),
+            (Key::Char('t'), Action::OpenTrash),
+            (Key::Ctrl('p'), Action::Find),
+        ];
+        let examples = [
+            (Key::Char('j'), Action::Down),
+            (Key::Char('k'), Action::Up),
+            (Key::Char('e'), Action::EditBody),
+            (Key::Char('h'), Action::EditHeaders),
+            (Key::Char('d'), Action::Delete),
+            (Key::Char('q'), Action::Close),
+            (Key::Esc, Action::Close),
+        ];
+        let trash = [
+            (Key::Char('j'), Action::Down),
+            (Key::Char('k'), Action::Up),
+            (Key::Char('\n'), Action::Restore),
+            (Key::Char('r'), Action::Restore),
+            (Key::Char('x'), Action::Purge),
+            (Key::Delete, Action::Purge),
+            (Key::Char('q'), Action::Close),
+            (Key::Esc, Action::Close),
+        ];
+        let schema = [
+            (Key::Char('j'), Action::Down),
+            (Key::Char('k'), Action::Up),
+            (Key::Char(' '), Action::ToggleSelection),
+            (Key::Char('\n'), Action::ToggleSelection),
+            (Key::Char('q'), Action::Close),
+            (Key::Esc, Action::Close),
+        ];
+        let bindings = defaults.iter().map(|(key, action)| ((Mode::Normal, *key), *action))
+            .chain(examples.iter().map(|(key, action)| ((Mode::Examples, *key), *action)))
+            .chain(trash.iter().map(|(key, action)| ((Mode::Trash, *key), *action)))
+            .chain(schema.iter().map(|(key, action)| ((Mode::Schema, *key), *action)))
+            .collect();
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+
+    /// The action bound to a key in a mode, if any.
+    pub fn action(&self, mode : Mode, key : Key) -> Option<Action> {
+        self.bindings.get(&(mode, key)).copied()
+    }
+
+    /// Overrides the defaults with the tables of a keymap file, one per mode,
+    /// where each action is given a key or a list of keys :
+    ///
+    /// ```toml
+    /// [normal]
+    /// down = ["down", "ctrl-n"]
+    /// up = ["up", "ctrl-p"]
+    /// quit = "ctrl-q"
+    ///
+    /// [trash]
+    /// purge = "D"
+    /// ```
+    ///
+    /// An action listed in a table loses its default keys in that mode, `[]`
+    /// unbinds it.
+    pub fn from_toml(text : &str) -> Result<Keymap, String> {
+        let table : toml::Table = text.parse().map_err(|e : toml::de::Error| e.to_string())?;
+        let mut keymap = Keymap::default();
+
+        for (mode_name, actions) in table.iter() {
+            let mode = Mode::from_str(mode_name).map_err(|_| format!("unknown mode [{}]", mode_name))?;
+            let actions = match actions {
+                toml::Value::Table(actions) => actions,
+                _ => return Err(format!("[{}] must be a table", mode)),
+            };
+            for (name, keys) in actions.iter() {
+                let action = Action::from_str(name).map_err(|_| format!("unknown action {}", name))?;
+                if !mode.allows(action) {
+                    return Err(format!("{} is not an action of [{}]", name, mode));
+                }
+                let keys = match keys {
+                    toml::Value::String(key) => vec![key.as_str()],
+                    toml::Value::Array(keys) => keys.iter()
+                        .map(|key| key.as_str().ok_or(format!("keys of {} must be strings", name)))
+                        .collect::<Result<Vec<&str>, String>>()?,
+                    _ => return Err(format!("{} must be a key or a list of keys", name)),
+                };
+                keymap.bindings.retain(|(bound_mode, _), bound| *bound_mode != mode || *bound != action);
+                for key in keys {
+                    let key = parse_key(key).ok_or(format!("unknown key {} for {}", key, name))?;
+                    keymap.bindings.insert((mode, key), action);
+                }
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// Loads `keymap.toml` from `$XDG_CONFIG_HOME/turl/`, or `~/.config/turl/`,
+    /// keeping the defaults when there is no such file.
+    pub fn load() -> Result<Keymap, String> {
+        let path = match config_dir() {
+            Some(dir) => dir.join("keymap.toml"),
+            None => return Ok(Keymap::default()),
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => Keymap::from_toml(&text).map_err(|e| format!("{} : {}", path.display(), e)),
+            Err(_) => Ok(Keymap::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_have_their_own_keys() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.action(Mode::Normal, Key::Char('d')), Some(Action::Duplicate));
+        assert_eq!(keymap.action(Mode::Examples, Key::Char('d')), Some(Action::Delete));
+        assert_eq!(keymap.action(Mode::Trash, Key::Char('d')), None);
+        assert_eq!(keymap.action(Mode::Schema, Key::Esc), Some(Action::Close));
+    }
+
+    #[test]
+    fn tables_override_their_mode_only() {
+        let keymap = Keymap::from_toml("[trash]\nup = \"ctrl-p\"\npurge = [\"D\"]\n").unwrap();
+        assert_eq!(keymap.action(Mode::Trash, Key::Ctrl('p')), Some(Action::Up));
+        assert_eq!(keymap.action(Mode::Trash, Key::Char('k')), None);
+        assert_eq!(keymap.action(Mode::Trash, Key::Char('x')), None);
+        assert_eq!(keymap.action(Mode::Trash, Key::Char('D')), Some(Action::Purge));
+        assert_eq!(keymap.action(Mode::Normal, Key::Char('k')), Some(Action::Up));
+        assert_eq!(keymap.action(Mode::Examples, Key::Char('k')), Some(Action::Up));
+    }
+
+    #[test]
+    fn rejects_what_a_mode_cannot_do() {
+        assert!(Keymap::from_toml("[schema]\nsend = \"s\"\n").is_err());
+        assert!(Keymap::from_toml("[normal]\nclose = \"s\"\n").is_err());
+        assert!(Keymap::from_toml("[editing]\nup = \"k\"\n").is_err());
+        assert!(Keymap::from_toml("[normal]\nup = \"nokey\"\n").is_err());
+    }
+}
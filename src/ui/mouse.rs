@@ -0,0 +1,111 @@
+use tui::layout::Rect;
+use unicode_width::UnicodeWidthStr;
+
+/// A pane border that can be dragged to resize the panes on both sides.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Border {
+    /// Between the tree and the request.
+    Tree,
+    /// Between the editors of the request and its response.
+    Response,
+}
+
+/// Whether a cell, in 0-based terminal coordinates, is inside an area.
+pub fn contains(
+    area : Rect,
+    column : u16,
+    row : u16) -> bool {
+
+    column >= area.left() && column < area.right() && row >= area.top() && row < area.bottom()
+}
+
+/// The tab under a column of the line of titles, laid out the way
+/// `tui::widgets::Tabs` draws them : a space, the title, a space and a one
+/// cell divider.
+///
+/// * `titles` - the titles of the tabs.
+/// * `area` - where the tabs were drawn, borders included.
+/// * `column` - the 0-based column clicked.
+pub fn tab_at(
+    titles : &[&str],
+    area : Rect,
+    column : u16) -> Option<usize> {
+
+    let mut x = area.left() + 1;
+    for (i, title) in titles.iter().enumerate() {
+        let start = x + 1;
+        let end = start + title.width() as u16;
+        if column >= start && column < end {
+            return Some(i);
+        }
+        x = end + 2;
+    }
+    None
+}
+
+/// The first row shown by a `tui::widgets::List` of single line items, which
+/// keeps its own offset private. Follows how the list scrolls, just enough to
+/// keep the selected row in view.
+///
+/// * `offset` - the first row shown in the previous frame.
+/// * `selected` - the selected row.
+/// * `len` - the number of rows.
+/// * `height` - the number of rows that fit, borders excluded.
+pub fn list_offset(
+    offset : usize,
+    selected : Option<usize>,
+    len : usize,
+    height : usize) -> usize {
+
+    if len == 0 || height == 0 {
+        return offset;
+    }
+    let selected = selected.unwrap_or(0).min(len - 1);
+    if selected >= offset + height {
+        selected + 1 - height
+    }
+    else if selected < offset {
+        selected
+    }
+    else {
+        offset
+    }
+}
+
+/// The row of a bordered list under a cell, None on its borders or past its
+/// last row.
+///
+/// * `area` - where the list was drawn, borders included.
+/// * `offset` - the first row shown, see `list_offset`.
+/// * `len` - the number of rows.
+pub fn row_at(
+    area : Rect,
+    offset : usize,
+    len : usize,
+    column : u16,
+    row : u16) -> Option<usize> {
+
+    let inner = Rect::new(area.x + 1, area.y + 1, area.width.saturating_sub(2), area.height.saturating_sub(2));
+    if !contains(inner, column, row) {
+        return None;
+    }
+    Some(offset + (row - inner.y) as usize).filter(|&i| i < len)
+}
+
+/// The share of a length taken up to a position, in percent, kept between 10
+/// and 90 so neither side of a border disappears.
+///
+/// * `start` - where the length starts.
+/// * `length` - the length shared by both sides.
+/// * `position` - where the border was dragged to.
+pub fn percent(
+    start : u16,
+    length : u16,
+    position : u16) -> u16 {
+
+    if length == 0 {
+        return 50;
+    }
+    let share = position.saturating_sub(start) as u32 * 100 / length as u32;
+    share.clamp(10, 90) as u16
+}
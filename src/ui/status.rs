@@ -0,0 +1,79 @@
+use std::time::{Duration, Instant};
+
+use tui::style::Style;
+
+use super::theme::Theme;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+
+    /// How long a message stays in the status bar, errors longer so they
+    /// can be read.
+    pub fn lifetime(&self) -> Duration {
+        match self {
+            Severity::Info => Duration::from_secs(3),
+            Severity::Warning => Duration::from_secs(6),
+            Severity::Error => Duration::from_secs(10),
+        }
+    }
+
+    pub fn style(&self, theme : &Theme) -> Style {
+        match self {
+            Severity::Info => theme.messages.info,
+            Severity::Warning => theme.messages.warning,
+            Severity::Error => theme.messages.error,
+        }
+    }
+}
+
+/// A message of the status bar.
+#[derive(Clone)]
+pub struct Message {
+    pub severity : Severity,
+    pub text : String,
+    pub expires : Instant,
+}
+
+/// The message shown in the status bar, replaced by the next one or cleared
+/// once it expires.
+#[derive(Default)]
+pub struct Status {
+    message : Option<Message>,
+}
+
+impl Status {
+
+    pub fn push(
+        &mut self,
+        severity : Severity,
+        text : impl Into<String>) {
+
+        let text : String = text.into();
+        // The bar is a single line.
+        let text = text.lines().collect::<Vec<&str>>().join(" ");
+        self.message = Some(Message { severity, text, expires : Instant::now() + severity.lifetime() });
+    }
+
+    pub fn info(&mut self, text : impl Into<String>) {
+        self.push(Severity::Info, text);
+    }
+
+    pub fn warning(&mut self, text : impl Into<String>) {
+        self.push(Severity::Warning, text);
+    }
+
+    pub fn error(&mut self, text : impl Into<String>) {
+        self.push(Severity::Error, text);
+    }
+
+    /// The message to show, if it has not expired.
+    pub fn current(&self) -> Option<&Message> {
+        self.message.as_ref().filter(|message| message.expires > Instant::now())
+    }
+}
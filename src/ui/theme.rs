@@ -0,0 +1,413 @@
+use tui::{
+    style::{Color, Modifier, Style},
+    widgets::Block,
+};
+
+use crate::database::container::Methods;
+
+/// Styles of the request methods.
+#[derive(Clone)]
+pub struct MethodStyles {
+    pub get : Style,
+    pub post : Style,
+    pub put : Style,
+    pub patch : Style,
+    pub delete : Style,
+    pub other : Style,
+}
+
+/// Styles of the response status classes.
+#[derive(Clone)]
+pub struct StatusStyles {
+    pub informational : Style,
+    pub success : Style,
+    pub redirection : Style,
+    pub client_error : Style,
+    pub server_error : Style,
+}
+
+/// Styles of JSON tokens in response bodies.
+#[derive(Clone)]
+pub struct JsonStyles {
+    pub key : Style,
+    pub string : Style,
+    pub number : Style,
+    pub boolean : Style,
+    pub null : Style,
+    pub punctuation : Style,
+}
+
+/// Styles of the status bar messages.
+#[derive(Clone)]
+pub struct MessageStyles {
+    pub info : Style,
+    pub warning : Style,
+    pub error : Style,
+}
+
+/// Every style used to draw the app.
+#[derive(Clone)]
+pub struct Theme {
+    pub border : Style,
+    /// The selected workspace tab.
+    pub tab_highlight : Style,
+    /// The selected item of a list.
+    pub list_highlight : Style,
+    /// A field or the command line being edited.
+    pub editing : Style,
+    /// Secondary text such as timestamps.
+    pub muted : Style,
+    /// Charts and sent messages.
+    pub accent : Style,
+    pub methods : MethodStyles,
+    pub status : StatusStyles,
+    pub json : JsonStyles,
+    pub messages : MessageStyles,
+}
+
+pub const BUILT_IN : &[&str] = &["dark", "light", "high-contrast"];
+
+fn fg(color : Color) -> Style {
+    Style::default().fg(color)
+}
+
+fn bold(color : Color) -> Style {
+    Style::default().fg(color).add_modifier(Modifier::BOLD)
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::dark()
+    }
+}
+
+impl Theme {
+
+    pub fn dark() -> Theme {
+        Theme {
+            border : Style::default(),
+            tab_highlight : Style::default().fg(Color::Black).bg(Color::White),
+            list_highlight : Style::default().add_modifier(Modifier::ITALIC),
+            editing : fg(Color::Yellow),
+            muted : fg(Color::DarkGray),
+            accent : fg(Color::Cyan),
+            methods : MethodStyles {
+                get : fg(Color::Green),
+                post : fg(Color::Yellow),
+                put : fg(Color::Blue),
+                patch : fg(Color::Magenta),
+                delete : fg(Color::Red),
+                other : fg(Color::Cyan),
+            },
+            status : StatusStyles {
+                informational : fg(Color::Cyan),
+                success : fg(Color::Green),
+                redirection : fg(Color::Yellow),
+                client_error : fg(Color::Red),
+                server_error : bold(Color::Red),
+            },
+            json : JsonStyles {
+                key : fg(Color::Cyan),
+                string : fg(Color::Green),
+                number : fg(Color::Yellow),
+                boolean : fg(Color::Magenta),
+                null : fg(Color::DarkGray),
+                punctuation : Style::default(),
+            },
+            messages : MessageStyles {
+                info : fg(Color::Green),
+                warning : fg(Color::Yellow),
+                error : bold(Color::Red),
+            },
+        }
+    }
+
+    /// For terminals with a light background, yellows are too pale there.
+    pub fn light() -> Theme {
+        let orange = Color::Rgb(175, 95, 0);
+        Theme {
+            border : fg(Color::Gray),
+            tab_highlight : Style::default().fg(Color::White).bg(Color::Blue),
+            list_highlight : Style::default().add_modifier(Modifier::ITALIC | Modifier::BOLD),
+            editing : fg(Color::Blue),
+            muted : fg(Color::Gray),
+            accent : fg(Color::Blue),
+            methods : MethodStyles {
+                get : fg(Color::Green),
+                post : fg(orange),
+                put : fg(Color::Blue),
+                patch : fg(Color::Magenta),
+                delete : fg(Color::Red),
+                other : fg(Color::Cyan),
+            },
+            status : StatusStyles {
+                informational : fg(Color::Blue),
+                success : fg(Color::Green),
+                redirection : fg(orange),
+                client_error : fg(Color::Red),
+                server_error : bold(Color::Red),
+            },
+            json : JsonStyles {
+                key : fg(Color::Blue),
+                string : fg(Color::Green),
+                number : fg(orange),
+                boolean : fg(Color::Magenta),
+                null : fg(Color::Gray),
+                punctuation : Style::default(),
+            },
+            messages : MessageStyles {
+                info : fg(Color::Green),
+                warning : fg(orange),
+                error : bold(Color::Red),
+            },
+        }
+    }
+
+    /// Bright bold colors and reversed highlights.
+    pub fn high_contrast() -> Theme {
+        Theme {
+            border : fg(Color::White),
+            tab_highlight : Style::default().fg(Color::Black).bg(Color::LightYellow).add_modifier(Modifier::BOLD),
+            list_highlight : Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            editing : bold(Color::LightYellow),
+            muted : fg(Color::White),
+            accent : bold(Color::LightCyan),
+            methods : MethodStyles {
+                get : bold(Color::LightGreen),
+                post : bold(Color::LightYellow),
+                put : bold(Color::LightBlue),
+                patch : bold(Color::LightMagenta),
+                delete : bold(Color::LightRed),
+                other : bold(Color::LightCyan),
+            },
+            status : StatusStyles {
+                informational : bold(Color::LightCyan),
+                success : bold(Color::LightGreen),
+                redirection : bold(Color::LightYellow),
+                client_error : bold(Color::LightRed),
+                server_error : Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+            },
+            json : JsonStyles {
+                key : bold(Color::LightCyan),
+                string : bold(Color::LightGreen),
+                number : bold(Color::LightYellow),
+                boolean : bold(Color::LightMagenta),
+                null : fg(Color::White),
+                punctuation : bold(Color::White),
+            },
+            messages : MessageStyles {
+                info : bold(Color::LightGreen),
+                warning : Style::default().fg(Color::Black).bg(Color::LightYellow).add_modifier(Modifier::BOLD),
+                error : Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+            },
+        }
+    }
+
+    /// A built-in theme, or a custom one from the `[themes]` table of the
+    /// config file. Custom themes override the styles of their `base` theme,
+    /// `dark` by default :
+    ///
+    /// ```toml
+    /// theme = "mine"
+    ///
+    /// [themes.mine]
+    /// base = "light"
+    /// border = "blue"
+    /// tab_highlight = { fg = "black", bg = "#ffaf00", modifiers = ["bold"] }
+    ///
+    /// [themes.mine.methods]
+    /// get = "lightgreen"
+    /// ```
+    ///
+    /// * `name` - the name of the theme.
+    /// * `custom` - the `[themes]` table of the config file.
+    pub fn named(
+        name : &str,
+        custom : &toml::Table) -> Result<Theme, String> {
+
+        Theme::resolve(name, custom, 0)
+    }
+
+    fn resolve(
+        name : &str,
+        custom : &toml::Table,
+        depth : usize) -> Result<Theme, String> {
+
+        let table = match custom.get(name) {
+            Some(toml::Value::Table(table)) => table,
+            Some(_) => return Err(format!("theme {} must be a table", name)),
+            None => return match name {
+                "dark" => Ok(Theme::dark()),
+                "light" => Ok(Theme::light()),
+                "high-contrast" => Ok(Theme::high_contrast()),
+                _ => Err(format!("unknown theme {}, the built-in ones are {}", name, BUILT_IN.join(", "))),
+            },
+        };
+        if depth > custom.len() {
+            return Err(format!("the bases of theme {} form a loop", name));
+        }
+
+        let base = match table.get("base") {
+            Some(toml::Value::String(base)) => base.as_str(),
+            Some(_) => return Err(format!("base of theme {} must be a name", name)),
+            None => "dark",
+        };
+        let mut theme = Theme::resolve(base, custom, depth + 1)?;
+        for (key, value) in table.iter().filter(|(key, _)| *key != "base") {
+            match value {
+                toml::Value::Table(group) if ["methods", "status", "json", "messages"].contains(&key.as_str()) => {
+                    for (item, value) in group.iter() {
+                        let path = format!("{}.{}", key, item);
+                        let style = parse_style(value).map_err(|e| format!("{} : {}", path, e))?;
+                        theme.set(&path, style)?;
+                    }
+                }
+                value => theme.set(key, parse_style(value).map_err(|e| format!("{} : {}", key, e))?)?,
+            }
+        }
+        Ok(theme)
+    }
+
+    /// Replaces a style from its config file key, e.g. `methods.get`.
+    fn set(
+        &mut self,
+        key : &str,
+        style : Style) -> Result<(), String> {
+
+        let target = match key {
+            "border" => &mut self.border,
+            "tab_highlight" => &mut self.tab_highlight,
+            "list_highlight" => &mut self.list_highlight,
+            "editing" => &mut self.editing,
+            "muted" => &mut self.muted,
+            "accent" => &mut self.accent,
+            "methods.get" => &mut self.methods.get,
+            "methods.post" => &mut self.methods.post,
+            "methods.put" => &mut self.methods.put,
+            "methods.patch" => &mut self.methods.patch,
+            "methods.delete" => &mut self.methods.delete,
+            "methods.other" => &mut self.methods.other,
+            "status.informational" => &mut self.status.informational,
+            "status.success" => &mut self.status.success,
+            "status.redirection" => &mut self.status.redirection,
+            "status.client_error" => &mut self.status.client_error,
+            "status.server_error" => &mut self.status.server_error,
+            "json.key" => &mut self.json.key,
+            "json.string" => &mut self.json.string,
+            "json.number" => &mut self.json.number,
+            "json.boolean" => &mut self.json.boolean,
+            "json.null" => &mut self.json.null,
+            "json.punctuation" => &mut self.json.punctuation,
+            "messages.info" => &mut self.messages.info,
+            "messages.warning" => &mut self.messages.warning,
+            "messages.error" => &mut self.messages.error,
+            _ => return Err(format!("unknown style {}", key)),
+        };
+        *target = style;
+        Ok(())
+    }
+
+    /// A block with the border style of the theme.
+    pub fn block(&self) -> Block<'static> {
+        Block::default().border_style(self.border)
+    }
+
+    pub fn method(&self, method : &Methods) -> Style {
+        match method {
+            Methods::GET => self.methods.get,
+            Methods::POST => self.methods.post,
+            Methods::PUT => self.methods.put,
+            Methods::PATCH => self.methods.patch,
+            Methods::DELETE => self.methods.delete,
+            _ => self.methods.other,
+        }
+    }
+
+    pub fn status(&self, status : u32) -> Style {
+        match status {
+            100..=199 => self.status.informational,
+            200..=299 => self.status.success,
+            300..=399 => self.status.redirection,
+            400..=499 => self.status.client_error,
+            500..=599 => self.status.server_error,
+            _ => Style::default(),
+        }
+    }
+}
+
+/// Parses a color : a name such as `red` or `lightblue`, `#rrggbb`, or a
+/// 256 colors palette index.
+pub fn parse_color(input : &str) -> Option<Color> {
+    if let Some(hex) = input.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i : usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    if let Ok(index) = input.parse() {
+        return Some(Color::Indexed(index));
+    }
+    match input.to_ascii_lowercase().replace(['_', '-', ' '], "").as_str() {
+        "reset" | "default" => Some(Color::Reset),
+        "black" => Some(Color::Black),
+        "red" => Some(Color::Red),
+        "green" => Some(Color::Green),
+        "yellow" => Some(Color::Yellow),
+        "blue" => Some(Color::Blue),
+        "magenta" => Some(Color::Magenta),
+        "cyan" => Some(Color::Cyan),
+        "gray" | "grey" => Some(Color::Gray),
+        "darkgray" | "darkgrey" => Some(Color::DarkGray),
+        "lightred" => Some(Color::LightRed),
+        "lightgreen" => Some(Color::LightGreen),
+        "lightyellow" => Some(Color::LightYellow),
+        "lightblue" => Some(Color::LightBlue),
+        "lightmagenta" => Some(Color::LightMagenta),
+        "lightcyan" => Some(Color::LightCyan),
+        "white" => Some(Color::White),
+        _ => None,
+    }
+}
+
+fn parse_modifier(input : &str) -> Option<Modifier> {
+    match input.to_ascii_lowercase().as_str() {
+        "bold" => Some(Modifier::BOLD),
+        "dim" => Some(Modifier::DIM),
+        "italic" => Some(Modifier::ITALIC),
+        "underlined" => Some(Modifier::UNDERLINED),
+        "reversed" => Some(Modifier::REVERSED),
+        "crossed_out" => Some(Modifier::CROSSED_OUT),
+        _ => None,
+    }
+}
+
+/// Parses a style : a foreground color, or a table with optional `fg`, `bg`
+/// and `modifiers`.
+fn parse_style(value : &toml::Value) -> Result<Style, String> {
+    let color = |value : &toml::Value| {
+        let name = value.as_str().ok_or("colors must be strings")?;
+        parse_color(name).ok_or(format!("unknown color {}", name))
+    };
+    match value {
+        toml::Value::String(_) => Ok(Style::default().fg(color(value)?)),
+        toml::Value::Table(table) => {
+            let mut style = Style::default();
+            for (key, value) in table.iter() {
+                match key.as_str() {
+                    "fg" => style = style.fg(color(value)?),
+                    "bg" => style = style.bg(color(value)?),
+                    "modifiers" => {
+                        for modifier in value.as_array().ok_or("modifiers must be a list")?.iter() {
+                            let name = modifier.as_str().unwrap_or_default();
+                            style = style.add_modifier(parse_modifier(name).ok_or(format!("unknown modifier {}", name))?);
+                        }
+                    }
+                    _ => return Err(format!("unknown key {}", key)),
+                }
+            }
+            Ok(style)
+        }
+        _ => Err(String::from("a style is a color or a table")),
+    }
+}
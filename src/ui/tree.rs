@@ -0,0 +1,123 @@
+use std::collections::HashSet;
+
+use crate::database::container::{Collection, Folder, Protocol, Request};
+use crate::database::trash::Kind;
+
+/// An item of the navigation tree.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Node {
+    Collection(i64),
+    Folder(i64),
+    Request(i64),
+}
+
+impl Node {
+
+    /// The kind and id of the item.
+    pub fn item(&self) -> (Kind, i64) {
+        match *self {
+            Node::Collection(id) => (Kind::Collection, id),
+            Node::Folder(id) => (Kind::Folder, id),
+            Node::Request(id) => (Kind::Request, id),
+        }
+    }
+
+    /// The node of an item, None for a workspace.
+    pub fn from_item((kind, id) : (Kind, i64)) -> Option<Node> {
+        match kind {
+            Kind::Workspace => None,
+            Kind::Collection => Some(Node::Collection(id)),
+            Kind::Folder => Some(Node::Folder(id)),
+            Kind::Request => Some(Node::Request(id)),
+        }
+    }
+}
+
+/// What a collection holds, fetched once it is expanded.
+#[derive(Default)]
+pub struct Contents {
+    pub folders : Vec<Folder>,
+    pub requests : Vec<Request>,
+}
+
+/// A line of the navigation tree.
+pub struct Row {
+    pub node : Node,
+    pub depth : usize,
+    pub name : String,
+    pub expanded : bool,
+    /// The collection the row is in.
+    pub id_collection : i64,
+    /// The folder the row is, or is in.
+    pub id_folder : Option<i64>,
+    /// The request of request rows.
+    pub request : Option<Request>,
+}
+
+/// Flattens the collections of a workspace into tree rows, listing the
+/// folders and requests of the collections and folders in `expanded`. In a
+/// folder, sub-folders come before requests.
+///
+/// * `collections` - the collections with their contents when expanded.
+/// * `expanded` - the collections and folders that are open.
+pub fn rows(
+    collections : &[(Collection, Option<Contents>)],
+    expanded : &HashSet<Node>) -> Vec<Row> {
+
+    let mut rows = vec![];
+    for (collection, contents) in collections.iter() {
+        let node = Node::Collection(collection.id);
+        rows.push(Row {
+            node,
+            depth : 0,
+            name : collection.name.clone(),
+            expanded : expanded.contains(&node),
+            id_collection : collection.id,
+            id_folder : None,
+            request : None,
+        });
+        if let (true, Some(contents)) = (expanded.contains(&node), contents) {
+            folder_rows(collection.id, None, 1, contents, expanded, &mut rows);
+        }
+    }
+    rows
+}
+
+/// Rows of the folders and requests in a folder, or at the root of a
+/// collection when `id_folder` is None.
+fn folder_rows(
+    id_collection : i64,
+    id_folder : Option<i64>,
+    depth : usize,
+    contents : &Contents,
+    expanded : &HashSet<Node>,
+    rows : &mut Vec<Row>) {
+
+    for folder in contents.folders.iter().filter(|folder| folder.id_parent == id_folder) {
+        let node = Node::Folder(folder.id);
+        let open = expanded.contains(&node);
+        rows.push(Row {
+            node,
+            depth,
+            name : folder.name.clone(),
+            expanded : open,
+            id_collection,
+            id_folder : Some(folder.id),
+            request : None,
+        });
+        if open {
+            folder_rows(id_collection, Some(folder.id), depth + 1, contents, expanded, rows);
+        }
+    }
+    for request in contents.requests.iter().filter(|request| request.id_folder == id_folder) {
+        rows.push(Row {
+            node : Node::Request(request.id),
+            depth,
+            name : request.name(),
+            expanded : false,
+            id_collection,
+            id_folder,
+            request : Some(request.clone()),
+        });
+    }
+}
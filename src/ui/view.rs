@@ -1,9 +1,18 @@
 use tui::{
     widgets::{ListItem},
+    layout::Rect,
+    style::Style,
     text::{Span, Spans, Text},
 };
 
 use crate::database::{container};
+use crate::http::graphql::TreeRow;
+use crate::http::client::Response;
+use crate::database::example::Example;
+use crate::database::trash::Trashed;
+use crate::ui::theme::Theme;
+use crate::ui::tree::Row;
+use crate::ui::finder::Entry;
 
 pub fn container_to_spans<T>(
     container : Vec<T>,
@@ -21,35 +30,201 @@ T : container::Container
     spans_vec
 }
 
-pub fn container_to_ListItem<T>(
-    container : Vec<T>,
-    ) -> Vec<ListItem<'static>> 
-where
-T : container::Container,
-{
+/// Indents the rows of the collections tree into list items, requests
+/// showing their method.
+pub fn tree_to_list_items(
+    rows : &[Row],
+    theme : &Theme,
+    ) -> Vec<ListItem<'static>> {
 
     let mut list_items = vec![];
-    for item in container.iter() {
-        list_items.push(ListItem::new(item.name()));
+    for row in rows.iter() {
+        let indent = Span::raw("  ".repeat(row.depth));
+        let spans = match &row.request {
+            Some(request) => Spans::from(vec![indent,
+                                         Span::styled(format!("{} ", request.method), theme.method(&request.method)),
+                                         Span::raw(row.name.clone())]),
+            None => Spans::from(vec![indent,
+                                     Span::raw(format!("{} {}", if row.expanded { "-" } else { "+" }, row.name))]),
+        };
+        list_items.push(ListItem::new(spans));
+    }
+
+    list_items
+}
+
+/// Lists the requests matched by the finder with their method, path and url.
+pub fn finder_to_list_items(
+    entries : &[&Entry],
+    theme : &Theme) -> Vec<ListItem<'static>> {
+
+    entries.iter()
+        .map(|entry| ListItem::new(Spans::from(vec![
+            Span::styled(format!("{:<7} ", entry.method.to_string()), theme.method(&entry.method)),
+            Span::raw(entry.path.clone()),
+            Span::styled(format!("  {}", entry.url), theme.muted),
+        ])))
+        .collect()
+}
+
+/// A rectangle in the middle of `area`, for popups.
+///
+/// * `area` - the area to center in.
+/// * `percent_x` - width of the popup, in percent of the area.
+/// * `percent_y` - height of the popup, in percent of the area.
+pub fn centered(
+    area : Rect,
+    percent_x : u16,
+    percent_y : u16) -> Rect {
+
+    let width = area.width * percent_x / 100;
+    let height = area.height * percent_y / 100;
+    Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height)
+}
+
+/// Indents the rows of a GraphQL schema tree into list items.
+pub fn schema_to_list_items(
+    rows : Vec<TreeRow>,
+    ) -> Vec<ListItem<'static>> {
+
+    let mut list_items = vec![];
+    for row in rows.into_iter() {
+        list_items.push(ListItem::new(format!("{}{}", "    ".repeat(row.depth), row.label)));
 
     }
 
     list_items
 }
 
+/// Status line, headers and body of a response as plain text.
+pub fn response_to_text(
+    response : &Response,
+    ) -> String {
 
-pub fn request_to_ListItem<T>(
-    request : Vec<T>,
-    ) -> Vec<ListItem<'static>> 
-where 
-T : container::Protocol,
-{
+    let mut text = format!("{} ({} ms)\n", response.status, response.time.as_millis());
+    for header in response.headers.iter() {
+        text.push_str(header);
+        text.push('\n');
+    }
+    text.push('\n');
+    text.push_str(&String::from_utf8_lossy(&response.body));
+    text
+}
+
+pub fn examples_to_list_items(
+    examples : &[Example],
+    ) -> Vec<ListItem<'static>> {
 
     let mut list_items = vec![];
-    for item in request.iter() {
-        list_items.push(ListItem::new(item.name()));
+    for example in examples.iter() {
+        list_items.push(ListItem::new(format!("{} {}", example.status, example.name)));
 
     }
 
     list_items
 }
+
+/// Lists the items in the trash, their kind muted.
+pub fn trash_to_list_items(
+    trashed : &[Trashed],
+    theme : &Theme) -> Vec<ListItem<'static>> {
+
+    trashed.iter()
+        .map(|item| ListItem::new(Spans::from(vec![
+            Span::styled(format!("{} ", item.kind), theme.muted),
+            Span::raw(item.name.clone()),
+        ])))
+        .collect()
+}
+
+/// Splits a line of JSON into styled tokens. Strings followed by a colon are
+/// keys.
+fn json_line_to_spans(
+    line : &str,
+    theme : &Theme,
+    ) -> Spans<'static> {
+
+    let chars : Vec<char> = line.chars().collect();
+    let mut spans = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let style = match chars[i] {
+            '"' => {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(chars.len());
+                let is_key = chars[i..].iter().find(|c| !c.is_whitespace()) == Some(&':');
+                if is_key { theme.json.key } else { theme.json.string }
+            }
+            '-' | '0'..='9' => {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || ['-', '+', '.'].contains(&chars[i])) {
+                    i += 1;
+                }
+                theme.json.number
+            }
+            c if c.is_alphabetic() => {
+                while i < chars.len() && chars[i].is_alphabetic() {
+                    i += 1;
+                }
+                match chars[start..i].iter().collect::<String>().as_str() {
+                    "true" | "false" => theme.json.boolean,
+                    "null" => theme.json.null,
+                    _ => Style::default(),
+                }
+            }
+            '{' | '}' | '[' | ']' | ',' | ':' => {
+                i += 1;
+                theme.json.punctuation
+            }
+            _ => {
+                while i < chars.len() && chars[i].is_whitespace() {
+                    i += 1;
+                }
+                i = i.max(start + 1);
+                Style::default()
+            }
+        };
+        spans.push(Span::styled(chars[start..i].iter().collect::<String>(), style));
+    }
+    Spans::from(spans)
+}
+
+/// Styles a response text as made by `response_to_text` : the status line
+/// gets the color of its class, headers are muted and JSON bodies are
+/// highlighted. Other texts are kept as they are.
+pub fn highlight_response(
+    text : &str,
+    theme : &Theme,
+    ) -> Vec<Spans<'static>> {
+
+    let mut lines = text.lines();
+    let first = lines.next().unwrap_or_default();
+    let status = first.get(..3).and_then(|code| code.parse::<u32>().ok()).filter(|_| first.get(3..5) == Some(" ("));
+    if status.is_none() {
+        return text.lines().map(|line| Spans::from(Span::raw(line.to_owned()))).collect();
+    }
+
+    let mut spans = vec![Spans::from(Span::styled(first.to_owned(), theme.status(status.unwrap_or_default())))];
+    let mut in_body = false;
+    let mut json = false;
+    for line in lines {
+        if !in_body {
+            if line.is_empty() {
+                in_body = true;
+                let body = text.split_once("\n\n").map(|(_, body)| body).unwrap_or_default().trim_start();
+                json = body.starts_with('{') || body.starts_with('[');
+            }
+            spans.push(Spans::from(Span::styled(line.to_owned(), theme.muted)));
+        }
+        else if json {
+            spans.push(json_line_to_spans(line, theme));
+        }
+        else {
+            spans.push(Spans::from(Span::raw(line.to_owned())));
+        }
+    }
+    spans
+}
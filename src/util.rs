@@ -1,3 +1,5 @@
 pub mod event;
 pub mod dbhandler;
+pub mod time;
+pub mod config;
 
@@ -0,0 +1,196 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::dbhandler;
+use crate::database::storage::{files, Backend, Storage};
+
+pub const USAGE : &str = "[--config <file>] [--database <file>] [--storage sqlite|files|memory] [--user <id>] [--workspace <name>] [--tick-rate <ms>] [--theme <name>]";
+
+/// Startup options. Each one is taken from the first of :
+///
+/// * a command line flag, e.g. `--database <file>`,
+/// * an environment variable, e.g. `TURL_DATABASE`,
+/// * the config file, `$XDG_CONFIG_HOME/turl/config.toml` unless `--config` or
+///   `TURL_CONFIG` name another one,
+/// * the defaults.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Path of the sqlite database, or the directory of the files backend,
+    /// `$XDG_DATA_HOME/turl/database` by default.
+    pub database : String,
+    /// Where the data is kept.
+    pub storage : Backend,
+    /// Id of the User to act as.
+    pub user : i64,
+    /// Name of the Workspace selected at startup, the first one otherwise.
+    pub workspace : Option<String>,
+    /// Interval of the UI ticks, also how often background work is polled.
+    pub tick_rate : Duration,
+    /// Name of the theme, built-in or from `themes`.
+    pub theme : String,
+    /// The `[themes]` table of the config file, see `ui::theme::Theme::named`.
+    pub themes : toml::Table,
+}
+
+/// Options in the order they are listed in `USAGE`, with their flag, their
+/// environment variable and their config file key.
+const OPTIONS : &[(&str, &str, &str)] = &[
+    ("--database", "TURL_DATABASE", "database"),
+    ("--storage", "TURL_STORAGE", "storage"),
+    ("--user", "TURL_USER", "user"),
+    ("--workspace", "TURL_WORKSPACE", "workspace"),
+    ("--tick-rate", "TURL_TICK_RATE", "tick_rate"),
+    ("--theme", "TURL_THEME", "theme"),
+];
+
+/// The `turl` directory of the user's configuration directory.
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join("turl"))
+}
+
+/// The `turl` directory of the user's data directory.
+pub fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share").map(|dir| dir.join("turl"))
+}
+
+/// An XDG base directory, or its default under the home directory.
+fn xdg_dir(
+    variable : &str,
+    default : &str) -> Option<PathBuf> {
+
+    match std::env::var_os(variable).filter(|dir| !dir.is_empty()) {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => Some(PathBuf::from(std::env::var_os("HOME")?).join(default)),
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            database : data_dir()
+                .map(|dir| dir.join("database").to_string_lossy().into_owned())
+                .unwrap_or_else(|| String::from("./.database")),
+            storage : Backend::Sqlite,
+            user : 1,
+            workspace : None,
+            tick_rate : Duration::from_millis(250),
+            theme : String::from("dark"),
+            themes : toml::Table::new(),
+        }
+    }
+}
+
+impl Config {
+
+    /// Reads the options out of the command line arguments, the environment
+    /// and the config file.
+    ///
+    /// * `args` - the arguments after the program name.
+    ///
+    /// Returns the config and the arguments that are not options, such as a
+    /// subcommand and its own flags.
+    pub fn load(args : &[String]) -> Result<(Config, Vec<String>), String> {
+        let mut flags : Vec<(&str, String)> = vec![];
+        let mut config_file = None;
+        let mut rest = vec![];
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let flag = OPTIONS.iter().map(|(flag, _, _)| *flag).find(|flag| flag == arg);
+            match (arg.as_str(), flag) {
+                ("--config", _) => config_file = Some(args.next().ok_or("--config needs a value")?.clone()),
+                (_, Some(flag)) => flags.push((flag, args.next().ok_or(format!("{} needs a value", flag))?.clone())),
+                _ => rest.push(arg.clone()),
+            }
+        }
+
+        let mut config = Config::default();
+
+        // Lowest priority first, each layer overrides the previous one.
+        let explicit = config_file.or_else(|| std::env::var("TURL_CONFIG").ok());
+        let path = match &explicit {
+            Some(path) => Some(PathBuf::from(path)),
+            None => config_dir().map(|dir| dir.join("config.toml")),
+        };
+        if let Some(path) = path {
+            match fs::read_to_string(&path) {
+                Ok(text) => config.apply_toml(&text).map_err(|e| format!("{} : {}", path.display(), e))?,
+                Err(e) if explicit.is_some() => return Err(format!("{} : {}", path.display(), e)),
+                Err(_) => {}
+            }
+        }
+        for (_, variable, key) in OPTIONS.iter() {
+            if let Ok(value) = std::env::var(variable) {
+                config.set(key, &value).map_err(|e| format!("{} : {}", variable, e))?;
+            }
+        }
+        for (flag, value) in flags {
+            let key = OPTIONS.iter().find(|(f, _, _)| *f == flag).map(|(_, _, key)| *key).unwrap_or_default();
+            config.set(key, &value).map_err(|e| format!("{} : {}", flag, e))?;
+        }
+
+        Ok((config, rest))
+    }
+
+    /// Sets an option from its config file key.
+    fn set(
+        &mut self,
+        key : &str,
+        value : &str) -> Result<(), String> {
+
+        match key {
+            "database" => self.database = value.to_owned(),
+            "storage" => self.storage = value.parse().map_err(|_| format!("invalid storage {}, sqlite, files or memory", value))?,
+            "user" => self.user = value.parse().map_err(|_| format!("invalid user id {}", value))?,
+            "workspace" => self.workspace = Some(value.to_owned()),
+            "tick_rate" => {
+                let millis : u64 = value.parse().map_err(|_| format!("invalid tick rate {}", value))?;
+                self.tick_rate = Duration::from_millis(millis.max(1));
+            }
+            "theme" => self.theme = value.to_owned(),
+            _ => return Err(format!("unknown option {}", key)),
+        }
+        Ok(())
+    }
+
+    /// Sets the options of a config file :
+    ///
+    /// ```toml
+    /// database = "/home/me/api/turl.db"
+    /// storage = "sqlite"
+    /// user = 1
+    /// workspace = "api"
+    /// tick_rate = 100
+    /// theme = "light"
+    /// ```
+    fn apply_toml(&mut self, text : &str) -> Result<(), String> {
+        let table : toml::Table = text.parse().map_err(|e : toml::de::Error| e.to_string())?;
+        for (key, value) in table.iter() {
+            if let ("themes", toml::Value::Table(themes)) = (key.as_str(), value) {
+                self.themes = themes.clone();
+                continue;
+            }
+            let value = match value {
+                toml::Value::String(s) => s.clone(),
+                value => value.to_string(),
+            };
+            self.set(key, &value)?;
+        }
+        Ok(())
+    }
+
+    /// Opens the configured storage, creating the directory it goes in if
+    /// needed.
+    pub fn open_storage(&self) -> Result<Box<dyn Storage>, String> {
+        let path = Path::new(&self.database);
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| format!("{} : {}", dir.display(), e))?;
+        }
+        let storage : crate::error::Result<Box<dyn Storage>> = match self.storage {
+            Backend::Sqlite => dbhandler::open(&self.database).map(|db| Box::new(db) as Box<dyn Storage>),
+            Backend::Files => files::open(path).map(|files| Box::new(files) as Box<dyn Storage>),
+            Backend::Memory => dbhandler::in_memory().map(|db| Box::new(db) as Box<dyn Storage>),
+        };
+        storage.map_err(|e| format!("{} : {}", self.database, e))
+    }
+}
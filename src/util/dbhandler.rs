@@ -1,10 +1,299 @@
 use sqlite::*;
+use crate::error::{Error, Result};
 
 pub struct Database {
     pub filename : String,
     pub connection : Connection,
 }
 
-fn connect(mut database : Database) {
-    database.connection = sqlite::open(":memory:").unwrap();
+/// Base tables, created when opening a fresh database file.
+const SCHEMA : &str = "
+    CREATE TABLE IF NOT EXISTS User (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        email TEXT
+    );
+    CREATE TABLE IF NOT EXISTS Workspace (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS User_Workspace (
+        id_user INTEGER REFERENCES User(id),
+        id_workspace INTEGER REFERENCES Workspace(id)
+    );
+    CREATE TABLE IF NOT EXISTS Collection (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        id_workspace INTEGER REFERENCES Workspace(id)
+    );
+    CREATE TABLE IF NOT EXISTS Request (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        method TEXT NOT NULL,
+        url TEXT,
+        params TEXT,
+        body TEXT,
+        id_collection INTEGER REFERENCES Collection(id)
+    );
+";
+
+/// Schema changes applied on top of the base tables, in order. The index of a
+/// migration + 1 is the `user_version` the database is at once it has run.
+///
+/// Columns are only ever appended because rows are read by position.
+const MIGRATIONS : &[&str] = &[
+    "ALTER TABLE Request ADD COLUMN body_mode TEXT NOT NULL DEFAULT 'NONE';
+     ALTER TABLE Request ADD COLUMN headers TEXT;",
+    "ALTER TABLE Request ADD COLUMN kind TEXT NOT NULL DEFAULT 'HTTP';
+     ALTER TABLE Request ADD COLUMN variables TEXT;
+     CREATE TABLE Graphql_Schema (
+         url TEXT PRIMARY KEY,
+         schema TEXT NOT NULL
+     );",
+    "CREATE TABLE Websocket_Message (
+         id INTEGER PRIMARY KEY,
+         id_request INTEGER REFERENCES Request(id),
+         direction TEXT NOT NULL,
+         content TEXT NOT NULL,
+         time INTEGER NOT NULL
+     );",
+    "CREATE TABLE Example (
+         id INTEGER PRIMARY KEY,
+         id_request INTEGER REFERENCES Request(id),
+         name TEXT NOT NULL,
+         status INTEGER NOT NULL,
+         headers TEXT,
+         body TEXT,
+         latency INTEGER NOT NULL DEFAULT 0
+     );",
+    "CREATE TABLE Script (
+         owner TEXT NOT NULL,
+         id_owner INTEGER NOT NULL,
+         stage TEXT NOT NULL,
+         source TEXT NOT NULL,
+         PRIMARY KEY (owner, id_owner, stage)
+     );",
+    "CREATE TABLE Command_History (
+         id INTEGER PRIMARY KEY,
+         command TEXT NOT NULL,
+         time INTEGER NOT NULL
+     );",
+    "CREATE TABLE Folder (
+         id INTEGER PRIMARY KEY,
+         name TEXT NOT NULL,
+         id_collection INTEGER REFERENCES Collection(id),
+         id_parent INTEGER REFERENCES Folder(id)
+     );
+     ALTER TABLE Request ADD COLUMN id_folder INTEGER REFERENCES Folder(id);",
+    "ALTER TABLE Workspace ADD COLUMN deleted INTEGER;
+     ALTER TABLE Collection ADD COLUMN deleted INTEGER;
+     ALTER TABLE Folder ADD COLUMN deleted INTEGER;
+     ALTER TABLE Request ADD COLUMN deleted INTEGER;",
+    "UPDATE Request SET method = 'PATCH' WHERE method = 'MODIFY';",
+    "CREATE TABLE Selection (
+         id_user INTEGER PRIMARY KEY REFERENCES User(id),
+         id_workspace INTEGER,
+         item TEXT,
+         expanded TEXT
+     );",
+    "CREATE TABLE Environment (
+         id INTEGER PRIMARY KEY,
+         id_workspace INTEGER REFERENCES Workspace(id),
+         name TEXT NOT NULL,
+         variables TEXT NOT NULL DEFAULT ''
+     );
+     CREATE TABLE Request_History (
+         id INTEGER PRIMARY KEY,
+         id_request INTEGER REFERENCES Request(id),
+         time INTEGER NOT NULL,
+         method TEXT NOT NULL,
+         url TEXT NOT NULL,
+         status INTEGER NOT NULL,
+         latency INTEGER NOT NULL
+     );",
+    "ALTER TABLE Selection ADD COLUMN tree_offset INTEGER NOT NULL DEFAULT 0;
+     ALTER TABLE Selection ADD COLUMN response_scroll INTEGER NOT NULL DEFAULT 0;",
+];
+
+/// Opens (or creates) the database file and brings its schema up to date.
+///
+/// * `filename` - path to the sqlite file.
+pub fn open(filename : &str) -> Result<Database> {
+    open_at(filename, MIGRATIONS.len())
+}
+
+/// Opens (or creates) a database with its schema as of a version, to load
+/// rows written by that version before upgrading them.
+///
+/// * `filename` - path to the sqlite file, or `:memory:`.
+/// * `version` - the number of migrations to run at most.
+pub fn open_at(
+    filename : &str,
+    version : usize) -> Result<Database> {
+
+    let connection = sqlite::open(filename)?;
+    connection.execute(SCHEMA)?;
+    let db = Database {
+        filename : filename.to_owned(),
+        connection,
+    };
+    migrate(&db, MIGRATIONS, version)?;
+    Ok(db)
+}
+
+/// Opens an empty database held in memory, gone once dropped.
+pub fn in_memory() -> Result<Database> {
+    open(":memory:")
+}
+
+/// Runs the migrations the database has not seen yet.
+///
+/// * `db` - Database to work on.
+pub fn upgrade(db : &Database) -> Result<()> {
+    migrate(db, MIGRATIONS, MIGRATIONS.len())
+}
+
+/// The number of migrations the database has seen.
+///
+/// * `db` - Database to work on.
+pub fn version(db : &Database) -> Result<usize> {
+    user_version(&db.connection)
+}
+
+fn user_version(connection : &Connection) -> Result<usize> {
+    let mut version = 0;
+    connection.iterate("PRAGMA user_version;", |pairs| {
+        version = pairs[0].1.and_then(|v| v.parse().ok()).unwrap_or(0);
+        true
+    })?;
+    Ok(version)
+}
+
+/// Runs the migrations the database has not seen yet, up to a version, each
+/// in a transaction so a failing one leaves the database as it was.
+fn migrate(
+    db : &Database,
+    migrations : &[&str],
+    until : usize) -> Result<()> {
+
+    let version = user_version(&db.connection)?;
+    for (i, migration) in migrations.iter().enumerate().take(until).skip(version) {
+        transaction(db, || {
+            db.connection.execute(format!("{} PRAGMA user_version = {};", migration, i + 1))?;
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+/// Runs `f` in a transaction, rolled back if it fails.
+///
+/// * `db` - Database to work on.
+/// * `f` - the statements to run.
+pub fn transaction<T, F>(
+    db : &Database,
+    f : F) -> Result<T>
+where
+F : FnOnce() -> Result<T>,
+{
+    db.connection.execute("BEGIN;")?;
+    match f() {
+        Ok(value) => {
+            db.connection.execute("COMMIT;")?;
+            Ok(value)
+        }
+        Err(e) => {
+            let _ = db.connection.execute("ROLLBACK;");
+            Err(e)
+        }
+    }
+}
+
+/// Reads a column that must hold an integer.
+///
+/// * `row` - the row read from a cursor.
+/// * `index` - the position of the column.
+/// * `table` - where the row comes from, for the error.
+pub fn integer(
+    row : &[Value],
+    index : usize,
+    table : &str) -> Result<i64> {
+
+    row.get(index)
+        .and_then(|value| value.as_integer())
+        .ok_or_else(|| Error::Corrupt(format!("{}, column {}", table, index)))
+}
+
+/// Reads a column that must hold text.
+///
+/// * `row` - the row read from a cursor.
+/// * `index` - the position of the column.
+/// * `table` - where the row comes from, for the error.
+pub fn text(
+    row : &[Value],
+    index : usize,
+    table : &str) -> Result<String> {
+
+    row.get(index)
+        .and_then(|value| value.as_string())
+        .map(|value| value.to_owned())
+        .ok_or_else(|| Error::Corrupt(format!("{}, column {}", table, index)))
+}
+
+/// Reads a column that may hold text or NULL, read as empty text.
+///
+/// * `row` - the row read from a cursor.
+/// * `index` - the position of the column.
+/// * `table` - where the row comes from, for the error.
+pub fn optional_text(
+    row : &[Value],
+    index : usize,
+    table : &str) -> Result<String> {
+
+    match row.get(index) {
+        Some(Value::Null) => Ok(String::new()),
+        _ => text(row, index, table),
+    }
+}
+
+/// Id of the row inserted last on this connection.
+pub fn last_insert_id(db : &Database) -> Result<i64> {
+    let mut cursor = db.connection.prepare("SELECT last_insert_rowid();")?.into_cursor();
+    Ok(cursor.next()?.and_then(|row| row[0].as_integer()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{example, script, selection, trash};
+    use crate::database::script::{Owner, Stage};
+
+    #[test]
+    fn failing_migration_rolls_back() {
+        let db = in_memory().unwrap();
+        let version = version(&db).unwrap();
+        let mut migrations = MIGRATIONS.to_vec();
+        migrations.push("CREATE TABLE Half (id INTEGER); INSERT INTO Missing VALUES (1);");
+        assert!(migrate(&db, &migrations, migrations.len()).is_err());
+        assert_eq!(super::version(&db).unwrap(), version);
+        assert!(db.connection.execute("SELECT * FROM Half;").is_err());
+        // No transaction was left open.
+        transaction(&db, || Ok(())).unwrap();
+    }
+
+    #[test]
+    fn corrupt_rows_are_reported() {
+        let db = in_memory().unwrap();
+        db.connection.execute("INSERT INTO Example(id_request, name, status) VALUES (1, 'ok', 'two hundred');
+                               INSERT INTO Script VALUES ('REQUEST', 1, 'PRE_REQUEST', X'00');
+                               INSERT INTO Selection(id_user, id_workspace, item, expanded) VALUES (1, 1, NULL, 'folder');
+                               INSERT INTO Workspace(name, deleted) VALUES ('ws', 'yesterday');
+                               INSERT INTO User_Workspace VALUES (1, 1);").unwrap();
+        assert!(matches!(example::get_examples(1, &db), Err(Error::Corrupt(_))));
+        assert!(matches!(script::get_script(Owner::Request, 1, Stage::PreRequest, &db), Err(Error::Corrupt(_))));
+        assert!(matches!(trash::get_trash(1, &db), Err(Error::Corrupt(_))));
+        // NULL and unparsed items are no corruption.
+        let selection = selection::get_selection(1, &db).unwrap().unwrap();
+        assert!(selection.item.is_none() && selection.expanded.is_empty());
+    }
 }
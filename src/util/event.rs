@@ -5,11 +5,14 @@ use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
 
-use termion::event::Key;
+use termion::event::{self as term, Key, MouseEvent};
 use termion::input::TermRead;
 
 pub enum Event<I> {
     Input(I),
+    /// A click, drag or wheel turn, reported once the terminal is wrapped in
+    /// a `termion::input::MouseTerminal`.
+    Mouse(MouseEvent),
     Tick,
 }
 
@@ -35,22 +38,21 @@ impl Default for Config {
 }
 
 impl Events {
-    pub fn new() -> Events {
-        Events::with_config(Config::default())
-    }
-
     pub fn with_config(config: Config) -> Events {
         let (tx, rx) = mpsc::channel();
         let input_handle = {
             let tx = tx.clone();
             thread::spawn(move || {
                 let stdin = io::stdin();
-                for evt in stdin.keys() {
-                    if let Ok(key) = evt {
-                        if let Err(err) = tx.send(Event::Input(key)) {
-                            eprintln!("{}", err);
-                            return;
-                        }
+                for evt in stdin.events() {
+                    let event = match evt {
+                        Ok(term::Event::Key(key)) => Event::Input(key),
+                        Ok(term::Event::Mouse(mouse)) => Event::Mouse(mouse),
+                        _ => continue,
+                    };
+                    if let Err(err) = tx.send(event) {
+                        eprintln!("{}", err);
+                        return;
                     }
                 }
             })
@@ -0,0 +1,19 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Milliseconds since the unix epoch, the form timestamps are stored in.
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Formats a timestamp in milliseconds as a `HH:MM:SS.mmm` UTC clock time.
+pub fn clock(millis : i64) -> String {
+    let seconds = millis.div_euclid(1000);
+    format!("{:02}:{:02}:{:02}.{:03}",
+            seconds.div_euclid(3600).rem_euclid(24),
+            seconds.div_euclid(60).rem_euclid(60),
+            seconds.rem_euclid(60),
+            millis.rem_euclid(1000))
+}
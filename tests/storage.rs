@@ -0,0 +1,160 @@
+//! The same operations run against every storage backend.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use tUrl::database::container::BodyMode;
+use tUrl::database::history::Sent;
+use tUrl::database::script::{Owner, Stage};
+use tUrl::database::selection::Selection;
+use tUrl::database::storage::{files, Storage};
+use tUrl::database::trash::Kind;
+use tUrl::util::dbhandler;
+
+/// An empty directory of the system's temporary directory, for one test.
+fn scratch(name : &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("turl-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Fills a new storage, checking every change reads back.
+fn exercise(db : &dyn Storage) {
+    let user = db.create_user(1, "tester").unwrap();
+    let other_user = db.create_user(2, "other").unwrap();
+    assert_eq!(db.get_user(1).unwrap().map(|user| user.name), Some(String::from("tester")));
+
+    // Workspaces with the same name still get their own ids.
+    let workspace = db.create_workspace(&user, "ws").unwrap();
+    let other = db.create_workspace(&other_user, "ws").unwrap();
+    assert_ne!(workspace, other);
+    assert_eq!(db.get_all_workspaces(1).unwrap().iter().map(|workspace| workspace.id).collect::<Vec<_>>(), vec![workspace]);
+
+    let collection = db.create_collection("api", workspace).unwrap();
+    let folder = db.create_folder("users", collection, None).unwrap();
+    let request = db.create_request("get", collection, Some(folder), "GET", "http://localhost/users").unwrap();
+    db.rename_request(request, "list").unwrap();
+    db.update_request_body(request, &BodyMode::Json, "{}").unwrap();
+    let stored = db.get_request(request).unwrap();
+    assert_eq!((stored.name.as_str(), stored.body.as_str(), stored.id_folder), ("list", "{}", Some(folder)));
+    assert_eq!(stored.body_mode, BodyMode::Json);
+
+    let copy = db.duplicate_request(request, "list copy", collection, None).unwrap();
+    assert_eq!(db.get_all_requests(collection).unwrap().len(), 2);
+
+    // The trash is kept per user.
+    let other_collection = db.create_collection("theirs", other).unwrap();
+    db.trash(Kind::Collection, other_collection).unwrap();
+    db.trash(Kind::Request, copy).unwrap();
+    assert_eq!(db.get_all_requests(collection).unwrap().len(), 1);
+    assert_eq!(db.get_trash(1).unwrap().iter().map(|item| item.id).collect::<Vec<_>>(), vec![copy]);
+    db.restore(Kind::Request, copy).unwrap();
+    assert_eq!(db.get_all_requests(collection).unwrap().len(), 2);
+    db.trash(Kind::Request, copy).unwrap();
+    assert_eq!(db.empty_trash(1).unwrap(), 1);
+    assert_eq!(db.get_trash(2).unwrap().len(), 1);
+
+    let mut variables = HashMap::new();
+    variables.insert(String::from("host"), String::from("localhost"));
+    let environment = db.create_environment(workspace, "local", &variables).unwrap();
+    variables.insert(String::from("token"), String::from("a=b"));
+    db.update_environment(environment, &variables).unwrap();
+    let environments = db.get_environments(workspace).unwrap();
+    assert_eq!(environments.len(), 1);
+    assert_eq!(environments[0].variables, variables);
+
+    let sent = Sent {
+        id_request : request,
+        time : 1,
+        method : String::from("GET"),
+        url : String::from("http://localhost/users"),
+        status : 200,
+        latency : 12,
+    };
+    db.add_sent(&sent).unwrap();
+    assert_eq!(db.get_sent(request).unwrap(), vec![sent]);
+
+    db.add_command("add workspace ws").unwrap();
+    db.add_command("add workspace ws").unwrap();
+    assert_eq!(db.get_commands().unwrap(), vec![String::from("add workspace ws")]);
+
+    let selection = Selection {
+        id_workspace : workspace,
+        item : Some((Kind::Request, request)),
+        expanded : vec![(Kind::Collection, collection), (Kind::Folder, folder)],
+        tree_offset : 3,
+        response_scroll : 40,
+    };
+    db.save_selection(1, &selection).unwrap();
+    assert!(db.get_selection(1).unwrap() == Some(selection));
+
+    // Saving again replaces the row in place, and the last value stays.
+    db.save_schema("http://localhost/graphql", "{}").unwrap();
+    db.save_schema("http://localhost/graphql", r#"{"types" : []}"#).unwrap();
+    db.save_script(Owner::Request, request, Stage::PreRequest, "let a = 1;").unwrap();
+    db.save_script(Owner::Request, request, Stage::PreRequest, "let a = 2;").unwrap();
+    db.save_selection(1, &Selection { tree_offset : 0, ..db.get_selection(1).unwrap().unwrap() }).unwrap();
+}
+
+/// Checks what `exercise` left, after opening the storage again.
+fn check_reopened(db : &dyn Storage) {
+    let workspaces = db.get_all_workspaces(1).unwrap();
+    assert_eq!(workspaces.len(), 1);
+    let collections = db.get_all_collections(workspaces[0].id).unwrap();
+    assert_eq!(collections.len(), 1);
+    let requests = db.get_all_requests(collections[0].id).unwrap();
+    assert_eq!(requests.iter().map(|request| request.name.as_str()).collect::<Vec<_>>(), vec!["list"]);
+    assert_eq!(db.get_environments(workspaces[0].id).unwrap().len(), 1);
+    assert_eq!(db.get_sent(requests[0].id).unwrap().len(), 1);
+    assert_eq!(db.get_schema("http://localhost/graphql").unwrap().as_deref(), Some(r#"{"types" : []}"#));
+    assert_eq!(db.get_script(Owner::Request, requests[0].id, Stage::PreRequest).unwrap(), "let a = 2;");
+    assert_eq!(db.get_selection(1).unwrap().map(|selection| (selection.tree_offset, selection.response_scroll)), Some((0, 40)));
+}
+
+/// Deleting a workspace takes what it holds with it.
+fn check_delete(db : &dyn Storage) {
+    let workspace = db.get_all_workspaces(1).unwrap()[0].id;
+    let collection = db.get_all_collections(workspace).unwrap()[0].id;
+    let request = db.get_all_requests(collection).unwrap()[0].id;
+    db.delete_workspace(workspace).unwrap();
+    assert!(db.get_all_workspaces(1).unwrap().is_empty());
+    assert!(db.get_all_requests(collection).unwrap().is_empty());
+    assert!(db.get_environments(workspace).unwrap().is_empty());
+    assert!(db.get_sent(request).unwrap().is_empty());
+}
+
+#[test]
+fn memory() {
+    let db = dbhandler::in_memory().unwrap();
+    exercise(&db);
+    check_reopened(&db);
+    check_delete(&db);
+}
+
+#[test]
+fn sqlite() {
+    let dir = scratch("sqlite");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("database");
+    exercise(&dbhandler::open(path.to_str().unwrap()).unwrap());
+    let db = dbhandler::open(path.to_str().unwrap()).unwrap();
+    check_reopened(&db);
+    check_delete(&db);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn files() {
+    let dir = scratch("files");
+    exercise(&files::open(&dir).unwrap());
+    assert!(dir.join("Request").read_dir().unwrap().count() == 1);
+    for table in ["Graphql_Schema", "Script", "Selection"].iter() {
+        assert_eq!(dir.join(table).read_dir().unwrap().count(), 1, "{}", table);
+    }
+    let db = files::open(&dir).unwrap();
+    check_reopened(&db);
+    check_delete(&db);
+    assert!(dir.join("Request").read_dir().unwrap().next().is_none());
+    fs::remove_dir_all(&dir).unwrap();
+}